@@ -0,0 +1,94 @@
+//! Adapter enumeration & selection
+
+
+
+/// Information about every adapter available on the given backends, in the same order that
+/// [`select_adapter`] indexes them.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn enumerate_adapters(backends: wgpu::Backends) -> Vec<wgpu::AdapterInfo> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends,
+        ..Default::default()
+    });
+
+    instance
+        .enumerate_adapters(backends)
+        .iter()
+        .map(|adapter| adapter.get_info())
+        .collect()
+}
+
+/// Pick the adapter at `index` (as listed by [`enumerate_adapters`]) from `instance`.
+///
+/// Returns `None` if there is no such adapter, or if it can't present to `surface`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn select_adapter(
+    instance: &wgpu::Instance,
+    backends: wgpu::Backends,
+    index: usize,
+    surface: Option<&wgpu::Surface>,
+) -> Option<wgpu::Adapter> {
+    instance
+        .enumerate_adapters(backends)
+        .into_iter()
+        .nth(index)
+        .filter(|adapter| surface.is_none_or(|surface| adapter.is_surface_supported(surface)))
+}
+
+
+
+/// Whether `adapter` supports every feature in `features`.
+pub fn adapter_supports(adapter: &wgpu::Adapter, features: wgpu::Features) -> bool {
+    adapter.features().contains(features)
+}
+
+/// The subset of `features` that `adapter` doesn't support.
+pub fn missing_features(adapter: &wgpu::Adapter, features: wgpu::Features) -> wgpu::Features {
+    features.difference(adapter.features())
+}
+
+/// Check that a surface configured with `format` can also be viewed as each of `view_formats`
+/// (like `SurfaceConfiguration::view_formats`), e.g. to view an sRGB surface as linear.
+///
+/// Only the sRGB and linear versions of `format` are allowed, and only on adapters with
+/// [`wgpu::DownlevelFlags::SURFACE_VIEW_FORMATS`] (which WebGL lacks).
+pub fn validate_surface_view_formats(
+    adapter: &wgpu::Adapter,
+    format: wgpu::TextureFormat,
+    view_formats: &[wgpu::TextureFormat],
+) -> Result<(), String> {
+    if view_formats.is_empty() {
+        return Ok(());
+    }
+    let flags = adapter.get_downlevel_capabilities().flags;
+    if !flags.contains(wgpu::DownlevelFlags::SURFACE_VIEW_FORMATS) {
+        return Err("adapter doesn't support surface view formats".to_string());
+    }
+    if let Some(view_format) = view_formats
+        .iter()
+        .find(|view_format| view_format.remove_srgb_suffix() != format.remove_srgb_suffix())
+    {
+        return Err(format!("a {format:?} surface can't be viewed as {view_format:?}"));
+    }
+
+    Ok(())
+}
+
+/// A limit requested beyond what an adapter allows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LimitFailure {
+    pub name: &'static str,
+    pub requested: u64,
+    pub allowed: u64,
+}
+
+/// Every limit in `limits` that `adapter` can't satisfy. Empty if a device can be requested
+/// with `limits`.
+pub fn unsupported_limits(adapter: &wgpu::Adapter, limits: &wgpu::Limits) -> Vec<LimitFailure> {
+    let mut failures = Vec::new();
+    limits.check_limits_with_fail_fn(&adapter.limits(), false, |name, requested, allowed| {
+        failures.push(LimitFailure { name, requested, allowed });
+    });
+
+    failures
+}
@@ -0,0 +1,322 @@
+//! Anti-aliasing
+
+
+
+use crate::{FXAA_WGSL, RenderTarget, SamplerConfig, draw_fullscreen_triangle};
+
+
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AntiAliasing {
+    #[default]
+    None,
+    /// Multisampling with this many samples per pixel.
+    Msaa(u32),
+    /// A post-process pass over the finished frame. Cheaper than MSAA, but blurrier.
+    Fxaa,
+}
+
+impl AntiAliasing {
+    /// The number of samples per pixel that pipelines drawing with this mode need.
+    pub const fn sample_count(self) -> u32 {
+        match self {
+            Self::Msaa(count) => count,
+            Self::None | Self::Fxaa => 1,
+        }
+    }
+
+    /// Downgrade this mode to one that `device` can use for targets of `format`.
+    ///
+    /// An unsupported sample count becomes the largest supported one below it. If multisampling
+    /// isn't supported at all, this falls back to [`AntiAliasing::Fxaa`].
+    pub fn supported(
+        self,
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let Self::Msaa(requested) = self else {
+            return self;
+        };
+        if requested <= 1 {
+            return Self::None;
+        }
+
+        // NOTE: Without this feature, only the counts WebGPU guarantees can be used, no matter
+        //       what the adapter itself supports.
+        let features = if device.features()
+            .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+        {
+            adapter.get_texture_format_features(format)
+        } else {
+            format.guaranteed_format_features(device.features())
+        };
+        let supported = features.flags.supported_sample_counts();
+
+        match supported.into_iter().filter(|&count| count > 1 && count <= requested).max() {
+            Some(count) => Self::Msaa(count),
+            None => Self::Fxaa,
+        }
+    }
+}
+
+
+
+/// An offscreen color target, and the pass that anti-aliases it into another target.
+pub struct Fxaa {
+    target: RenderTarget,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    sampler: wgpu::Sampler,
+    pipeline: wgpu::RenderPipeline,
+    format: wgpu::TextureFormat,
+}
+
+impl Fxaa {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, size: [u32; 2]) -> Self {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("fxaa.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(FXAA_WGSL)),
+        });
+        let bind_group_layout = device.create_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                label: Some("FXAA Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            },
+        );
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("FXAA Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("FXAA Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: Some("vs_fullscreen"),
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        let sampler = device.create_sampler(
+            &SamplerConfig::linear().descriptor(Some("FXAA Sampler")),
+        );
+        let (target, bind_group) =
+            Self::create_target(device, &bind_group_layout, &sampler, format, size);
+
+        Self {
+            target,
+            bind_group_layout,
+            bind_group,
+            sampler,
+            pipeline,
+            format,
+        }
+    }
+
+    fn create_target(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        format: wgpu::TextureFormat,
+        size: [u32; 2],
+    ) -> (RenderTarget, wgpu::BindGroup) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("FXAA Input Texture"),
+            size: wgpu::Extent3d {
+                width: size[0].max(1),
+                height: size[1].max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let target = RenderTarget::from_texture(&texture);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("FXAA Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&target.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+
+        (target, bind_group)
+    }
+
+    /// Resize the offscreen target. This should match the size of the final output.
+    pub fn resize(&mut self, device: &wgpu::Device, size: [u32; 2]) {
+        (self.target, self.bind_group) = Self::create_target(
+            device,
+            &self.bind_group_layout,
+            &self.sampler,
+            self.format,
+            size,
+        );
+    }
+
+    /// The target to draw the frame into before [`Fxaa::apply`]ing it.
+    pub fn target(&self) -> &RenderTarget {
+        &self.target
+    }
+
+    /// Draw the anti-aliased contents of [`Fxaa::target`] over all of `output`.
+    pub fn apply(&self, encoder: &mut wgpu::CommandEncoder, output: &RenderTarget) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("FXAA Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &output.view,
+                resolve_target: output.resolve_target.as_ref(),
+                ops: wgpu::Operations {
+                    // NOTE: Every pixel is overwritten, so there's no need to load anything.
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        draw_fullscreen_triangle(&mut render_pass);
+    }
+}
+
+
+
+/// The intermediate targets needed to draw anti-aliased frames into a surface (or any other
+/// single-sampled output).
+pub struct AntiAliaser {
+    mode: AntiAliasing,
+    format: wgpu::TextureFormat,
+    multisampled: Option<wgpu::TextureView>,
+    fxaa: Option<Fxaa>,
+    size: [u32; 2],
+}
+
+impl AntiAliaser {
+    /// Set up `mode` for outputs of `format` and `size`, downgrading it (see
+    /// [`AntiAliasing::supported`]) if needed.
+    pub fn new(
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        size: [u32; 2],
+        mode: AntiAliasing,
+    ) -> Self {
+        let mode = mode.supported(adapter, device, format);
+        let mut antialiaser = Self {
+            mode,
+            format,
+            multisampled: None,
+            fxaa: (mode == AntiAliasing::Fxaa).then(|| Fxaa::new(device, format, size)),
+            size,
+        };
+        antialiaser.resize(device, size);
+
+        antialiaser
+    }
+
+    /// The mode actually in use, which may be lower than what was asked for.
+    pub fn mode(&self) -> AntiAliasing {
+        self.mode
+    }
+
+    /// The sample count that pipelines drawing into [`AntiAliaser::target`] must use.
+    pub fn sample_count(&self) -> u32 {
+        self.mode.sample_count()
+    }
+
+    /// Resize the intermediate targets. This should match the size of the output.
+    pub fn resize(&mut self, device: &wgpu::Device, size: [u32; 2]) {
+        self.size = size;
+        if let Some(fxaa) = &mut self.fxaa {
+            fxaa.resize(device, size);
+        }
+        if let AntiAliasing::Msaa(sample_count) = self.mode {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Multisampled Color Texture"),
+                size: wgpu::Extent3d {
+                    width: size[0].max(1),
+                    height: size[1].max(1),
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count,
+                dimension: wgpu::TextureDimension::D2,
+                format: self.format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            });
+            self.multisampled = Some(texture.create_view(&wgpu::TextureViewDescriptor::default()));
+        }
+    }
+
+    /// The target to draw a frame into, so that it ends up in `output` once
+    /// [`AntiAliaser::resolve`] has been called.
+    pub fn target(&self, output: &wgpu::TextureView) -> RenderTarget {
+        let target = match (&self.multisampled, &self.fxaa) {
+            (Some(multisampled), _) => {
+                RenderTarget::resolving(multisampled.clone(), output.clone())
+            }
+            (None, Some(fxaa)) => RenderTarget::new(fxaa.target().view.clone()),
+            (None, None) => RenderTarget::new(output.clone()),
+        };
+
+        target.with_size(self.size).with_sample_count(self.sample_count())
+    }
+
+    /// Finish anti-aliasing the frame drawn into [`AntiAliaser::target`].
+    ///
+    /// NOTE: Multisampled targets are resolved at the end of each pass that draws into them, so
+    ///       this only does anything for [`AntiAliasing::Fxaa`].
+    pub fn resolve(&self, encoder: &mut wgpu::CommandEncoder, output: &wgpu::TextureView) {
+        if let Some(fxaa) = &self.fxaa {
+            fxaa.apply(encoder, &RenderTarget::new(output.clone()));
+        }
+    }
+}
@@ -0,0 +1,354 @@
+//! Texture atlases
+
+
+
+use crate::{SamplerConfig, Texture, texel_size};
+
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AtlasError {
+    /// The image is larger than the device's maximum texture dimension, so it can never fit.
+    TooLarge { size: [u32; 2], max_dimension: u32 },
+    /// The atlas is already as large as the device allows, and has no room left for the image.
+    Full { size: [u32; 2] },
+    /// The image data isn't tightly packed texels (in the atlas' format) of the given size.
+    InvalidData { expected_len: usize, len: usize },
+}
+
+impl std::fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooLarge { size, max_dimension } => write!(
+                f,
+                "a {}x{} image exceeds the device's maximum texture dimension of {max_dimension}",
+                size[0], size[1],
+            ),
+            Self::Full { size } => write!(
+                f,
+                "no room left for a {}x{} image in an atlas at its maximum size",
+                size[0], size[1],
+            ),
+            Self::InvalidData { expected_len, len } => write!(
+                f,
+                "expected {expected_len} bytes of image data, got {len}",
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AtlasError {}
+
+/// A rectangle allocated within an [`Atlas`], in texels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AtlasRegion {
+    pub pos: [u32; 2],
+    pub size: [u32; 2],
+}
+
+impl AtlasRegion {
+    /// The region's `(min, max)` texture coordinates within an atlas of `atlas_size`.
+    pub fn uv_rect(&self, atlas_size: [u32; 2]) -> ([f32; 2], [f32; 2]) {
+        let [w, h] = atlas_size.map(|d| d as f32);
+        (
+            [self.pos[0] as f32 / w, self.pos[1] as f32 / h],
+            [
+                (self.pos[0] + self.size[0]) as f32 / w,
+                (self.pos[1] + self.size[1]) as f32 / h,
+            ],
+        )
+    }
+}
+
+struct Shelf {
+    y: u32,
+    height: u32,
+    cursor: u32,
+}
+
+/// A growable texture that images are packed into, shelf by shelf. It's RGBA8 (sRGB) unless
+/// created with [`Atlas::with_format`].
+///
+/// The atlas doubles in size whenever it runs out of room, up to the device's
+/// `max_texture_dimension_2d` (or a lower cap set with [`Atlas::set_max_dimension`]). Growing replaces [`Atlas::texture`] (bumping
+/// [`Atlas::generation`]), so anything bound to the old texture, and any texture coordinates
+/// computed from the old size, must be refreshed.
+pub struct Atlas {
+    texture: Texture,
+    format: wgpu::TextureFormat,
+    sampler: SamplerConfig,
+    max_dimension: u32,
+    shelves: Vec<Shelf>,
+    used_area: u64,
+    generation: u32,
+}
+
+impl Atlas {
+    pub fn new(device: &wgpu::Device, size: [u32; 2], sampler: SamplerConfig) -> Self {
+        Self::with_format(device, size, sampler, Texture::FORMAT)
+    }
+
+    /// An atlas of [`Texture::MASK_FORMAT`] alpha masks, e.g. for
+    /// [`Renderer::add_tinted_icon`](crate::Renderer::add_tinted_icon).
+    pub fn masks(device: &wgpu::Device, size: [u32; 2], sampler: SamplerConfig) -> Self {
+        Self::with_format(device, size, sampler, Texture::MASK_FORMAT)
+    }
+
+    pub fn with_format(
+        device: &wgpu::Device,
+        size: [u32; 2],
+        sampler: SamplerConfig,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let max_dimension = device.limits().max_texture_dimension_2d;
+        let size = size.map(|d| d.clamp(1, max_dimension));
+
+        Self {
+            texture: Self::create_texture(device, size, format, &sampler),
+            format,
+            sampler,
+            max_dimension,
+            shelves: Vec::new(),
+            used_area: 0,
+            generation: 0,
+        }
+    }
+
+    fn create_texture(
+        device: &wgpu::Device,
+        size: [u32; 2],
+        format: wgpu::TextureFormat,
+        sampler: &SamplerConfig,
+    ) -> Texture {
+        Texture::empty_with_format(
+            device,
+            size,
+            format,
+            wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+            sampler,
+            Some("Atlas Texture"),
+        )
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        self.texture.size
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    /// The largest width and height the atlas can grow to.
+    pub fn max_dimension(&self) -> u32 {
+        self.max_dimension
+    }
+
+    /// Stop the atlas from growing past `max_dimension` texels in either direction, e.g. to
+    /// bound its memory. The cap is clamped between the atlas' current size and the device's
+    /// `max_texture_dimension_2d`.
+    pub fn set_max_dimension(&mut self, device: &wgpu::Device, max_dimension: u32) {
+        let [width, height] = self.size();
+        self.max_dimension = max_dimension
+            .min(device.limits().max_texture_dimension_2d)
+            .max(width.max(height));
+    }
+
+    /// Incremented every time the atlas grows into a new texture.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// The number of texels allocated so far.
+    pub fn used_area(&self) -> u64 {
+        self.used_area
+    }
+
+    /// The fraction (`0.0..=1.0`) of the atlas' current area that has been allocated.
+    pub fn occupancy(&self) -> f32 {
+        let [w, h] = self.size();
+        self.used_area as f32 / (w as u64 * h as u64) as f32
+    }
+
+    /// Pack an image (of tightly packed texels in the atlas' format) into the atlas, growing it
+    /// if needed.
+    pub fn insert(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        size: [u32; 2],
+        data: &[u8],
+    ) -> Result<AtlasRegion, AtlasError> {
+        let texel_size = texel_size(self.format);
+        let expected_len = size[0] as usize * size[1] as usize * texel_size as usize;
+        if data.len() != expected_len {
+            return Err(AtlasError::InvalidData { expected_len, len: data.len() });
+        }
+
+        let region = self.allocate(device, queue, size)?;
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.texture.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: region.pos[0], y: region.pos[1], z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            data,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(size[0] * texel_size),
+                rows_per_image: Some(size[1]),
+            },
+            wgpu::Extent3d {
+                width: size[0],
+                height: size[1],
+                depth_or_array_layers: 1,
+            },
+        );
+
+        Ok(region)
+    }
+
+    /// Reserve space for an image of the given size, growing the atlas if needed.
+    pub fn allocate(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        size: [u32; 2],
+    ) -> Result<AtlasRegion, AtlasError> {
+        if size[0] > self.max_dimension || size[1] > self.max_dimension {
+            return Err(AtlasError::TooLarge { size, max_dimension: self.max_dimension });
+        }
+
+        loop {
+            if let Some(region) = self.try_allocate(size) {
+                self.used_area += size[0] as u64 * size[1] as u64;
+                return Ok(region);
+            }
+            if !self.grow(device, queue) {
+                return Err(AtlasError::Full { size });
+            }
+        }
+    }
+
+    fn try_allocate(&mut self, size: [u32; 2]) -> Option<AtlasRegion> {
+        let [atlas_width, atlas_height] = self.size();
+
+        // Use the shortest shelf that fits, to waste as little height as possible.
+        let best = self.shelves
+            .iter_mut()
+            .filter(|shelf| shelf.height >= size[1] && atlas_width - shelf.cursor >= size[0])
+            .min_by_key(|shelf| shelf.height);
+        if let Some(shelf) = best {
+            let region = AtlasRegion { pos: [shelf.cursor, shelf.y], size };
+            shelf.cursor += size[0];
+            return Some(region);
+        }
+
+        let next_y = self.shelves.last().map_or(0, |shelf| shelf.y + shelf.height);
+        if size[0] > atlas_width || size[1] > atlas_height - next_y {
+            return None;
+        }
+        self.shelves.push(Shelf { y: next_y, height: size[1], cursor: size[0] });
+
+        Some(AtlasRegion { pos: [0, next_y], size })
+    }
+
+    /// Double the atlas' size (up to the maximum dimension), keeping its contents. Returns
+    /// `false` if it's already as large as it can be.
+    fn grow(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> bool {
+        let old_size = self.size();
+        let new_size = old_size.map(|d| (d * 2).min(self.max_dimension));
+        if new_size == old_size {
+            return false;
+        }
+
+        let texture = Self::create_texture(device, new_size, self.format, &self.sampler);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Atlas Grow Encoder"),
+        });
+        encoder.copy_texture_to_texture(
+            self.texture.texture.as_image_copy(),
+            texture.texture.as_image_copy(),
+            wgpu::Extent3d {
+                width: old_size[0],
+                height: old_size[1],
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        self.texture = texture;
+        self.generation += 1;
+
+        true
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn atlases_grow_until_their_max_dimension() {
+        let Some((_adapter, device, queue)) = testing::gpu() else {
+            return;
+        };
+        let mut atlas = Atlas::new(&device, [4, 4], SamplerConfig::default());
+        atlas.set_max_dimension(&device, 8);
+        assert_eq!(atlas.max_dimension(), 8);
+
+        for expected in [[0, 0], [4, 0], [0, 4], [4, 4]] {
+            let region = atlas.allocate(&device, &queue, [4, 4]).unwrap();
+            assert_eq!(region.pos, expected);
+        }
+        assert_eq!(atlas.size(), [8, 8]);
+        assert_eq!(atlas.generation(), 1);
+        assert_eq!(atlas.used_area(), 64);
+        assert_eq!(atlas.occupancy(), 1.0);
+
+        assert_eq!(
+            atlas.allocate(&device, &queue, [1, 1]),
+            Err(AtlasError::Full { size: [1, 1] }),
+        );
+        assert_eq!(
+            atlas.allocate(&device, &queue, [9, 1]),
+            Err(AtlasError::TooLarge { size: [9, 1], max_dimension: 8 }),
+        );
+        assert_eq!(atlas.size(), [8, 8]);
+    }
+
+    #[test]
+    fn max_dimensions_stay_within_the_device_limit() {
+        let Some((_adapter, device, queue)) = testing::gpu() else {
+            return;
+        };
+        let limit = device.limits().max_texture_dimension_2d;
+        let mut atlas = Atlas::new(&device, [4, 4], SamplerConfig::default());
+        assert_eq!(atlas.max_dimension(), limit);
+
+        atlas.set_max_dimension(&device, u32::MAX);
+        assert_eq!(atlas.max_dimension(), limit);
+        atlas.set_max_dimension(&device, 1);
+        assert_eq!(atlas.max_dimension(), 4);
+
+        atlas.set_max_dimension(&device, u32::MAX);
+        assert_eq!(
+            atlas.allocate(&device, &queue, [limit + 1, 1]),
+            Err(AtlasError::TooLarge { size: [limit + 1, 1], max_dimension: limit }),
+        );
+        assert_eq!(
+            atlas.insert(&device, &queue, [2, 2], &[0; 4]),
+            Err(AtlasError::InvalidData { expected_len: 16, len: 4 }),
+        );
+        assert_eq!(atlas.occupancy(), 0.0);
+    }
+}
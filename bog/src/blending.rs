@@ -0,0 +1,77 @@
+//! Blend modes
+
+
+
+use crate::{SOLID_WGSL, Shader, ShaderDescriptor, ShaderError, Vertex};
+
+
+
+/// Common ways of combining what a pipeline draws with what's already in the target.
+///
+/// Blending is part of a pipeline, not of a draw, so each mode needs a [`Shader`] of its own
+/// (see [`ShaderDescriptor::blend_mode`]). Draw commands then pick a mode by picking a shader.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlendMode {
+    /// Overwrite the target, alpha included.
+    Replace,
+    /// Draw over the target according to the (straight) alpha of what's drawn.
+    #[default]
+    Alpha,
+    /// Add what's drawn to the target (`src + dst`, ignoring alpha), for glows and light that
+    /// accumulates.
+    ///
+    /// NOTE: Sums saturate at `1.0` in normalized formats, so overlapping lights quickly clip to
+    ///       white; draw into a float (HDR) target to keep them. The sum is also taken in linear
+    ///       space on sRGB targets (which is correct for light), so it looks brighter than adding
+    ///       the sRGB-encoded values would.
+    Additive,
+}
+
+impl BlendMode {
+    pub const fn blend_state(self) -> wgpu::BlendState {
+        match self {
+            Self::Replace => wgpu::BlendState::REPLACE,
+            Self::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+            Self::Additive => {
+                let add = wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                };
+                wgpu::BlendState { color: add, alpha: add }
+            }
+        }
+    }
+}
+
+impl Shader {
+    /// Like [`Shader::solid`], but blending with `mode` instead of [`BlendMode::Alpha`].
+    pub fn solid_blended(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        mode: BlendMode,
+    ) -> Result<Self, ShaderError> {
+        Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(SOLID_WGSL)),
+            label: Some("solid.wgsl"),
+            pipeline_label: Some("Solid Pipeline"),
+            pipeline_layout_label: Some("Solid Pipeline Layout"),
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &[Vertex::desc()],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            blend_mode: Some(mode),
+            primitive: ShaderDescriptor::UNCULLED_PRIMITIVE,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..ShaderDescriptor::default().multisample
+            },
+            ..Default::default()
+        })
+    }
+}
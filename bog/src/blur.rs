@@ -0,0 +1,161 @@
+//! Blurring textures
+
+
+
+use wgpu::util::DeviceExt as _;
+
+use crate::{BLUR_WGSL, ComputeShader, ComputeShaderDescriptor, SamplerConfig, Texture};
+
+
+
+/// The format of the textures made by [`blur_texture`].
+///
+/// NOTE: sRGB formats can't be written to from compute shaders, so the blurred texels are stored
+///       as linear values.
+pub const BLUR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+const WORKGROUP_SIZE: u32 = 8;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+struct BlurParams {
+    direction: [i32; 2],
+    radius: i32,
+    _padding: i32,
+}
+
+/// Box blur `input` by `radius` texels in each direction, into a new texture of
+/// [`BLUR_FORMAT`] (sampled linearly) for drawing behind frosted panels.
+///
+/// The blur is separable, so it's done in two compute passes (horizontal, then vertical) that
+/// each read `2 * radius + 1` texels per output texel. `input` must have been created with
+/// [`wgpu::TextureUsages::TEXTURE_BINDING`].
+///
+/// Fails if the device doesn't support compute shaders or storage textures.
+pub fn blur_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    input: &Texture,
+    radius: u32,
+) -> Result<Texture, String> {
+    if device.limits().max_storage_textures_per_shader_stage == 0 {
+        return Err("device doesn't support storage textures".to_string());
+    }
+    if !input.texture.usage().contains(wgpu::TextureUsages::TEXTURE_BINDING) {
+        return Err("the texture to blur can't be bound to a shader".to_string());
+    }
+
+    let shader = ComputeShader::new(device, ComputeShaderDescriptor {
+        source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(BLUR_WGSL)),
+        label: Some("blur.wgsl"),
+        pipeline_label: Some("Blur Pipeline"),
+        pipeline_layout_label: Some("Blur Pipeline Layout"),
+        bind_group_layout_label: Some("Blur Bind Group Layout"),
+        entry_point: Some("cs_main"),
+        bind_group_layout_entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::StorageTexture {
+                    access: wgpu::StorageTextureAccess::WriteOnly,
+                    format: BLUR_FORMAT,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+        ],
+    }).map_err(|error| error.to_string())?;
+
+    let usage = wgpu::TextureUsages::TEXTURE_BINDING
+        | wgpu::TextureUsages::STORAGE_BINDING
+        | wgpu::TextureUsages::COPY_SRC;
+    let sampler = SamplerConfig::linear();
+    let horizontal = Texture::empty_with_format(
+        device,
+        input.size,
+        BLUR_FORMAT,
+        usage,
+        &sampler,
+        Some("Horizontal Blur Texture"),
+    );
+    let output = Texture::empty_with_format(
+        device,
+        input.size,
+        BLUR_FORMAT,
+        usage,
+        &sampler,
+        Some("Blurred Texture"),
+    );
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Blur Encoder"),
+    });
+    for (direction, from, to) in [
+        ([1, 0], &input.view, &horizontal.view),
+        ([0, 1], &horizontal.view, &output.view),
+    ] {
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Blur Params Buffer"),
+            contents: bytemuck::bytes_of(&BlurParams {
+                direction,
+                radius: radius as i32,
+                _padding: 0,
+            }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Blur Bind Group"),
+            layout: &shader.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(from),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(to),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: params.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Blur Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&shader.pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups(
+            input.size[0].div_ceil(WORKGROUP_SIZE),
+            input.size[1].div_ceil(WORKGROUP_SIZE),
+            1,
+        );
+    }
+    queue.submit(std::iter::once(encoder.finish()));
+
+    Ok(output)
+}
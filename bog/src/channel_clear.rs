@@ -0,0 +1,146 @@
+//! Channel clears
+
+
+
+use wgpu::util::DeviceExt as _;
+
+use crate::{
+    CHANNEL_CLEAR_WGSL, Color, RenderTarget, Shader, ShaderDescriptor, ShaderError,
+    draw_fullscreen_triangle,
+};
+
+
+
+/// Clears only some channels of a target, like the color of one whose alpha is an accumulated
+/// mask (or the other way around).
+///
+/// A [`wgpu::LoadOp::Clear`] always clears every channel, and write masks don't apply to it.
+/// So unless the channels are [`wgpu::ColorWrites::ALL`] (when this is a plain clear), the
+/// target is loaded and a fullscreen triangle is drawn over it with the channels as its write
+/// mask, which replaces them and keeps the rest.
+///
+/// NOTE: Later passes have to load the target too, since clearing it would undo the kept
+///       channels.
+pub struct ChannelClear {
+    shader: Shader,
+    channels: wgpu::ColorWrites,
+    color: Color,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl ChannelClear {
+    /// Create a clear of `channels` to (those components of) `color`, for targets of `format`
+    /// with `sample_count` samples per pixel.
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        channels: wgpu::ColorWrites,
+        color: Color,
+    ) -> Result<Self, ShaderError> {
+        let bind_group_layout = device.create_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                label: Some("Channel Clear Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            },
+        );
+        let shader = Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(CHANNEL_CLEAR_WGSL)),
+            label: Some("channel_clear.wgsl"),
+            pipeline_label: Some("Channel Clear Pipeline"),
+            pipeline_layout_label: Some("Channel Clear Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            vertex_entry_point: Some("vs_fullscreen"),
+            vertex_buffers: &[],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format,
+                // NOTE: Blending would mix the old value into the cleared channels.
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: channels,
+            })],
+            primitive: ShaderDescriptor::UNCULLED_PRIMITIVE,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..ShaderDescriptor::default().multisample
+            },
+            ..Default::default()
+        })?;
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Channel Clear Buffer"),
+            contents: bytemuck::bytes_of(&[color.r, color.g, color.b, color.a]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Channel Clear Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Ok(Self {
+            shader,
+            channels,
+            color,
+            buffer,
+            bind_group,
+        })
+    }
+
+    /// The channels this clears. They're part of the pipeline, so changing them takes a new
+    /// [`ChannelClear`].
+    pub fn channels(&self) -> wgpu::ColorWrites {
+        self.channels
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn set_color(&mut self, queue: &wgpu::Queue, color: Color) {
+        self.color = color;
+        let color = [color.r, color.g, color.b, color.a];
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&color));
+    }
+
+    /// Record a pass clearing the channels of `target`, keeping the others as they were.
+    pub fn clear(&self, encoder: &mut wgpu::CommandEncoder, target: &RenderTarget) {
+        let load = if self.channels == wgpu::ColorWrites::ALL {
+            wgpu::LoadOp::Clear(self.color.into())
+        } else {
+            wgpu::LoadOp::Load
+        };
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Channel Clear Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target.view,
+                resolve_target: target.resolve_target.as_ref(),
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        if self.channels != wgpu::ColorWrites::ALL && !self.channels.is_empty() {
+            render_pass.set_pipeline(&self.shader.pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            draw_fullscreen_triangle(&mut render_pass);
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Anti-aliased circles
+
+
+
+use wgpu::util::DeviceExt as _;
+
+use crate::{CIRCLE_WGSL, CoordinateSpace, Quad, Renderer, Shader, ShaderDescriptor, ShaderError};
+
+
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CircleVertex {
+    pub(crate) pos: [f32; 2],
+    pub(crate) center: [f32; 2],
+    /// The offset from the circle's center, in radii.
+    local: [f32; 2],
+    pub(crate) color: [f32; 3],
+    /// The inner radius of an outline, in radii, or `0.0` for a filled circle.
+    pub(crate) inner: f32,
+}
+
+impl crate::Positioned for CircleVertex {
+    fn pos(&self) -> [f32; 2] {
+        self.pos
+    }
+
+    fn pos_mut(&mut self) -> &mut [f32; 2] {
+        &mut self.pos
+    }
+
+    fn convert(&mut self, from: CoordinateSpace, to: CoordinateSpace) {
+        self.pos = to.from_ndc(from.to_ndc(self.pos));
+        self.center = to.from_ndc(from.to_ndc(self.center));
+    }
+}
+
+impl CircleVertex {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<CircleVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 9]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32,
+                },
+            ]
+        }
+    }
+}
+
+impl Renderer {
+    /// Add a filled, anti-aliased circle. These are collected into [`Batch::circles`].
+    ///
+    /// [`Batch::circles`]: crate::Batch::circles
+    pub fn add_circle(&mut self, center: [f32; 2], radius: f32, color: [f32; 3]) {
+        self.push_circle(center, radius, 0.0, color);
+    }
+
+    /// Add an anti-aliased ring of `thickness` whose outer edge is at `radius`.
+    pub fn add_circle_outline(
+        &mut self,
+        center: [f32; 2],
+        radius: f32,
+        thickness: f32,
+        color: [f32; 3],
+    ) {
+        if thickness <= 0.0 {
+            return;
+        }
+        self.push_circle(center, radius, (1.0 - thickness / radius).max(0.0), color);
+    }
+
+    fn push_circle(&mut self, center: [f32; 2], radius: f32, inner: f32, color: [f32; 3]) {
+        if radius <= f32::EPSILON {
+            return;
+        }
+
+        let [x, y] = center;
+        self.circles.add(
+            [[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0], [1.0, 1.0]].map(|local| CircleVertex {
+                pos: [x + local[0] * radius, y + local[1] * radius],
+                center,
+                local,
+                color,
+                inner,
+            }),
+            Quad::indices_u32(),
+        );
+    }
+}
+
+
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+struct FeatherParams {
+    viewport: [f32; 2],
+    width: f32,
+    _padding: f32,
+}
+
+/// The uniform that anti-aliased primitives (like [`Renderer::add_circle`]) are feathered with.
+///
+/// The feather width is given in physical pixels and scaled by the viewport size in the shader,
+/// so edges stay equally crisp however far the content is zoomed.
+pub struct EdgeFeather {
+    params: FeatherParams,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl EdgeFeather {
+    /// Feather edges by `width` pixels in a target of `viewport` (physical) pixels.
+    pub fn new(device: &wgpu::Device, viewport: [u32; 2], width: f32) -> Self {
+        let params = FeatherParams {
+            viewport: viewport.map(|d| d.max(1) as f32),
+            width: width.max(0.0),
+            _padding: 0.0,
+        };
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Edge Feather Buffer"),
+            contents: bytemuck::bytes_of(&params),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Edge Feather Bind Group"),
+            // NOTE: Layouts with identical entries are interchangeable.
+            layout: &Self::bind_group_layout(device),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            params,
+            buffer,
+            bind_group,
+        }
+    }
+
+    pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Edge Feather Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        })
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    pub fn width(&self) -> f32 {
+        self.params.width
+    }
+
+    /// Call this whenever the target is resized.
+    pub fn set_viewport(&mut self, queue: &wgpu::Queue, viewport: [u32; 2]) {
+        self.params.viewport = viewport.map(|d| d.max(1) as f32);
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&self.params));
+    }
+
+    pub fn set_width(&mut self, queue: &wgpu::Queue, width: f32) {
+        self.params.width = width.max(0.0);
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&self.params));
+    }
+}
+
+impl Shader {
+    /// The built-in pipeline for drawing [`Batch::circles`] into a target of `format` with
+    /// `sample_count` samples per pixel. Bind an [`EdgeFeather`] at `@group(0)`.
+    ///
+    /// [`Batch::circles`]: crate::Batch::circles
+    pub fn circle(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Result<Self, ShaderError> {
+        Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(CIRCLE_WGSL)),
+            label: Some("circle.wgsl"),
+            pipeline_label: Some("Circle Pipeline"),
+            pipeline_layout_label: Some("Circle Pipeline Layout"),
+            bind_group_layouts: &[&EdgeFeather::bind_group_layout(device)],
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &[CircleVertex::desc()],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            primitive: ShaderDescriptor::UNCULLED_PRIMITIVE,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..ShaderDescriptor::default().multisample
+            },
+            ..Default::default()
+        })
+    }
+}
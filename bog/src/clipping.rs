@@ -0,0 +1,142 @@
+//! Per-quad clipping
+
+
+
+use crate::{
+    CLIPPED_WGSL, CoordinateSpace, Quad, Renderer, Shader, ShaderDescriptor, ShaderError,
+};
+
+
+
+/// A vertex of a quad that's clipped to a rect in the fragment shader, so differently clipped
+/// quads can still share a draw call.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ClippedVertex {
+    pos: [f32; 2],
+    color: [f32; 3],
+    /// Two opposite corners of the clip rect.
+    clip: [[f32; 2]; 2],
+    /// `1.0` to fade out over a pixel at the clip rect's edges, or `0.0` to cut them off.
+    smooth: f32,
+}
+
+impl crate::Positioned for ClippedVertex {
+    fn pos(&self) -> [f32; 2] {
+        self.pos
+    }
+
+    fn pos_mut(&mut self) -> &mut [f32; 2] {
+        &mut self.pos
+    }
+
+    fn convert(&mut self, from: CoordinateSpace, to: CoordinateSpace) {
+        self.pos = to.from_ndc(from.to_ndc(self.pos));
+        self.clip = self.clip.map(|corner| to.from_ndc(from.to_ndc(corner)));
+    }
+}
+
+impl ClippedVertex {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<ClippedVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 7]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 9]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32,
+                },
+            ]
+        }
+    }
+}
+
+impl Renderer {
+    /// Add `quad`, with everything outside of `clip` cut off. These are collected into
+    /// [`Batch::clipped`], which is drawn in a single call however many clip rects it uses.
+    ///
+    /// [`Batch::clipped`]: crate::Batch::clipped
+    pub fn add_clipped_quad(&mut self, quad: &Quad, color: [f32; 3], clip: &Quad) {
+        self.push_clipped_quad(quad, color, clip, 0.0);
+    }
+
+    /// Like [`Renderer::add_clipped_quad`], but anti-aliasing the clip rect's edges.
+    pub fn add_clipped_quad_smooth(&mut self, quad: &Quad, color: [f32; 3], clip: &Quad) {
+        self.push_clipped_quad(quad, color, clip, 1.0);
+    }
+
+    fn push_clipped_quad(&mut self, quad: &Quad, color: [f32; 3], clip: &Quad, smooth: f32) {
+        if quad.is_degenerate() || clip.is_degenerate() || !quad.overlaps(clip) {
+            return;
+        }
+
+        let (min, max) = clip.min_max();
+        let [x, y] = quad.pos;
+        let [w, h] = quad.size;
+        self.clipped.add(
+            [[x, y], [x + w, y], [x, y + h], [x + w, y + h]].map(|pos| ClippedVertex {
+                pos,
+                color,
+                clip: [min, max],
+                smooth,
+            }),
+            Quad::indices_u32(),
+        );
+    }
+}
+
+impl Shader {
+    /// The built-in pipeline for drawing [`Batch::clipped`] into a target of `format` with
+    /// `sample_count` samples per pixel.
+    ///
+    /// [`Batch::clipped`]: crate::Batch::clipped
+    pub fn clipped(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Result<Self, ShaderError> {
+        Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(CLIPPED_WGSL)),
+            label: Some("clipped.wgsl"),
+            pipeline_label: Some("Clipped Pipeline"),
+            pipeline_layout_label: Some("Clipped Pipeline Layout"),
+            bind_group_layouts: &[],
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &[ClippedVertex::desc()],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            primitive: ShaderDescriptor::UNCULLED_PRIMITIVE,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..ShaderDescriptor::default().multisample
+            },
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,143 @@
+//! Colors
+//!
+//! Every color passed to a [`Renderer`](crate::Renderer) is in linear space, and so is a render
+//! pass's clear color. When the target is sRGB (like [`Texture::FORMAT`](crate::Texture::FORMAT)
+//! and the surface formats the example prefers), both are encoded by the GPU as they're written,
+//! so a clear and a fill given the same values come out identical. Use [`Color`] for both, so
+//! that values picked from a design tool (which are sRGB-encoded) are decoded just once.
+
+
+
+use crate::{Quad, QuadBuilder, Renderer};
+
+
+
+/// A linear-space color with straight (not premultiplied) alpha.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const BLACK: Self = Self::rgb(0.0, 0.0, 0.0);
+    pub const WHITE: Self = Self::rgb(1.0, 1.0, 1.0);
+    pub const TRANSPARENT: Self = Self::rgba(0.0, 0.0, 0.0, 0.0);
+
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Self::rgba(r, g, b, 1.0)
+    }
+
+    pub const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Decode sRGB-encoded components (like the ones shown by most color pickers).
+    pub fn from_srgb(r: f32, g: f32, b: f32) -> Self {
+        Self::rgb(srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b))
+    }
+
+    /// Decode an sRGB hex color like `0x3366cc`.
+    pub fn from_srgb_hex(hex: u32) -> Self {
+        let [_, r, g, b] = hex.to_be_bytes();
+        Self::from_srgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
+    }
+
+    /// Decode a packed `0xRRGGBBAA` color, like the ones in config files. The color channels are
+    /// sRGB-encoded, but alpha is linear (as it always is).
+    pub fn from_srgba_packed(packed: u32) -> Self {
+        let [r, g, b, a] = packed.to_be_bytes();
+        Self {
+            a: a as f32 / 255.0,
+            ..Self::from_srgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
+        }
+    }
+
+    /// The color as passed to the `add_*` functions of a [`Renderer`](crate::Renderer).
+    pub const fn to_array(self) -> [f32; 3] {
+        [self.r, self.g, self.b]
+    }
+
+    /// The sRGB-encoded components, e.g. for targets that aren't sRGB themselves.
+    pub fn to_srgb(self) -> [f32; 3] {
+        [self.r, self.g, self.b].map(linear_to_srgb)
+    }
+}
+
+impl From<[f32; 3]> for Color {
+    fn from([r, g, b]: [f32; 3]) -> Self {
+        Self::rgb(r, g, b)
+    }
+}
+
+impl From<Color> for [f32; 3] {
+    fn from(color: Color) -> Self {
+        color.to_array()
+    }
+}
+
+/// The clear color that matches primitives drawn in `color`.
+impl From<Color> for wgpu::Color {
+    fn from(color: Color) -> Self {
+        Self {
+            r: color.r as f64,
+            g: color.g as f64,
+            b: color.b as f64,
+            a: color.a as f64,
+        }
+    }
+}
+
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+}
+
+
+
+impl Renderer {
+    /// Like [`Renderer::add_quad`], but with a packed `0xRRGGBBAA` color (see
+    /// [`Color::from_srgba_packed`]). The alpha is drawn as the quad's opacity.
+    pub fn add_quad_packed(&mut self, quad: &Quad, color: u32) {
+        let color = Color::from_srgba_packed(color);
+        self.add(QuadBuilder::from(*quad).with_color(color.to_array()).with_opacity(color.a));
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(color: Color, expected: Color) {
+        let [a, b] = [color, expected].map(|c| [c.r, c.g, c.b, c.a]);
+        assert!(a.iter().zip(&b).all(|(a, b)| (a - b).abs() < 1e-4), "{color:?} != {expected:?}");
+    }
+
+    #[test]
+    fn packed_colors_are_rgba_from_the_top_byte() {
+        assert_eq!(Color::from_srgba_packed(0xFF0000FF), Color::rgb(1.0, 0.0, 0.0));
+        assert_eq!(Color::from_srgba_packed(0x00FF00FF), Color::rgb(0.0, 1.0, 0.0));
+        assert_eq!(Color::from_srgba_packed(0x0000FFFF), Color::rgb(0.0, 0.0, 1.0));
+        assert_eq!(Color::from_srgba_packed(0x00000000), Color::TRANSPARENT);
+        assert_eq!(Color::from_srgba_packed(0xFFFFFF00), Color::rgba(1.0, 1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn packed_colors_decode_srgb_but_not_alpha() {
+        // NOTE: sRGB 0x80 (about 0.502) is about 0.216 in linear space.
+        let gray = 0.2159;
+        assert_close(Color::from_srgba_packed(0x80808080), Color::rgba(gray, gray, gray, 0.502));
+        assert_close(Color::from_srgba_packed(0x3366CCFF), Color::from_srgb_hex(0x3366CC));
+        assert_close(
+            Color::from_srgba_packed(0x336699CC),
+            Color { a: 0.8, ..Color::from_srgb(0.2, 0.4, 0.6) },
+        );
+    }
+}
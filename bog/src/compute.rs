@@ -0,0 +1,107 @@
+//! Compute shaders
+
+
+
+use crate::ShaderError;
+
+
+
+pub struct ComputeShader {
+    pub pipeline: wgpu::ComputePipeline,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl ComputeShader {
+    pub fn new(
+        device: &wgpu::Device,
+        desc: ComputeShaderDescriptor,
+    ) -> Result<Self, ShaderError> {
+        if device.limits().max_compute_workgroups_per_dimension == 0 {
+            return Err(ShaderError::ComputeUnsupported);
+        }
+        crate::validate_entry_points(&desc.source, &[
+            (desc.entry_point, wgpu::naga::ShaderStage::Compute),
+        ])?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: desc.label,
+            source: desc.source,
+        });
+        let bind_group_layout = device.create_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                label: desc.bind_group_layout_label,
+                entries: desc.bind_group_layout_entries,
+            },
+        );
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: desc.pipeline_layout_label,
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: desc.pipeline_label,
+            layout: Some(&pipeline_layout),
+            module: &module,
+            entry_point: desc.entry_point,
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        Ok(Self {
+            pipeline,
+            bind_group_layout,
+        })
+    }
+}
+
+pub struct ComputeShaderDescriptor<'a> {
+    pub source: wgpu::ShaderSource<'a>,
+    pub label: Option<&'a str>,
+    pub pipeline_label: Option<&'a str>,
+    pub pipeline_layout_label: Option<&'a str>,
+    pub bind_group_layout_label: Option<&'a str>,
+    pub entry_point: Option<&'a str>,
+    /// The layout of the shader's (only) bind group, at `@group(0)`.
+    pub bind_group_layout_entries: &'a [wgpu::BindGroupLayoutEntry],
+}
+
+impl<'a> Default for ComputeShaderDescriptor<'a> {
+    fn default() -> Self {
+        Self {
+            source: wgpu::ShaderSource::Dummy(std::marker::PhantomData),
+            label: None,
+            pipeline_label: None,
+            pipeline_layout_label: None,
+            bind_group_layout_label: None,
+            entry_point: None,
+            bind_group_layout_entries: &[],
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BLUR_WGSL, testing};
+
+    #[test]
+    fn entry_points_must_be_compute_functions() {
+        let Some((_adapter, device, _queue)) = testing::gpu() else {
+            return;
+        };
+        let desc = |entry_point| ComputeShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(BLUR_WGSL)),
+            entry_point: Some(entry_point),
+            ..Default::default()
+        };
+        let error = ComputeShader::new(&device, desc("cs_mian")).err();
+
+        if device.limits().max_compute_workgroups_per_dimension == 0 {
+            assert_eq!(error, Some(ShaderError::ComputeUnsupported));
+        } else {
+            assert_eq!(error, Some(ShaderError::MissingEntryPoint("cs_mian".to_string())));
+        }
+    }
+}
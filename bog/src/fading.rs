@@ -0,0 +1,264 @@
+//! Fading targets
+
+
+
+use wgpu::util::DeviceExt as _;
+
+use crate::{
+    Color, DrawCommand, FADE_WGSL, RenderTarget, SamplerConfig, Shader, ShaderDescriptor,
+    ShaderError, Texture, draw_fullscreen_triangle, render_to,
+};
+
+
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+struct FadeParams {
+    color: [f32; 3],
+    fade: f32,
+}
+
+/// A persistent offscreen target that fades towards a background color each frame instead of
+/// being cleared, for trails and motion blur.
+///
+/// Each [`FadingTarget::render`] first blends the background over the previous contents with
+/// an opacity of [`FadingTarget::fade`], then draws the new commands on top of that. A fade of
+/// `0.0` keeps everything that was ever drawn, and a fade of `1.0` clears the target every frame.
+///
+/// NOTE: With 8-bit formats, small fades may never reach the background exactly, since each
+///       step towards it can round back to the same value.
+pub struct FadingTarget {
+    texture: Texture,
+    target: RenderTarget,
+    shader: Shader,
+    params: FadeParams,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    format: wgpu::TextureFormat,
+    needs_clear: bool,
+}
+
+impl FadingTarget {
+    /// Create a target of `format` and `size`, fading towards (and initially cleared to) the
+    /// opaque `background`.
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        size: [u32; 2],
+        background: Color,
+        fade: f32,
+    ) -> Result<Self, ShaderError> {
+        let bind_group_layout = Self::bind_group_layout(device);
+        let shader = Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(FADE_WGSL)),
+            label: Some("fade.wgsl"),
+            pipeline_label: Some("Fade Pipeline"),
+            pipeline_layout_label: Some("Fade Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            vertex_entry_point: Some("vs_fullscreen"),
+            vertex_buffers: &[],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            primitive: ShaderDescriptor::UNCULLED_PRIMITIVE,
+            ..Default::default()
+        })?;
+        let params = FadeParams {
+            color: [background.r, background.g, background.b],
+            fade: fade.clamp(0.0, 1.0),
+        };
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Fade Buffer"),
+            contents: bytemuck::bytes_of(&params),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Fade Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        let texture = Self::create_texture(device, format, size);
+        let target = RenderTarget::from_texture(&texture.texture);
+
+        Ok(Self {
+            texture,
+            target,
+            shader,
+            params,
+            buffer,
+            bind_group,
+            format,
+            needs_clear: true,
+        })
+    }
+
+    fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Fade Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        })
+    }
+
+    fn create_texture(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        size: [u32; 2],
+    ) -> Texture {
+        Texture::empty_with_format(
+            device,
+            size.map(|d| d.max(1)),
+            format,
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            &SamplerConfig::linear(),
+            Some("Fading Target"),
+        )
+    }
+
+    /// The accumulated contents, for drawing (or copying) into the final target.
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn target(&self) -> &RenderTarget {
+        &self.target
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    pub fn fade(&self) -> f32 {
+        self.params.fade
+    }
+
+    /// Set how much of the way towards the background the contents move each frame, from
+    /// `0.0` to `1.0`.
+    pub fn set_fade(&mut self, queue: &wgpu::Queue, fade: f32) {
+        self.params.fade = fade.clamp(0.0, 1.0);
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&self.params));
+    }
+
+    pub fn set_background(&mut self, queue: &wgpu::Queue, background: Color) {
+        self.params.color = [background.r, background.g, background.b];
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&self.params));
+    }
+
+    /// Clear the target to the background on the next render, dropping any trails.
+    pub fn reset(&mut self) {
+        self.needs_clear = true;
+    }
+
+    /// Recreate the target at `size`. Its previous contents are lost.
+    pub fn resize(&mut self, device: &wgpu::Device, size: [u32; 2]) {
+        self.texture = Self::create_texture(device, self.format, size);
+        self.target = RenderTarget::from_texture(&self.texture.texture);
+        self.needs_clear = true;
+    }
+
+    /// Record the fade pass, followed by a pass drawing `commands` over the faded contents.
+    pub fn render(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        commands: &[DrawCommand],
+    ) -> Result<(), String> {
+        let load = if self.needs_clear {
+            let [r, g, b] = self.params.color.map(f64::from);
+            wgpu::LoadOp::Clear(wgpu::Color { r, g, b, a: 1.0 })
+        } else {
+            wgpu::LoadOp::Load
+        };
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Fade Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.target.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            render_pass.set_pipeline(&self.shader.pipeline);
+            render_pass.set_bind_group(0, &self.bind_group, &[]);
+            draw_fullscreen_triangle(&mut render_pass);
+        }
+        self.needs_clear = false;
+
+        render_to(encoder, std::slice::from_ref(&self.target), None, commands)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Quad, Renderer, read_pixel, testing};
+
+    #[test]
+    fn frames_fade_towards_the_background() {
+        let Some((_adapter, device, queue)) = testing::gpu() else {
+            return;
+        };
+        let black = Color::rgb(0.0, 0.0, 0.0);
+        let shader = Shader::solid(&device, Texture::FORMAT, 1).unwrap();
+        let mut white = Renderer::start();
+        white.add_quad(&Quad::new([-1.0, -1.0], [2.0, 2.0]), [1.0, 1.0, 1.0]);
+        let white = white.finish(&device);
+
+        let fade = |fading: &mut FadingTarget, commands: &[DrawCommand]| {
+            let mut encoder = device.create_command_encoder(&Default::default());
+            fading.render(&mut encoder, commands).unwrap();
+            queue.submit(std::iter::once(encoder.finish()));
+            read_pixel(&device, &queue, &fading.texture().texture, 4, 4).unwrap()
+        };
+        let draw_white = [DrawCommand {
+            shader: &shader,
+            mesh: &white.solid,
+            bind_groups: &[],
+            instances: None,
+            scissor: None,
+        }];
+
+        let mut fading = FadingTarget::new(&device, Texture::FORMAT, [8, 8], black, 0.5).unwrap();
+        assert_eq!(fade(&mut fading, &[]), [0, 0, 0, 255]);
+        assert_eq!(fade(&mut fading, &draw_white), [255; 4]);
+
+        // NOTE: Each fade halves the (linear) brightness, so after three it's an eighth, which
+        //       is about 0.39 (or 99 out of 255) in sRGB.
+        let mut last = 255;
+        for _ in 0..3 {
+            let [r, g, b, a] = fade(&mut fading, &[]);
+            assert!(r < last && r == g && g == b && a == 255, "{:?}", [r, g, b, a]);
+            last = r;
+        }
+        assert!(last.abs_diff(99) <= 3, "{last}");
+
+        fading.set_fade(&queue, 0.0);
+        assert_eq!(fade(&mut fading, &[])[0], last);
+        fading.set_fade(&queue, 1.0);
+        assert_eq!(fade(&mut fading, &[]), [0, 0, 0, 255]);
+    }
+}
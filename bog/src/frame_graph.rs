@@ -0,0 +1,161 @@
+//! Frame graphs
+
+
+
+use crate::RenderTarget;
+
+
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FrameGraphError {
+    /// Two passes were registered with the same name.
+    DuplicatePass(String),
+    /// A pass depends on a pass that was never registered.
+    UnknownDependency { pass: String, dependency: String },
+    /// These passes depend on each other (directly or not), so none of them can run first.
+    Cycle(Vec<String>),
+}
+
+impl std::fmt::Display for FrameGraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DuplicatePass(name) => write!(f, "pass '{name}' was added more than once"),
+            Self::UnknownDependency { pass, dependency } => {
+                write!(f, "pass '{pass}' depends on unknown pass '{dependency}'")
+            }
+            Self::Cycle(passes) => {
+                write!(f, "passes have cyclic dependencies: {}", passes.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for FrameGraphError {}
+
+/// The named targets of a [`FrameGraph`], as seen by each of its passes.
+pub struct FrameTargets<'a> {
+    targets: Vec<(String, &'a RenderTarget)>,
+}
+
+impl<'a> FrameTargets<'a> {
+    pub fn get(&self, name: &str) -> Option<&'a RenderTarget> {
+        self.targets
+            .iter()
+            .find_map(|(target_name, target)| (target_name == name).then_some(*target))
+    }
+}
+
+type RecordPass<'a> = Box<dyn FnOnce(&mut wgpu::CommandEncoder, &FrameTargets<'a>) + 'a>;
+
+struct Pass<'a> {
+    name: String,
+    dependencies: Vec<String>,
+    record: RecordPass<'a>,
+}
+
+/// A set of passes that are recorded into one encoder, each after all of its dependencies.
+///
+/// Passes with no ordering between them run in the order they were added. There's no resource
+/// tracking: targets are just named so passes can find them, and it's up to each pass to load or
+/// clear what it uses.
+pub struct FrameGraph<'a> {
+    targets: FrameTargets<'a>,
+    passes: Vec<Pass<'a>>,
+}
+
+impl<'a> FrameGraph<'a> {
+    pub fn new() -> Self {
+        Self {
+            targets: FrameTargets { targets: Vec::new() },
+            passes: Vec::new(),
+        }
+    }
+
+    /// Make `target` available to every pass as `name`, replacing any previous target of that
+    /// name.
+    pub fn add_target(&mut self, name: impl Into<String>, target: &'a RenderTarget) {
+        let name = name.into();
+        self.targets.targets.retain(|(existing, _)| *existing != name);
+        self.targets.targets.push((name, target));
+    }
+
+    /// Add a pass that runs after every pass named in `dependencies`.
+    pub fn add_pass(
+        &mut self,
+        name: impl Into<String>,
+        dependencies: &[&str],
+        record: impl FnOnce(&mut wgpu::CommandEncoder, &FrameTargets<'a>) + 'a,
+    ) {
+        self.passes.push(Pass {
+            name: name.into(),
+            dependencies: dependencies.iter().map(|name| name.to_string()).collect(),
+            record: Box::new(record),
+        });
+    }
+
+    /// The order that [`FrameGraph::execute`] would run the passes in.
+    pub fn order(&self) -> Result<Vec<&str>, FrameGraphError> {
+        Ok(self.sorted()?.into_iter().map(|i| self.passes[i].name.as_str()).collect())
+    }
+
+    /// Record every pass into `encoder`. Nothing is recorded if the passes can't be ordered.
+    pub fn execute(self, encoder: &mut wgpu::CommandEncoder) -> Result<(), FrameGraphError> {
+        let order = self.sorted()?;
+        let mut passes: Vec<Option<Pass>> = self.passes.into_iter().map(Some).collect();
+        for i in order {
+            if let Some(pass) = passes[i].take() {
+                (pass.record)(encoder, &self.targets);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Indices into `passes`, dependencies first.
+    fn sorted(&self) -> Result<Vec<usize>, FrameGraphError> {
+        let index_of = |name: &str| self.passes.iter().position(|pass| pass.name == name);
+
+        let mut dependencies = Vec::with_capacity(self.passes.len());
+        for (i, pass) in self.passes.iter().enumerate() {
+            if index_of(&pass.name) != Some(i) {
+                return Err(FrameGraphError::DuplicatePass(pass.name.clone()));
+            }
+            let mut indices = Vec::with_capacity(pass.dependencies.len());
+            for dependency in &pass.dependencies {
+                indices.push(index_of(dependency).ok_or_else(|| {
+                    FrameGraphError::UnknownDependency {
+                        pass: pass.name.clone(),
+                        dependency: dependency.clone(),
+                    }
+                })?);
+            }
+            dependencies.push(indices);
+        }
+
+        let mut done = vec![false; self.passes.len()];
+        let mut order = Vec::with_capacity(self.passes.len());
+        while order.len() < self.passes.len() {
+            // NOTE: Always taking the first ready pass keeps unrelated passes in insertion order.
+            let next = (0..self.passes.len())
+                .find(|&i| !done[i] && dependencies[i].iter().all(|&dep| done[dep]));
+            let Some(next) = next else {
+                return Err(FrameGraphError::Cycle(
+                    (0..self.passes.len())
+                        .filter(|&i| !done[i])
+                        .map(|i| self.passes[i].name.clone())
+                        .collect(),
+                ));
+            };
+            done[next] = true;
+            order.push(next);
+        }
+
+        Ok(order)
+    }
+}
+
+impl Default for FrameGraph<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
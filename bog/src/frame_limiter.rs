@@ -0,0 +1,142 @@
+//! Frame limiting
+
+
+
+/// Bounds how many submissions the GPU can fall behind by.
+///
+/// Submitting through [`FrameLimiter::submit`] blocks until the oldest tracked submission has
+/// finished whenever `max_in_flight` of them are still pending, so work can't pile up in the
+/// queue when rendering outpaces the GPU.
+pub struct FrameLimiter {
+    max_in_flight: usize,
+    in_flight: std::collections::VecDeque<wgpu::SubmissionIndex>,
+}
+
+impl FrameLimiter {
+    /// Allow up to `max_in_flight` (at least `1`) submissions to be pending at once.
+    pub fn new(max_in_flight: usize) -> Self {
+        Self {
+            max_in_flight: max_in_flight.max(1),
+            in_flight: std::collections::VecDeque::new(),
+        }
+    }
+
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+
+    pub fn set_max_in_flight(&mut self, max_in_flight: usize) {
+        self.max_in_flight = max_in_flight.max(1);
+    }
+
+    /// Submit `command_buffers` to `queue`, first waiting for older submissions if too many are
+    /// still in flight.
+    pub fn submit<I: IntoIterator<Item = wgpu::CommandBuffer>>(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        command_buffers: I,
+    ) -> wgpu::SubmissionIndex {
+        while self.in_flight.len() >= self.max_in_flight {
+            let Some(oldest) = self.in_flight.pop_front() else {
+                break;
+            };
+            device.poll(wgpu::Maintain::WaitForSubmissionIndex(oldest));
+        }
+
+        let index = queue.submit(command_buffers);
+        self.in_flight.push_back(index.clone());
+
+        index
+    }
+}
+
+
+
+/// Caps how many frames are drawn per second, for surfaces that don't already (like ones
+/// presenting without vsync), so rendering doesn't burn power on frames nobody sees.
+pub struct FrameRateCap {
+    frame_time: Option<std::time::Duration>,
+    next_frame: Option<std::time::Instant>,
+}
+
+impl FrameRateCap {
+    /// NOTE: Sleeping is only accurate to about this much, so the rest of each wait is spun.
+    const SPIN_TIME: std::time::Duration = std::time::Duration::from_millis(1);
+
+    /// Cap frames to `max_fps` per second, or not at all if it's `None`.
+    pub fn new(max_fps: Option<f32>) -> Self {
+        let mut cap = Self {
+            frame_time: None,
+            next_frame: None,
+        };
+        cap.set_max_fps(max_fps);
+
+        cap
+    }
+
+    pub fn max_fps(&self) -> Option<f32> {
+        self.frame_time.map(|frame_time| 1.0 / frame_time.as_secs_f32())
+    }
+
+    /// Values that aren't positive (or finite) turn the cap off, as do ones so small or large
+    /// that the time between frames doesn't fit in a [`Duration`](std::time::Duration) (or
+    /// rounds to nothing).
+    pub fn set_max_fps(&mut self, max_fps: Option<f32>) {
+        self.frame_time = max_fps
+            .filter(|fps| fps.is_finite() && *fps > 0.0)
+            .and_then(|fps| std::time::Duration::try_from_secs_f32(1.0 / fps).ok())
+            .filter(|frame_time| !frame_time.is_zero());
+        self.next_frame = None;
+    }
+
+    /// Block until the next frame is due, returning how long that took. Call this once per
+    /// frame, before drawing it.
+    ///
+    /// Frames are spaced from when each wait ends, so a late frame pushes back the ones after
+    /// it rather than letting them catch up with a burst.
+    pub fn wait(&mut self) -> std::time::Duration {
+        let Some(frame_time) = self.frame_time else {
+            return std::time::Duration::ZERO;
+        };
+
+        let start = std::time::Instant::now();
+        if let Some(next_frame) = self.next_frame {
+            if let Some(sleep) = next_frame.checked_duration_since(start + Self::SPIN_TIME) {
+                std::thread::sleep(sleep);
+            }
+            while std::time::Instant::now() < next_frame {
+                std::hint::spin_loop();
+            }
+        }
+
+        let now = std::time::Instant::now();
+        self.next_frame = Some(now + frame_time);
+
+        now - start
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unrepresentable_rates_turn_the_cap_off() {
+        for max_fps in [1e-20, 1e-40, -60.0, 0.0, f32::NAN, f32::INFINITY, f32::MAX] {
+            assert_eq!(FrameRateCap::new(Some(max_fps)).max_fps(), None, "{max_fps}");
+        }
+    }
+
+    #[test]
+    fn representable_rates_are_kept() {
+        let max_fps = FrameRateCap::new(Some(60.0)).max_fps().unwrap();
+        assert!((max_fps - 60.0).abs() < 1e-3);
+
+        let mut cap = FrameRateCap::new(Some(60.0));
+        cap.set_max_fps(None);
+        assert_eq!(cap.max_fps(), None);
+    }
+}
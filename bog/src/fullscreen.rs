@@ -0,0 +1,24 @@
+//! Fullscreen passes
+
+
+
+use crate::FULLSCREEN_TRIANGLE_WGSL;
+
+
+
+/// Draw the single triangle that covers the whole target, for a pipeline whose vertex stage is
+/// `vs_fullscreen` (from [`FULLSCREEN_TRIANGLE_WGSL`]). The pipeline shouldn't have any vertex
+/// buffers, and shouldn't cull clockwise triangles (like with
+/// [`ShaderDescriptor::UNCULLED_PRIMITIVE`](crate::ShaderDescriptor::UNCULLED_PRIMITIVE)).
+pub fn draw_fullscreen_triangle(render_pass: &mut wgpu::RenderPass<'_>) {
+    render_pass.draw(0..3, 0..1);
+}
+
+/// The source of a fullscreen pass, made by putting [`FULLSCREEN_TRIANGLE_WGSL`] in front of
+/// `fragment_source`, which can take a `FullscreenVertex` as its input.
+pub fn fullscreen_wgsl(fragment_source: &str) -> String {
+    let mut source = String::with_capacity(FULLSCREEN_TRIANGLE_WGSL.len() + fragment_source.len());
+    source.push_str(FULLSCREEN_TRIANGLE_WGSL);
+    source.push_str(fragment_source);
+    source
+}
@@ -0,0 +1,47 @@
+//! `glam` interop
+
+
+
+/// Convert one of the plain arrays that `bog` uses into its `glam` equivalent.
+///
+/// NOTE: `glam` itself already implements `From` between `Vec2` and `[f32; 2]`, but the orphan
+///       rules don't allow `bog` to add `From` impls between `glam` types and arrays (neither of
+///       which it owns), so matrices go through these traits instead.
+pub trait IntoGlam {
+    type Glam;
+
+    fn into_glam(self) -> Self::Glam;
+}
+
+/// The reverse of [`IntoGlam`].
+pub trait FromGlam: IntoGlam {
+    fn from_glam(value: Self::Glam) -> Self;
+}
+
+impl IntoGlam for [[f32; 4]; 4] {
+    type Glam = glam::Mat4;
+
+    fn into_glam(self) -> glam::Mat4 {
+        glam::Mat4::from_cols_array_2d(&self)
+    }
+}
+
+impl FromGlam for [[f32; 4]; 4] {
+    fn from_glam(value: glam::Mat4) -> Self {
+        value.to_cols_array_2d()
+    }
+}
+
+impl IntoGlam for [f32; 2] {
+    type Glam = glam::Vec2;
+
+    fn into_glam(self) -> glam::Vec2 {
+        glam::Vec2::from_array(self)
+    }
+}
+
+impl FromGlam for [f32; 2] {
+    fn from_glam(value: glam::Vec2) -> Self {
+        value.to_array()
+    }
+}
@@ -0,0 +1,684 @@
+//! Gradient fills
+
+
+
+use wgpu::util::DeviceExt as _;
+
+use crate::{
+    BACKGROUND_GRADIENT_WGSL, Color, Geometry, IndexWidth, LINEAR_GRADIENT_WGSL, Mesh, Quad,
+    RADIAL_GRADIENT_WGSL, RenderTarget, Renderer, ScissorRect, Shader, ShaderDescriptor,
+    ShaderError, draw_fullscreen_triangle,
+};
+
+
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GradientVertex {
+    pos: [f32; 2],
+    /// The offset from the gradient's center, in radii.
+    local: [f32; 2],
+    inner_color: [f32; 3],
+    outer_color: [f32; 3],
+    flags: u32,
+}
+
+impl crate::Positioned for GradientVertex {
+    fn pos(&self) -> [f32; 2] {
+        self.pos
+    }
+
+    fn pos_mut(&mut self) -> &mut [f32; 2] {
+        &mut self.pos
+    }
+}
+
+impl GradientVertex {
+    const GAMMA_CORRECT: u32 = 1;
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<GradientVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 7]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 10]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Uint32,
+                },
+            ]
+        }
+    }
+}
+
+/// A circular gradient from `inner_color` at its center to `outer_color` at `radius`, drawn as
+/// the square around that circle (with `outer_color` filling its corners).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RadialGradient {
+    pub center: [f32; 2],
+    pub radius: f32,
+    pub inner_color: [f32; 3],
+    pub outer_color: [f32; 3],
+    /// Interpolate between the colors in linear space, rather than between their sRGB-encoded
+    /// values (which is what most design tools do).
+    pub gamma_correct: bool,
+}
+
+impl RadialGradient {
+    pub const fn new(
+        center: [f32; 2],
+        radius: f32,
+        inner_color: [f32; 3],
+        outer_color: [f32; 3],
+    ) -> Self {
+        Self {
+            center,
+            radius,
+            inner_color,
+            outer_color,
+            gamma_correct: false,
+        }
+    }
+
+    pub const fn gamma_correct(mut self) -> Self {
+        self.gamma_correct = true;
+        self
+    }
+
+    pub fn push(&self, vertices: &mut Vec<GradientVertex>, indices: &mut Vec<u32>) {
+        let [x, y] = self.center;
+        let r = self.radius;
+        let flags = if self.gamma_correct { GradientVertex::GAMMA_CORRECT } else { 0 };
+
+        let base = vertices.len() as u32;
+        indices.extend(Quad::indices_u32().map(|i| base + i));
+        vertices.extend([[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0], [1.0, 1.0]].map(|local| {
+            GradientVertex {
+                pos: [x + local[0] * r, y + local[1] * r],
+                local,
+                inner_color: self.inner_color,
+                outer_color: self.outer_color,
+                flags,
+            }
+        }));
+    }
+}
+
+impl Renderer {
+    /// Add a radial gradient. These are collected into [`Batch::radial_gradients`].
+    ///
+    /// [`Batch::radial_gradients`]: crate::Batch::radial_gradients
+    pub fn add_radial_gradient(
+        &mut self,
+        center: [f32; 2],
+        radius: f32,
+        inner_color: [f32; 3],
+        outer_color: [f32; 3],
+    ) {
+        self.add_gradient(&RadialGradient::new(center, radius, inner_color, outer_color));
+    }
+
+    pub fn add_gradient(&mut self, gradient: &RadialGradient) {
+        if gradient.radius <= f32::EPSILON {
+            return;
+        }
+
+        let geometry = &mut self.radial_gradients;
+        gradient.push(&mut geometry.vertices, &mut geometry.indices);
+    }
+}
+
+/// The most color stops a single linear gradient can have.
+pub const MAX_GRADIENT_STOPS: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GradientError {
+    /// A gradient needs at least one color stop.
+    NoStops,
+    /// More than [`MAX_GRADIENT_STOPS`] stops were given.
+    TooManyStops(usize),
+}
+
+impl std::fmt::Display for GradientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GradientError::NoStops => write!(f, "gradient has no color stops"),
+            GradientError::TooManyStops(count) => write!(
+                f,
+                "gradient has {count} color stops, but at most {MAX_GRADIENT_STOPS} are supported",
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GradientError {}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LinearGradientVertex {
+    pos: [f32; 2],
+    /// The offset from the gradient quad's center.
+    local: [f32; 2],
+    /// The gradient's direction, scaled so that `dot(local, axis)` spans `-0.5..=0.5`.
+    axis: [f32; 2],
+}
+
+impl crate::Positioned for LinearGradientVertex {
+    fn pos(&self) -> [f32; 2] {
+        self.pos
+    }
+
+    fn pos_mut(&mut self) -> &mut [f32; 2] {
+        &mut self.pos
+    }
+}
+
+impl LinearGradientVertex {
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<LinearGradientVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+            ]
+        }
+    }
+}
+
+/// The color stops of a linear gradient, as laid out in its uniform.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct GradientStops {
+    positions: [f32; MAX_GRADIENT_STOPS],
+    /// Padded to `vec4`s, as uniform arrays need.
+    colors: [[f32; 4]; MAX_GRADIENT_STOPS],
+    count: u32,
+    _padding: [u32; 3],
+}
+
+impl GradientStops {
+    /// `stops`, sorted by position. There must be at most [`MAX_GRADIENT_STOPS`] of them.
+    fn new(stops: &[(f32, [f32; 3])]) -> Self {
+        let mut sorted = [(0.0, [0.0; 3]); MAX_GRADIENT_STOPS];
+        sorted[..stops.len()].copy_from_slice(stops);
+        sorted[..stops.len()].sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Self {
+            positions: sorted.map(|(pos, _)| pos),
+            colors: sorted.map(|(_, [r, g, b])| [r, g, b, 1.0]),
+            count: stops.len() as u32,
+            _padding: [0; 3],
+        }
+    }
+
+    fn bind_group(&self, device: &wgpu::Device) -> wgpu::BindGroup {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Linear Gradient Stops Buffer"),
+            contents: bytemuck::bytes_of(self),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Linear Gradient Bind Group"),
+            // NOTE: Layouts with identical entries are interchangeable, so this works with any
+            //       pipeline built by `Shader::linear_gradient`.
+            layout: &stops_bind_group_layout(device),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        })
+    }
+}
+
+fn stops_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Linear Gradient Bind Group Layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    })
+}
+
+/// The gradients drawn with one set of color stops.
+#[derive(Clone)]
+pub(crate) struct LinearGradientGeometry {
+    pub(crate) stops: GradientStops,
+    pub(crate) geometry: Geometry<LinearGradientVertex>,
+}
+
+/// The geometry in `list` drawn with `stops`, which is added if they haven't been used yet.
+pub(crate) fn stops_geometry_for(
+    list: &mut Vec<LinearGradientGeometry>,
+    stops: GradientStops,
+) -> &mut Geometry<LinearGradientVertex> {
+    let index = match list.iter().position(|gradient| gradient.stops == stops) {
+        Some(index) => index,
+        None => {
+            list.push(LinearGradientGeometry {
+                stops,
+                geometry: Geometry::new(),
+            });
+            list.len() - 1
+        }
+    };
+
+    &mut list[index].geometry
+}
+
+/// A mesh made of [`LinearGradientVertex`]es, and the uniform holding its color stops (bound at
+/// `@group(0)`).
+pub struct LinearGradientMesh {
+    pub mesh: Mesh,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl LinearGradientMesh {
+    /// Upload each non-empty geometry in `list` with its stops' bind group.
+    pub(crate) fn upload_all(
+        list: &[LinearGradientGeometry],
+        device: &wgpu::Device,
+        width: Option<IndexWidth>,
+    ) -> Vec<Self> {
+        list.iter()
+            .filter_map(|gradient| Some(Self {
+                mesh: gradient.geometry.upload(device, width, "Linear Gradient")?,
+                bind_group: gradient.stops.bind_group(device),
+            }))
+            .collect()
+    }
+}
+
+impl Renderer {
+    /// Fill `quad` with a linear gradient running along `angle` (in radians, turning from the +X
+    /// axis of the renderer's space towards its +Y axis). The gradient spans the quad's full
+    /// extent in that direction, so stops at `0.0` and `1.0` land exactly on its corners.
+    ///
+    /// These are collected into [`Batch::linear_gradients`], one mesh per distinct set of
+    /// `stops`.
+    ///
+    /// [`Batch::linear_gradients`]: crate::Batch::linear_gradients
+    pub fn add_linear_gradient(
+        &mut self,
+        quad: &Quad,
+        angle: f32,
+        stops: &[(f32, [f32; 3])],
+    ) -> Result<(), GradientError> {
+        if stops.is_empty() {
+            return Err(GradientError::NoStops);
+        }
+        if stops.len() > MAX_GRADIENT_STOPS {
+            return Err(GradientError::TooManyStops(stops.len()));
+        }
+        if quad.is_degenerate() {
+            return Ok(());
+        }
+
+        let [w, h] = quad.size;
+        let (sin, cos) = angle.sin_cos();
+        // NOTE: The quad's extent along the gradient's direction.
+        let length = (w * cos).abs() + (h * sin).abs();
+        let axis = [cos / length, sin / length];
+
+        let geometry = stops_geometry_for(&mut self.linear_gradients, GradientStops::new(stops));
+        let base = geometry.vertices.len() as u32;
+        geometry.indices.extend(Quad::indices_u32().map(|i| base + i));
+
+        let [x, y] = quad.pos;
+        let (half_w, half_h) = (w * 0.5, h * 0.5);
+        for [dx, dy] in [[0.0, 0.0], [w, 0.0], [0.0, h], [w, h]] {
+            geometry.vertices.push(LinearGradientVertex {
+                pos: [x + dx, y + dy],
+                local: [dx - half_w, dy - half_h],
+                axis,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl Shader {
+    /// The built-in pipeline for drawing [`Batch::radial_gradients`] into a target of `format`
+    /// with `sample_count` samples per pixel.
+    ///
+    /// [`Batch::radial_gradients`]: crate::Batch::radial_gradients
+    pub fn radial_gradient(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Result<Self, ShaderError> {
+        gradient_shader(
+            device,
+            format,
+            sample_count,
+            RADIAL_GRADIENT_WGSL,
+            "Radial Gradient",
+            GradientVertex::desc(),
+            &[],
+        )
+    }
+
+    /// The built-in pipeline for drawing [`Batch::linear_gradients`] into a target of `format`
+    /// with `sample_count` samples per pixel, binding each one's `bind_group`.
+    ///
+    /// [`Batch::linear_gradients`]: crate::Batch::linear_gradients
+    pub fn linear_gradient(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Result<Self, ShaderError> {
+        gradient_shader(
+            device,
+            format,
+            sample_count,
+            LINEAR_GRADIENT_WGSL,
+            "Linear Gradient",
+            LinearGradientVertex::desc(),
+            &[&stops_bind_group_layout(device)],
+        )
+    }
+}
+
+fn gradient_shader(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    source: &'static str,
+    label: &str,
+    vertex_buffer: wgpu::VertexBufferLayout,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+) -> Result<Shader, ShaderError> {
+    Shader::new(device, ShaderDescriptor {
+        source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(source)),
+        label: Some(label),
+        pipeline_label: Some(&format!("{label} Pipeline")),
+        pipeline_layout_label: Some(&format!("{label} Pipeline Layout")),
+        bind_group_layouts,
+        vertex_entry_point: Some("vs_main"),
+        vertex_buffers: &[vertex_buffer],
+        fragment_entry_point: Some("fs_main"),
+        fragment_targets: &[Some(wgpu::ColorTargetState {
+            format,
+            blend: Some(wgpu::BlendState::REPLACE),
+            write_mask: wgpu::ColorWrites::ALL,
+        })],
+        primitive: ShaderDescriptor::UNCULLED_PRIMITIVE,
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..ShaderDescriptor::default().multisample
+        },
+        ..Default::default()
+    })
+}
+
+
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+struct BackgroundColors {
+    top: [f32; 4],
+    bottom: [f32; 4],
+}
+
+/// A vertical gradient filling a whole target, for backgrounds.
+///
+/// It's drawn in a pass of its own with a single triangle generated in the vertex shader, so
+/// there are no buffers to upload besides its two colors.
+pub struct BackgroundGradient {
+    shader: Shader,
+    colors: BackgroundColors,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl BackgroundGradient {
+    /// Create a gradient from `top` to `bottom`, for targets of `format` with `sample_count`
+    /// samples per pixel.
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        top: Color,
+        bottom: Color,
+    ) -> Result<Self, ShaderError> {
+        let bind_group_layout = device.create_bind_group_layout(
+            &wgpu::BindGroupLayoutDescriptor {
+                label: Some("Background Gradient Bind Group Layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            },
+        );
+        let shader = Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(
+                BACKGROUND_GRADIENT_WGSL,
+            )),
+            label: Some("background_gradient.wgsl"),
+            pipeline_label: Some("Background Gradient Pipeline"),
+            pipeline_layout_label: Some("Background Gradient Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            vertex_entry_point: Some("vs_fullscreen"),
+            vertex_buffers: &[],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            primitive: ShaderDescriptor::UNCULLED_PRIMITIVE,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..ShaderDescriptor::default().multisample
+            },
+            ..Default::default()
+        })?;
+        let colors = BackgroundColors {
+            top: [top.r, top.g, top.b, top.a],
+            bottom: [bottom.r, bottom.g, bottom.b, bottom.a],
+        };
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Background Gradient Buffer"),
+            contents: bytemuck::bytes_of(&colors),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Background Gradient Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Ok(Self {
+            shader,
+            colors,
+            buffer,
+            bind_group,
+        })
+    }
+
+    pub fn set_colors(&mut self, queue: &wgpu::Queue, top: Color, bottom: Color) {
+        self.colors = BackgroundColors {
+            top: [top.r, top.g, top.b, top.a],
+            bottom: [bottom.r, bottom.g, bottom.b, bottom.a],
+        };
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&self.colors));
+    }
+
+    /// Record a pass filling `target` with the gradient, in place of clearing it.
+    ///
+    /// With a `scissor`, only that rect is filled and the rest of the target is kept (and so
+    /// its [`RenderTarget::size`] must be known). Without one, the old contents are discarded.
+    pub fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &RenderTarget,
+        scissor: Option<ScissorRect>,
+    ) -> Result<(), String> {
+        let rect = match (scissor, target.size) {
+            (Some(scissor), Some(size)) => Some(scissor.clamped(size)),
+            (Some(_), None) => {
+                return Err("scissored backgrounds need the target's size".to_string());
+            }
+            (None, _) => None,
+        };
+        // NOTE: Every pixel is overwritten anyway, so a clear is only there to tell the GPU
+        //       that the old contents don't matter.
+        let load = match rect {
+            Some(_) => wgpu::LoadOp::Load,
+            None => wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+        };
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Background Gradient Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target.view,
+                resolve_target: target.resolve_target.as_ref(),
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        if let Some(rect) = rect {
+            render_pass.set_scissor_rect(rect.x, rect.y, rect.width, rect.height);
+        }
+        render_pass.set_pipeline(&self.shader.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        draw_fullscreen_triangle(&mut render_pass);
+
+        Ok(())
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DrawCommand, Texture, testing};
+
+    #[test]
+    fn gradients_with_the_same_stops_share_a_mesh() {
+        let Some((_adapter, device, _queue)) = testing::gpu() else {
+            return;
+        };
+        let quad = Quad::new([-1.0, -1.0], [1.0, 1.0]);
+        let (black, white) = ((0.0, [0.0; 3]), (1.0, [1.0; 3]));
+        let mut renderer = Renderer::start();
+        renderer.add_linear_gradient(&quad, 0.0, &[black, white]).unwrap();
+        renderer.add_linear_gradient(&quad, 1.0, &[white, black]).unwrap();
+        renderer.add_linear_gradient(&quad, 0.0, &[white]).unwrap();
+
+        assert_eq!(renderer.add_linear_gradient(&quad, 0.0, &[]), Err(GradientError::NoStops));
+        assert_eq!(
+            renderer.add_linear_gradient(&quad, 0.0, &[black; MAX_GRADIENT_STOPS + 1]),
+            Err(GradientError::TooManyStops(MAX_GRADIENT_STOPS + 1)),
+        );
+
+        let batch = renderer.finish(&device);
+        assert_eq!(batch.linear_gradients.len(), 2);
+        assert_eq!(batch.linear_gradients[0].mesh.num_indices, 12);
+        assert_eq!(batch.linear_gradients[1].mesh.num_indices, 6);
+    }
+
+    #[test]
+    fn four_stops_are_read_from_the_uniform() {
+        let Some((_adapter, device, queue)) = testing::gpu() else {
+            return;
+        };
+        let shader = Shader::linear_gradient(&device, Texture::FORMAT, 1).unwrap();
+        let (red, green, blue, white) =
+            ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [1.0, 1.0, 1.0]);
+        let mut renderer = Renderer::start();
+        // NOTE: Out of order, to be sorted before they're uploaded.
+        renderer.add_linear_gradient(
+            &Quad::new([-1.0, -1.0], [2.0, 2.0]),
+            0.0,
+            &[(1.0, white), (0.5, blue), (0.0, red), (0.25, green)],
+        ).unwrap();
+        let batch = renderer.finish(&device);
+        let gradient = &batch.linear_gradients[0];
+
+        let pixels = testing::render(&device, &queue, [16, 1], &[DrawCommand {
+            shader: &shader,
+            mesh: &gradient.mesh,
+            bind_groups: &[&gradient.bind_group],
+            instances: None,
+            scissor: None,
+        }]);
+        let nearest = |x: usize| {
+            let pixel = &pixels[x * 4..x * 4 + 3];
+            [red, green, blue, white]
+                .into_iter()
+                .min_by_key(|color| {
+                    color.iter()
+                        .zip(pixel)
+                        .map(|(&c, &p)| (c * 255.0 - p as f32).abs() as u32)
+                        .sum::<u32>()
+                })
+                .unwrap()
+        };
+
+        assert_eq!(nearest(0), red);
+        assert_eq!(nearest(3), green);
+        assert_eq!(nearest(7), blue);
+        assert_eq!(nearest(15), white);
+    }
+}
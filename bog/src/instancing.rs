@@ -0,0 +1,159 @@
+//! Instanced drawing
+
+
+
+use wgpu::util::DeviceExt as _;
+
+use crate::{INSTANCED_WGSL, Mesh, Shader, ShaderDescriptor, ShaderError, Vertex};
+
+
+
+/// The offset and scale applied to one instance of a mesh, in clip space.
+///
+/// Each vertex position `p` is drawn at `p * scale + offset`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Transform2D {
+    pub offset: [f32; 2],
+    pub scale: [f32; 2],
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform2D {
+    pub const IDENTITY: Self = Self::new([0.0, 0.0], [1.0, 1.0]);
+
+    pub const fn new(offset: [f32; 2], scale: [f32; 2]) -> Self {
+        Self { offset, scale }
+    }
+
+    pub const fn translation(offset: [f32; 2]) -> Self {
+        Self::new(offset, [1.0, 1.0])
+    }
+
+    /// A translation by `offset` pixels (Y-down) in a viewport of `size` pixels.
+    pub const fn translation_pixels(offset: [f32; 2], size: [f32; 2]) -> Self {
+        Self::translation([offset[0] * 2.0 / size[0], -offset[1] * 2.0 / size[1]])
+    }
+
+    /// The per-instance layout, read from vertex buffer slot `1` at locations `4` and `5`.
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Transform2D>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+            ]
+        }
+    }
+}
+
+
+
+/// A buffer of [`Transform2D`]s, drawing a mesh once for each of them.
+pub struct Instances {
+    buffer: wgpu::Buffer,
+    count: u32,
+    capacity: u32,
+}
+
+impl Instances {
+    pub fn new(device: &wgpu::Device, transforms: &[Transform2D]) -> Self {
+        // NOTE: Empty buffers can't be bound, so there's always room for at least one instance.
+        let contents = if transforms.is_empty() {
+            bytemuck::bytes_of(&Transform2D::IDENTITY)
+        } else {
+            bytemuck::cast_slice(transforms)
+        };
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Instance Buffer"),
+            contents,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+
+        Self {
+            buffer,
+            count: transforms.len() as u32,
+            capacity: transforms.len().max(1) as u32,
+        }
+    }
+
+    /// Replace the transforms, only reallocating the buffer when there are more of them than
+    /// it can hold.
+    pub fn update(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        transforms: &[Transform2D],
+    ) {
+        if transforms.len() as u32 > self.capacity {
+            *self = Self::new(device, transforms);
+        } else {
+            queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(transforms));
+            self.count = transforms.len() as u32;
+        }
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+}
+
+impl Mesh {
+    /// Draw this mesh once per instance. The pipeline must read [`Transform2D::desc`] from
+    /// vertex buffer slot `1`, like [`Shader::instanced`] does.
+    pub fn draw_instances(&self, render_pass: &mut wgpu::RenderPass, instances: &Instances) {
+        render_pass.set_vertex_buffer(1, instances.buffer.slice(..));
+        self.draw_range_instanced(render_pass, 0..self.num_indices, 0..instances.count);
+    }
+}
+
+impl Shader {
+    /// The built-in pipeline for drawing [`Vertex`] meshes with [`Mesh::draw_instances`] into a
+    /// target of `format` with `sample_count` samples per pixel.
+    pub fn instanced(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Result<Self, ShaderError> {
+        Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(INSTANCED_WGSL)),
+            label: Some("instanced.wgsl"),
+            pipeline_label: Some("Instanced Pipeline"),
+            pipeline_layout_label: Some("Instanced Pipeline Layout"),
+            bind_group_layouts: &[],
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &[Vertex::desc(), Transform2D::desc()],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            primitive: wgpu::PrimitiveState {
+                // NOTE: Negative scales flip the winding of each instance.
+                cull_mode: None,
+                ..ShaderDescriptor::default().primitive
+            },
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..ShaderDescriptor::default().multisample
+            },
+            ..Default::default()
+        })
+    }
+}
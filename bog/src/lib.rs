@@ -5,5 +5,82 @@
 pub extern crate wgpu;
 pub extern crate winit;
 
+mod adapter;
+mod antialiasing;
+mod atlas;
+mod blending;
+mod blur;
+mod channel_clear;
+mod circles;
+mod clipping;
+mod color;
+mod compute;
+mod fading;
+mod frame_graph;
+mod frame_limiter;
+mod fullscreen;
+#[cfg(feature = "glam")]
+mod glam_interop;
+mod gradient;
+mod instancing;
+mod math;
+mod memory;
+mod model;
+mod particles;
+mod picking;
+mod pipeline_cache;
+mod quad_builder;
+mod readback;
+mod shader_file;
+mod shaders;
+mod shapes;
+mod strips;
+mod svg;
+mod target;
 mod test_renderer;
+#[cfg(test)]
+mod testing;
+mod text;
+mod textured;
+mod texture;
+mod texture_array;
+mod uniforms;
+mod wireframe;
+
+pub use adapter::*;
+pub use antialiasing::*;
+pub use atlas::*;
+pub use blending::*;
+pub use blur::*;
+pub use channel_clear::*;
+pub use circles::*;
+pub use clipping::*;
+pub use color::*;
+pub use compute::*;
+pub use fading::*;
+pub use frame_graph::*;
+pub use frame_limiter::*;
+pub use fullscreen::*;
+#[cfg(feature = "glam")]
+pub use glam_interop::*;
+pub use gradient::*;
+pub use instancing::*;
+pub use math::*;
+pub use memory::*;
+pub use model::*;
+pub use particles::*;
+pub use picking::*;
+pub use pipeline_cache::*;
+pub use quad_builder::*;
+pub use readback::*;
+pub use shader_file::*;
+pub use shaders::*;
+pub use shapes::*;
+pub use strips::*;
+pub use target::*;
 pub use test_renderer::*;
+pub use text::*;
+pub use textured::*;
+pub use texture::*;
+pub use texture_array::*;
+pub use uniforms::*;
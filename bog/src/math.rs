@@ -0,0 +1,140 @@
+//! Matrix math
+
+
+
+/// An orthographic projection, as a column-major matrix (the layout WGSL's `mat4x4<f32>`
+/// expects).
+///
+/// `left..right` and `bottom..top` map to `-1.0..=1.0` in clip space, and `near..far` maps to
+/// wgpu's `0.0..=1.0` depth range. Swapping `bottom` and `top` flips the Y axis.
+pub const fn ortho(
+    left: f32,
+    right: f32,
+    bottom: f32,
+    top: f32,
+    near: f32,
+    far: f32,
+) -> [[f32; 4]; 4] {
+    let width = right - left;
+    let height = top - bottom;
+    let depth = far - near;
+
+    [
+        [2.0 / width, 0.0, 0.0, 0.0],
+        [0.0, 2.0 / height, 0.0, 0.0],
+        [0.0, 0.0, 1.0 / depth, 0.0],
+        [-(right + left) / width, -(top + bottom) / height, -near / depth, 1.0],
+    ]
+}
+
+/// An orthographic projection for a `width` by `height` pixel viewport, with the origin at its
+/// top-left corner (and Y pointing down) if `top_left` is set, or at its bottom-left corner (and
+/// Y pointing up) otherwise. Depths from `0.0` to `1.0` are kept as they are.
+pub const fn ortho_pixels(width: f32, height: f32, top_left: bool) -> [[f32; 4]; 4] {
+    if top_left {
+        ortho(0.0, width, height, 0.0, 0.0, 1.0)
+    } else {
+        ortho(0.0, width, 0.0, height, 0.0, 1.0)
+    }
+}
+
+/// The matrix that leaves every point where it is.
+pub const IDENTITY_MATRIX: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// The column-major product `a * b`, which applies `b` first and then `a`.
+pub const fn multiply_matrices(a: [[f32; 4]; 4], b: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    let mut out = [[0.0; 4]; 4];
+    let mut column = 0;
+    while column < 4 {
+        let mut row = 0;
+        while row < 4 {
+            let mut i = 0;
+            while i < 4 {
+                out[column][row] += a[i][row] * b[column][i];
+                i += 1;
+            }
+            row += 1;
+        }
+        column += 1;
+    }
+
+    out
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::transform;
+
+    fn translate(x: f32, y: f32) -> [[f32; 4]; 4] {
+        let mut matrix = IDENTITY_MATRIX;
+        matrix[3] = [x, y, 0.0, 1.0];
+        matrix
+    }
+
+    fn scale(x: f32, y: f32) -> [[f32; 4]; 4] {
+        let mut matrix = IDENTITY_MATRIX;
+        matrix[0][0] = x;
+        matrix[1][1] = y;
+        matrix
+    }
+
+    #[test]
+    fn ortho_maps_corners_to_clip_space() {
+        let matrix = ortho(-10.0, 30.0, 5.0, 25.0, 0.0, 1.0);
+        assert_eq!(transform(matrix, [-10.0, 5.0]), [-1.0, -1.0]);
+        assert_eq!(transform(matrix, [30.0, 5.0]), [1.0, -1.0]);
+        assert_eq!(transform(matrix, [30.0, 25.0]), [1.0, 1.0]);
+        assert_eq!(transform(matrix, [-10.0, 25.0]), [-1.0, 1.0]);
+        assert_eq!(transform(matrix, [10.0, 15.0]), [0.0, 0.0]);
+
+        // NOTE: Depths go from `near` at `0.0` to `far` at `1.0`.
+        let matrix = ortho(-1.0, 1.0, -1.0, 1.0, 2.0, 6.0);
+        let depth = |z: f32| matrix[2][2] * z + matrix[3][2];
+        assert_eq!([depth(2.0), depth(4.0), depth(6.0)], [0.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn top_left_pixel_projections_flip_y() {
+        let top_left = ortho_pixels(200.0, 100.0, true);
+        assert_eq!(transform(top_left, [0.0, 0.0]), [-1.0, 1.0]);
+        assert_eq!(transform(top_left, [200.0, 100.0]), [1.0, -1.0]);
+
+        let bottom_left = ortho_pixels(200.0, 100.0, false);
+        assert_eq!(transform(bottom_left, [0.0, 0.0]), [-1.0, -1.0]);
+        assert_eq!(transform(bottom_left, [200.0, 100.0]), [1.0, 1.0]);
+    }
+
+    #[test]
+    fn multiplying_by_the_identity_changes_nothing() {
+        let matrix = multiply_matrices(translate(2.0, 3.0), scale(4.0, 5.0));
+        assert_eq!(multiply_matrices(IDENTITY_MATRIX, matrix), matrix);
+        assert_eq!(multiply_matrices(matrix, IDENTITY_MATRIX), matrix);
+        assert_eq!(multiply_matrices(IDENTITY_MATRIX, IDENTITY_MATRIX), IDENTITY_MATRIX);
+    }
+
+    #[test]
+    fn products_apply_the_right_matrix_first() {
+        let translate_scaled = multiply_matrices(translate(2.0, 3.0), scale(4.0, 5.0));
+        assert_eq!(transform(translate_scaled, [1.0, 1.0]), [6.0, 8.0]);
+        assert_eq!(
+            translate_scaled,
+            [
+                [4.0, 0.0, 0.0, 0.0],
+                [0.0, 5.0, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [2.0, 3.0, 0.0, 1.0],
+            ],
+        );
+
+        let scale_translated = multiply_matrices(scale(4.0, 5.0), translate(2.0, 3.0));
+        assert_eq!(transform(scale_translated, [1.0, 1.0]), [12.0, 20.0]);
+    }
+}
@@ -0,0 +1,78 @@
+//! Memory reports
+
+
+
+/// A summary of the GPU memory that a device's allocator has handed out, for tracking down
+/// leaks (e.g. from buffers and textures being recreated every frame).
+///
+/// Only the Vulkan and DX12 backends allocate through an allocator that can report on itself.
+/// Memory is reserved in large blocks (per memory type), and then sub-allocated to buffers and
+/// textures, so a block's size is what the driver sees, and allocated bytes are what's in use.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    /// Bytes reserved from the driver, including the unallocated parts of blocks.
+    pub reserved_bytes: u64,
+    /// Bytes in use by allocations.
+    pub allocated_bytes: u64,
+    /// The number of live allocations.
+    pub allocations: usize,
+    /// Every memory block, in the allocator's order.
+    pub blocks: Vec<MemoryBlock>,
+}
+
+/// One block of a [`MemoryReport`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoryBlock {
+    /// Bytes reserved for this block.
+    pub reserved_bytes: u64,
+    /// Bytes in use by the allocations in this block.
+    pub allocated_bytes: u64,
+    /// The number of live allocations in this block.
+    pub allocations: usize,
+}
+
+impl MemoryReport {
+    /// Summarize the allocator report of `device`, or return `None` if its backend can't make
+    /// one.
+    pub fn generate(device: &wgpu::Device) -> Option<Self> {
+        let report = device.generate_allocator_report()?;
+        let blocks = report.blocks
+            .iter()
+            .map(|block| {
+                let allocations = report.allocations
+                    .get(block.allocations.clone())
+                    .unwrap_or_default();
+                MemoryBlock {
+                    reserved_bytes: block.size,
+                    allocated_bytes: allocations.iter().map(|allocation| allocation.size).sum(),
+                    allocations: allocations.len(),
+                }
+            })
+            .collect();
+
+        Some(Self {
+            reserved_bytes: report.total_reserved_bytes,
+            allocated_bytes: report.total_allocated_bytes,
+            allocations: report.allocations.len(),
+            blocks,
+        })
+    }
+
+    /// Bytes reserved but not allocated, which the allocator may give back to the driver later.
+    pub const fn unused_bytes(&self) -> u64 {
+        self.reserved_bytes.saturating_sub(self.allocated_bytes)
+    }
+}
+
+impl std::fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} allocations using {} of {} bytes in {} blocks",
+            self.allocations,
+            self.allocated_bytes,
+            self.reserved_bytes,
+            self.blocks.len(),
+        )
+    }
+}
@@ -0,0 +1,173 @@
+//! Model matrices
+
+
+
+use wgpu::util::DeviceExt as _;
+
+use crate::{
+    CoordinateSpace, IDENTITY_MATRIX, SOLID_MODEL_WGSL, Shader, ShaderDescriptor, ShaderError,
+    Vertex, multiply_matrices,
+};
+
+
+
+/// The uniform that a [`Batch`](crate::Batch) is transformed with when it's drawn with
+/// [`Shader::solid_model`], so a reusable batch can be moved, scaled, or rotated without
+/// rebuilding it.
+///
+/// The matrix is column-major, and given in the [`CoordinateSpace`] the batch was built in
+/// (e.g. translations in pixels for [`CoordinateSpace::Pixels`]). To draw through a camera too,
+/// multiply its view-projection in first (with [`multiply_matrices`]).
+pub struct ModelMatrix {
+    matrix: [[f32; 4]; 4],
+    space: CoordinateSpace,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl ModelMatrix {
+    /// Upload `matrix`, which applies to positions given in `space`.
+    pub fn new(device: &wgpu::Device, space: CoordinateSpace, matrix: [[f32; 4]; 4]) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Model Matrix Buffer"),
+            contents: bytemuck::bytes_of(&Self::clip_matrix(space, matrix)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Model Matrix Bind Group"),
+            // NOTE: Layouts with identical entries are interchangeable.
+            layout: &Self::bind_group_layout(device),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            matrix,
+            space,
+            buffer,
+            bind_group,
+        }
+    }
+
+    /// Upload the identity, which draws everything where it was added.
+    pub fn identity(device: &wgpu::Device, space: CoordinateSpace) -> Self {
+        Self::new(device, space, IDENTITY_MATRIX)
+    }
+
+    pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Model Matrix Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        })
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    pub fn matrix(&self) -> [[f32; 4]; 4] {
+        self.matrix
+    }
+
+    pub fn space(&self) -> CoordinateSpace {
+        self.space
+    }
+
+    pub fn set(&mut self, queue: &wgpu::Queue, matrix: [[f32; 4]; 4]) {
+        self.matrix = matrix;
+        let clip_matrix = Self::clip_matrix(self.space, matrix);
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&clip_matrix));
+    }
+
+    /// The matrix that's actually uploaded. Batches are already in clip space by the time
+    /// they're drawn, so they're moved back into `space`, transformed there, and then moved
+    /// into clip space again.
+    fn clip_matrix(space: CoordinateSpace, matrix: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+        multiply_matrices(
+            space.to_ndc_matrix(),
+            multiply_matrices(matrix, space.from_ndc_matrix()),
+        )
+    }
+}
+
+impl Shader {
+    /// Like [`Shader::solid`], but transforming each vertex by a [`ModelMatrix`] bound at
+    /// `@group(0)` (like [`Batch::model`](crate::Batch::model)).
+    pub fn solid_model(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Result<Self, ShaderError> {
+        Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(SOLID_MODEL_WGSL)),
+            label: Some("solid_model.wgsl"),
+            pipeline_label: Some("Solid Model Pipeline"),
+            pipeline_layout_label: Some("Solid Model Pipeline Layout"),
+            bind_group_layouts: &[&ModelMatrix::bind_group_layout(device)],
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &[Vertex::desc()],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            primitive: wgpu::PrimitiveState {
+                // NOTE: Mirroring matrices flip the winding, as does Y-down pixel space.
+                cull_mode: None,
+                ..ShaderDescriptor::default().primitive
+            },
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..ShaderDescriptor::default().multisample
+            },
+            ..Default::default()
+        })
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::transform;
+
+    #[test]
+    fn identities_stay_identities_in_clip_space() {
+        let pixels = CoordinateSpace::Pixels { width: 200.0, height: 100.0 };
+        for space in [CoordinateSpace::Ndc, pixels] {
+            assert_eq!(ModelMatrix::clip_matrix(space, IDENTITY_MATRIX), IDENTITY_MATRIX);
+        }
+    }
+
+    #[test]
+    fn pixel_translations_move_by_pixels_in_clip_space() {
+        let pixels = CoordinateSpace::Pixels { width: 200.0, height: 100.0 };
+        let translation = [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [20.0, 10.0, 0.0, 1.0],
+        ];
+        let matrix = ModelMatrix::clip_matrix(pixels, translation);
+
+        // NOTE: The batch is already in NDC by the time it's drawn.
+        for (pos, moved) in [([0.0, 0.0], [20.0, 10.0]), ([100.0, 50.0], [120.0, 60.0])] {
+            let [x, y] = transform(matrix, pixels.to_ndc(pos));
+            let [moved_x, moved_y] = pixels.to_ndc(moved);
+            assert!((x - moved_x).abs() < 1e-6 && (y - moved_y).abs() < 1e-6, "{:?}", [x, y]);
+        }
+    }
+}
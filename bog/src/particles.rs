@@ -0,0 +1,309 @@
+//! GPU particles
+
+
+
+use wgpu::util::DeviceExt as _;
+
+use crate::{
+    ComputeShader, ComputeShaderDescriptor, PARTICLES_WGSL, RenderTarget, Shader,
+    ShaderDescriptor,
+};
+
+
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Particle {
+    pub pos: [f32; 2],
+    pub vel: [f32; 2],
+    pub color: [f32; 3],
+    /// The width and height of the particle. Particles with a (nearly) zero size aren't drawn.
+    pub size: f32,
+}
+
+impl Particle {
+    /// The layout of the particle buffer when read as per-instance vertex data.
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Particle>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 7]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32,
+                },
+            ]
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    gravity: [f32; 2],
+    dt: f32,
+    count: u32,
+}
+
+
+
+/// Particles that live entirely on the GPU: a compute pass steps them, and they're then drawn
+/// instanced, straight from the same storage buffer.
+pub struct ParticleSystem {
+    compute: ComputeShader,
+    render: Shader,
+    particles: wgpu::Buffer,
+    params: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    count: u32,
+    pub gravity: [f32; 2],
+}
+
+impl ParticleSystem {
+    const WORKGROUP_SIZE: u32 = 64;
+
+    /// Set up `particles` to be drawn into targets of `format` with `sample_count` samples per
+    /// pixel. Fails if the device doesn't support compute shaders or storage buffers.
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        particles: &[Particle],
+    ) -> Result<Self, String> {
+        if device.limits().max_storage_buffers_per_shader_stage == 0 {
+            return Err("device doesn't support storage buffers".to_string());
+        }
+
+        let compute = ComputeShader::new(device, ComputeShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(PARTICLES_WGSL)),
+            label: Some("particles.wgsl"),
+            pipeline_label: Some("Particle Compute Pipeline"),
+            pipeline_layout_label: Some("Particle Compute Pipeline Layout"),
+            bind_group_layout_label: Some("Particle Bind Group Layout"),
+            entry_point: Some("cs_main"),
+            bind_group_layout_entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        }).map_err(|error| error.to_string())?;
+        let render = Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(PARTICLES_WGSL)),
+            label: Some("particles.wgsl"),
+            pipeline_label: Some("Particle Render Pipeline"),
+            pipeline_layout_label: Some("Particle Render Pipeline Layout"),
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &[Particle::desc()],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            ..Default::default()
+        }).map_err(|error| error.to_string())?;
+
+        let count = particles.len() as u32;
+        // NOTE: Storage buffers can't be empty, so without any particles the buffer holds a
+        //       zero-sized one that's never stepped or drawn.
+        let contents: &[Particle] = if particles.is_empty() {
+            &[bytemuck::Zeroable::zeroed()]
+        } else {
+            particles
+        };
+        let particles = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Buffer"),
+            contents: bytemuck::cast_slice(contents),
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::VERTEX
+                | wgpu::BufferUsages::COPY_DST,
+        });
+        let params = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Params Buffer"),
+            size: std::mem::size_of::<Params>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Particle Bind Group"),
+            layout: &compute.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: particles.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: params.as_entire_binding(),
+                },
+            ],
+        });
+
+        Ok(Self {
+            compute,
+            render,
+            particles,
+            params,
+            bind_group,
+            count,
+            gravity: [0.0, -0.5],
+        })
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Record a compute pass stepping every particle forward by `dt` seconds. This should be
+    /// recorded before the particles are rendered in the same encoder.
+    pub fn update(&self, queue: &wgpu::Queue, encoder: &mut wgpu::CommandEncoder, dt: f32) {
+        queue.write_buffer(&self.params, 0, bytemuck::bytes_of(&Params {
+            gravity: self.gravity,
+            dt,
+            count: self.count,
+        }));
+
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Particle Compute Pass"),
+            timestamp_writes: None,
+        });
+        compute_pass.set_pipeline(&self.compute.pipeline);
+        compute_pass.set_bind_group(0, &self.bind_group, &[]);
+        compute_pass.dispatch_workgroups(self.count.div_ceil(Self::WORKGROUP_SIZE), 1, 1);
+    }
+
+    /// Draw the particles into `target` on top of its current contents.
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, target: &RenderTarget) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Particle Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target.view,
+                resolve_target: target.resolve_target.as_ref(),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_pipeline(&self.render.pipeline);
+        render_pass.set_vertex_buffer(0, self.particles.slice(..));
+        render_pass.draw(0..6, 0..self.count);
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Texture, pop_error_scope, read_pixel, testing};
+
+    /// A device that can step particles and draw 4x multisampled, and a 4x target resolving
+    /// into the returned texture.
+    fn multisampled_gpu() -> Option<(wgpu::Device, wgpu::Queue, RenderTarget, wgpu::Texture)> {
+        let (adapter, device, queue) = testing::gpu()?;
+        let flags = adapter.get_texture_format_features(Texture::FORMAT).flags;
+        if device.limits().max_storage_buffers_per_shader_stage == 0
+            || !flags.sample_count_supported(4)
+        {
+            return None;
+        }
+
+        let multisampled = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Multisampled Test Target"),
+            size: wgpu::Extent3d {
+                width: 8,
+                height: 8,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 4,
+            dimension: wgpu::TextureDimension::D2,
+            format: Texture::FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let resolved = testing::target(&device, [8, 8]);
+        let target = RenderTarget::resolving(
+            multisampled.create_view(&Default::default()),
+            resolved.create_view(&Default::default()),
+        );
+
+        Some((device, queue, target, resolved))
+    }
+
+    #[test]
+    fn empty_systems_can_be_stepped_and_drawn() {
+        let Some((device, queue, target, _resolved)) = multisampled_gpu() else {
+            return;
+        };
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let particles = ParticleSystem::new(&device, Texture::FORMAT, 4, &[]).unwrap();
+        let mut encoder = device.create_command_encoder(&Default::default());
+        particles.update(&queue, &mut encoder, 0.1);
+        particles.render(&mut encoder, &target);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        assert_eq!(particles.count(), 0);
+        if let Some(error) = pop_error_scope(&device) {
+            panic!("{error}");
+        }
+    }
+
+    #[test]
+    fn multisampled_particles_are_resolved() {
+        let Some((device, queue, target, resolved)) = multisampled_gpu() else {
+            return;
+        };
+
+        let particles = ParticleSystem::new(&device, Texture::FORMAT, 4, &[Particle {
+            pos: [0.0, 0.0],
+            vel: [0.0, 0.0],
+            color: [1.0, 1.0, 1.0],
+            size: 1.0,
+        }]).unwrap();
+        let mut encoder = device.create_command_encoder(&Default::default());
+        particles.render(&mut encoder, &target);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        assert_eq!(read_pixel(&device, &queue, &resolved, 4, 4).unwrap(), [255; 4]);
+    }
+}
@@ -0,0 +1,161 @@
+//! GPU picking
+
+
+
+use crate::{Mesh, PICK_WGSL, Shader, ShaderDescriptor, Vertex};
+
+
+
+/// Finds the primitive under a pixel by rendering pick ids (see [`Renderer::set_pick_id`]) into
+/// an offscreen `R32Uint` texture and reading a single texel back.
+///
+/// [`Renderer::set_pick_id`]: crate::Renderer::set_pick_id
+pub struct Picker {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    shader: Shader,
+    readback: wgpu::Buffer,
+}
+
+impl Picker {
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Uint;
+
+    pub fn new(device: &wgpu::Device, size: [u32; 2]) -> Self {
+        let shader = Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(PICK_WGSL)),
+            label: Some("pick.wgsl"),
+            pipeline_label: Some("Pick Pipeline"),
+            pipeline_layout_label: Some("Pick Pipeline Layout"),
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &[Vertex::desc()],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format: Self::FORMAT,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            primitive: wgpu::PrimitiveState {
+                // NOTE: Pick every primitive regardless of its winding.
+                cull_mode: None,
+                ..ShaderDescriptor::default().primitive
+            },
+            ..Default::default()
+        }).unwrap();
+        // NOTE: A single texel is copied, but rows in buffer copies must still be aligned.
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Pick Readback Buffer"),
+            size: wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let (texture, view) = Self::create_target(device, size);
+
+        Self {
+            texture,
+            view,
+            shader,
+            readback,
+        }
+    }
+
+    fn create_target(device: &wgpu::Device, size: [u32; 2]) -> (wgpu::Texture, wgpu::TextureView) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Pick Texture"),
+            size: wgpu::Extent3d {
+                width: size[0].max(1),
+                height: size[1].max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        (texture, view)
+    }
+
+    /// Resize the pick target. This should match the size of the surface being picked from.
+    pub fn resize(&mut self, device: &wgpu::Device, size: [u32; 2]) {
+        (self.texture, self.view) = Self::create_target(device, size);
+    }
+
+    /// The pick id of the topmost primitive in `meshes` covering the pixel at `(x, y)`
+    /// (measured from the top-left), or `None` if there isn't one.
+    ///
+    /// NOTE: This blocks until the GPU has finished rendering the pick pass.
+    pub fn pick(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        meshes: &[&Mesh],
+        x: u32,
+        y: u32,
+    ) -> Option<u32> {
+        if x >= self.texture.width() || y >= self.texture.height() {
+            return None;
+        }
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Pick Encoder"),
+        });
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Pick Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &self.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            render_pass.set_pipeline(&self.shader.pipeline);
+            for mesh in meshes {
+                mesh.draw(&mut render_pass);
+            }
+        }
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.readback,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT),
+                    rows_per_image: Some(1),
+                },
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = self.readback.slice(..4);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        receiver.recv().ok()?.ok()?;
+
+        let id = u32::from_ne_bytes(slice.get_mapped_range()[..4].try_into().unwrap());
+        self.readback.unmap();
+
+        (id != 0).then_some(id)
+    }
+}
@@ -0,0 +1,133 @@
+//! Pipeline caching
+
+
+
+use crate::{Shader, ShaderDescriptor, ShaderError};
+
+
+
+/// A driver-level cache of compiled pipelines, which can be saved to disk so that the next run
+/// doesn't have to compile them from scratch. Pass it to [`ShaderDescriptor::cache`].
+///
+/// Only Vulkan supports this (see [`PipelineCache::FEATURES`]). Elsewhere, drivers usually keep
+/// a cache of their own anyway.
+pub struct PipelineCache {
+    cache: wgpu::PipelineCache,
+    /// Identifies the adapter (and driver) that the cached data is valid for.
+    key: String,
+}
+
+impl PipelineCache {
+    /// The features a device has to be created with to use pipeline caches.
+    pub const FEATURES: wgpu::Features = wgpu::Features::PIPELINE_CACHE;
+
+    /// Create a cache for pipelines made by `device`, starting out with `data` if it's given.
+    /// Data that doesn't suit the adapter (e.g. after a driver update) is silently dropped.
+    ///
+    /// Fails if the device wasn't created with [`PipelineCache::FEATURES`], or if the adapter
+    /// can't cache pipelines at all.
+    ///
+    /// # Safety
+    ///
+    /// `data` must have come from [`PipelineCache::data`], as for
+    /// [`wgpu::Device::create_pipeline_cache`].
+    pub unsafe fn new(
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        data: Option<&[u8]>,
+    ) -> Result<Self, String> {
+        if !device.features().contains(Self::FEATURES) {
+            return Err("the device wasn't created with PIPELINE_CACHE".to_string());
+        }
+        let Some(key) = wgpu::util::pipeline_cache_key(&adapter.get_info()) else {
+            return Err(format!("{:?} adapters can't cache pipelines", adapter.get_info().backend));
+        };
+
+        // SAFETY: The caller vouched for `data`.
+        let cache = unsafe {
+            device.create_pipeline_cache(&wgpu::PipelineCacheDescriptor {
+                label: Some("Pipeline Cache"),
+                data,
+                fallback: true,
+            })
+        };
+
+        Ok(Self { cache, key })
+    }
+
+    /// Create a cache for pipelines made by `device`, starting out with what
+    /// [`PipelineCache::save_to_dir`] last saved to `dir` for this adapter (if anything).
+    ///
+    /// # Safety
+    ///
+    /// The cache files in `dir` must only ever have been written by
+    /// [`PipelineCache::save_to_dir`].
+    pub unsafe fn load_from_dir(
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        dir: &std::path::Path,
+    ) -> Result<Self, String> {
+        let data = wgpu::util::pipeline_cache_key(&adapter.get_info())
+            .and_then(|key| std::fs::read(dir.join(key)).ok());
+
+        // SAFETY: The caller vouched for the files in `dir`.
+        unsafe { Self::new(adapter, device, data.as_deref()) }
+    }
+
+    /// Write everything cached so far to a file in `dir` named after [`PipelineCache::key`],
+    /// replacing what was there. Files for other adapters are left alone.
+    pub fn save_to_dir(&self, dir: &std::path::Path) -> Result<(), String> {
+        let Some(data) = self.data() else {
+            return Ok(());
+        };
+        std::fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+
+        // NOTE: The data is written next to the cache file first, so a crash can't leave half
+        //       of it behind.
+        let path = dir.join(&self.key);
+        let temp_path = path.with_extension("temp");
+        std::fs::write(&temp_path, data).map_err(|error| error.to_string())?;
+        std::fs::rename(&temp_path, &path).map_err(|error| error.to_string())
+    }
+
+    /// Everything cached so far, to be given back to [`PipelineCache::new`] next time.
+    pub fn data(&self) -> Option<Vec<u8>> {
+        self.cache.get_data()
+    }
+
+    /// Identifies the adapter and driver that [`PipelineCache::data`] is valid for.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn get(&self) -> &wgpu::PipelineCache {
+        &self.cache
+    }
+}
+
+impl Shader {
+    /// Build every one of `descriptors` up front (say, during a loading screen) rather than on
+    /// first use, compiling them on as many threads as there are descriptors. The results are in
+    /// the same order as `descriptors`.
+    ///
+    /// NOTE: Setting [`ShaderDescriptor::cache`] on each of them lets later runs skip most of
+    ///       the compiling.
+    pub fn new_all(
+        device: &wgpu::Device,
+        descriptors: Vec<ShaderDescriptor>,
+    ) -> Vec<Result<Self, ShaderError>> {
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = descriptors
+                .into_iter()
+                .map(|desc| scope.spawn(move || Shader::new(device, desc)))
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect()
+        })
+    }
+}
@@ -0,0 +1,218 @@
+//! Quads with extra options
+
+
+
+use crate::{Quad, Renderer, Vertex};
+
+
+
+/// A [`Quad`] along with everything about how it's drawn, built up with fluent modifiers and
+/// drawn with [`Renderer::add`], e.g. `Quad::new(pos, size).with_color(c).rotated(0.3)`. Plain
+/// quads are simpler with [`Renderer::add_quad`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuadBuilder {
+    pub quad: Quad,
+    /// The colors of the corners, in the order [`Quad::push_with_color`] emits them: at `pos`,
+    /// then along the width, then along the height, then opposite `pos`.
+    pub corner_colors: [[f32; 3]; 4],
+    /// In radians, around the quad's center (counter-clockwise with Y pointing up).
+    pub rotation: f32,
+    pub opacity: f32,
+    /// The depth to draw at (see [`QuadBuilder::at_depth`]), or `None` for that of the
+    /// renderer's current layer.
+    pub depth: Option<f32>,
+}
+
+impl From<Quad> for QuadBuilder {
+    fn from(quad: Quad) -> Self {
+        Self {
+            quad,
+            corner_colors: [[1.0, 1.0, 1.0]; 4],
+            rotation: 0.0,
+            opacity: 1.0,
+            depth: None,
+        }
+    }
+}
+
+impl QuadBuilder {
+    pub const fn with_color(mut self, color: [f32; 3]) -> Self {
+        self.corner_colors = [color; 4];
+        self
+    }
+
+    /// Blend between a color at each corner (see [`QuadBuilder::corner_colors`]).
+    pub const fn with_corner_colors(mut self, colors: [[f32; 3]; 4]) -> Self {
+        self.corner_colors = colors;
+        self
+    }
+
+    /// Rotate by `angle` radians around the quad's center.
+    pub const fn rotated(mut self, angle: f32) -> Self {
+        self.rotation = angle;
+        self
+    }
+
+    /// Only seen by shaders that read the vertex color as a `vec4` (and blend).
+    pub const fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    /// Draw at `depth` (from `1.0` at the back towards `0.0`, like
+    /// [`Renderer::layer_depth`]) whatever layer the renderer is on. This only matters when
+    /// drawing with a depth test, like [`Shader::solid_layered`](crate::Shader::solid_layered)
+    /// does.
+    pub const fn at_depth(mut self, depth: f32) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    pub fn push(self, renderer: &mut Renderer) {
+        renderer.add(self);
+    }
+
+    fn corners(&self, quad: &Quad) -> [[f32; 2]; 4] {
+        let [x, y] = quad.pos;
+        let [w, h] = quad.size;
+        let corners = [[x, y], [x + w, y], [x, y + h], [x + w, y + h]];
+        if self.rotation == 0.0 {
+            return corners;
+        }
+
+        let center = [x + w * 0.5, y + h * 0.5];
+        let (sin, cos) = self.rotation.sin_cos();
+        corners.map(|[px, py]| {
+            let [dx, dy] = [px - center[0], py - center[1]];
+            [center[0] + dx * cos - dy * sin, center[1] + dx * sin + dy * cos]
+        })
+    }
+}
+
+impl Quad {
+    /// Start a [`QuadBuilder`] drawing this quad in `color`.
+    pub fn with_color(self, color: [f32; 3]) -> QuadBuilder {
+        QuadBuilder::from(self).with_color(color)
+    }
+
+    /// Start a [`QuadBuilder`] drawing this quad with a color at each corner.
+    pub fn with_corner_colors(self, colors: [[f32; 3]; 4]) -> QuadBuilder {
+        QuadBuilder::from(self).with_corner_colors(colors)
+    }
+
+    /// Start a [`QuadBuilder`] drawing this quad rotated by `angle` radians.
+    pub fn rotated(self, angle: f32) -> QuadBuilder {
+        QuadBuilder::from(self).rotated(angle)
+    }
+
+    /// Start a [`QuadBuilder`] drawing this quad at `opacity`.
+    pub fn with_opacity(self, opacity: f32) -> QuadBuilder {
+        QuadBuilder::from(self).with_opacity(opacity)
+    }
+
+    /// Start a [`QuadBuilder`] drawing this quad at `depth`.
+    pub fn at_depth(self, depth: f32) -> QuadBuilder {
+        QuadBuilder::from(self).at_depth(depth)
+    }
+}
+
+impl Renderer {
+    /// Add a quad with all of the options in `quad`. Degenerate quads are skipped.
+    ///
+    /// NOTE: Rotated quads aren't snapped to pixels, or culled by the view bounds.
+    pub fn add(&mut self, quad: impl Into<QuadBuilder>) {
+        let builder = quad.into();
+        let quad = if builder.rotation == 0.0 {
+            self.snap(&builder.quad)
+        } else {
+            builder.quad
+        };
+        if quad.is_degenerate() || (builder.rotation == 0.0 && self.cull(&quad)) {
+            return;
+        }
+
+        let opacity = builder.opacity.clamp(0.0, 1.0);
+        let corners = builder.corners(&quad);
+        let start = self.vertices.len();
+        self.reserve(Quad::num_vertices() as usize, Quad::num_indices() as usize);
+        self.add_mesh(
+            (0..4).map(|i| Vertex::new(corners[i], builder.corner_colors[i]).with_alpha(opacity)),
+            Quad::indices_u32(),
+        );
+        if let Some(depth) = builder.depth {
+            for vertex in &mut self.vertices[start..] {
+                *vertex = vertex.with_depth(depth);
+            }
+            self.depths.push((start..self.vertices.len(), depth));
+        }
+        self.quads += 1;
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: [f32; 3] = [1.0, 1.0, 1.0];
+
+    fn vertices(builder: QuadBuilder) -> Vec<Vertex> {
+        let mut renderer = Renderer::start();
+        renderer.add(builder);
+        renderer.vertices().to_vec()
+    }
+
+    #[test]
+    fn rotated_quads_turn_around_their_center() {
+        let vertices = vertices(Quad::new([0.0, 0.0], [0.4, 0.2]).rotated(std::f32::consts::PI));
+
+        let expected = [[0.4, 0.2], [0.0, 0.2], [0.4, 0.0], [0.0, 0.0]];
+        for (vertex, expected) in vertices.iter().zip(expected) {
+            assert!((vertex.pos[0] - expected[0]).abs() < 1e-6, "{vertex:?}");
+            assert!((vertex.pos[1] - expected[1]).abs() < 1e-6, "{vertex:?}");
+        }
+    }
+
+    #[test]
+    fn corner_colors_follow_the_vertex_order() {
+        let colors = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [1.0, 1.0, 0.0]];
+        let vertices = vertices(Quad::new([0.0, 0.0], [0.5, 0.5]).with_corner_colors(colors));
+
+        assert_eq!(vertices, [
+            Vertex::new([0.0, 0.0], colors[0]),
+            Vertex::new([0.5, 0.0], colors[1]),
+            Vertex::new([0.0, 0.5], colors[2]),
+            Vertex::new([0.5, 0.5], colors[3]),
+        ]);
+    }
+
+    #[test]
+    fn opacity_is_clamped_into_every_vertex() {
+        let quad = Quad::new([0.0, 0.0], [0.5, 0.5]);
+        for (opacity, alpha) in [(0.25, 0.25), (2.0, 1.0), (-1.0, 0.0)] {
+            let vertices = vertices(quad.with_color(WHITE).with_opacity(opacity));
+            assert_eq!(vertices.len(), 4);
+            for vertex in vertices {
+                assert_eq!(vertex, Vertex::new(vertex.pos, WHITE).with_alpha(alpha));
+            }
+        }
+    }
+
+    #[test]
+    fn explicit_depths_outlast_layers() {
+        let mut renderer = Renderer::start();
+        renderer.next_layer();
+        renderer.add(Quad::new([0.0, 0.0], [0.5, 0.5]).at_depth(0.25));
+        renderer.add_quad(&Quad::new([0.0, 0.0], [0.5, 0.5]), WHITE);
+        renderer.tag_vertices();
+
+        for vertex in &renderer.vertices()[..4] {
+            assert_eq!(*vertex, Vertex::new(vertex.pos, WHITE).with_depth(0.25));
+        }
+        let layer_depth = Renderer::layer_depth(1);
+        for vertex in &renderer.vertices()[4..] {
+            assert_eq!(*vertex, Vertex::new(vertex.pos, WHITE).with_depth(layer_depth));
+        }
+    }
+}
@@ -0,0 +1,441 @@
+//! Reading rendered pixels back to the CPU
+
+
+
+use crate::{DrawCommand, Quad, RenderTarget, Renderer, Shader, Texture, render_to};
+
+
+
+/// Copy the 4-byte texel at `(x, y)` (measured from the top-left) of `texture` to the CPU.
+///
+/// NOTE: This blocks until the GPU has finished all submitted work.
+pub fn read_pixel(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    x: u32,
+    y: u32,
+) -> Result<[u8; 4], String> {
+    if texture.format().block_copy_size(None) != Some(4) {
+        return Err(format!("{:?} doesn't have 4-byte texels", texture.format()));
+    }
+    if x >= texture.width() || y >= texture.height() {
+        return Err(format!(
+            "({x}, {y}) is outside of a {}x{} texture",
+            texture.width(),
+            texture.height(),
+        ));
+    }
+
+    // NOTE: A single texel is copied, but rows in buffer copies must still be aligned.
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Pixel Readback Buffer"),
+        size: wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Pixel Readback Encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        wgpu::TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d { x, y, z: 0 },
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT),
+                rows_per_image: Some(1),
+            },
+        },
+        wgpu::Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..4);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver
+        .recv()
+        .map_err(|_| "readback was never mapped".to_string())?
+        .map_err(|error| error.to_string())?;
+
+    let pixel = slice.get_mapped_range()[..4].try_into().unwrap();
+    buffer.unmap();
+
+    Ok(pixel)
+}
+
+/// Copy all of `texture` (with 4-byte texels) to the CPU, as tightly packed rows from the top.
+///
+/// NOTE: This blocks until the GPU has finished all submitted work. See [`read_texture_async`]
+///       to keep rendering in the meantime.
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Result<Vec<u8>, String> {
+    read_texture_async(device, queue, texture)?.wait(device)
+}
+
+/// Submit a copy of all of `texture` (with 4-byte texels) to the CPU, without waiting for it.
+///
+/// The returned [`PendingReadback`] can be checked each frame, or awaited. Either way, the
+/// device still has to be polled (like with `device.poll(wgpu::Maintain::Poll)` once per frame)
+/// for it to finish on native backends.
+pub fn read_texture_async(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Result<PendingReadback, String> {
+    if texture.format().block_copy_size(None) != Some(4) {
+        return Err(format!("{:?} doesn't have 4-byte texels", texture.format()));
+    }
+
+    start_readback(device, queue, texture, wgpu::TextureAspect::All, 4)
+}
+
+/// Copy the depth values of `texture` to the CPU, as tightly packed rows from the top.
+///
+/// Values are in wgpu's `0.0..=1.0` depth range (`Depth16Unorm` is converted). Depth from
+/// [`ortho`](crate::ortho) is already linear, so `near + depth * (far - near)` gives back the
+/// distance. The texture needs `COPY_SRC` usage and a single sample, like the ones
+/// [`DepthAttachment::create_texture`](crate::DepthAttachment::create_texture) makes, and
+/// (like `Depth24Plus`) formats whose depth can't be copied at all are rejected.
+///
+/// Fails on adapters without [`wgpu::DownlevelFlags::DEPTH_TEXTURE_AND_BUFFER_COPIES`] (like
+/// most GL ones), which can't copy depth at all.
+///
+/// NOTE: This blocks until the GPU has finished all submitted work.
+pub fn read_depth(
+    adapter: &wgpu::Adapter,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Result<Vec<f32>, String> {
+    let format = texture.format();
+    let texel_bytes = match format.block_copy_size(Some(wgpu::TextureAspect::DepthOnly)) {
+        Some(bytes @ (2 | 4)) if format.has_depth_aspect() => bytes,
+        _ => return Err(format!("{format:?} has no depth that can be copied")),
+    };
+    if !adapter
+        .get_downlevel_capabilities()
+        .flags
+        .contains(wgpu::DownlevelFlags::DEPTH_TEXTURE_AND_BUFFER_COPIES)
+    {
+        return Err("the adapter can't copy depth textures to buffers".to_string());
+    }
+    if texture.sample_count() > 1 {
+        return Err("multisampled depth can't be copied, only resolved".to_string());
+    }
+
+    let data = start_readback(device, queue, texture, wgpu::TextureAspect::DepthOnly, texel_bytes)?
+        .wait(device)?;
+
+    Ok(match texel_bytes {
+        2 => data
+            .chunks_exact(2)
+            .map(|texel| u16::from_ne_bytes([texel[0], texel[1]]) as f32 / u16::MAX as f32)
+            .collect(),
+        _ => data
+            .chunks_exact(4)
+            .map(|texel| f32::from_ne_bytes(texel.try_into().unwrap()))
+            .collect(),
+    })
+}
+
+fn start_readback(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    aspect: wgpu::TextureAspect,
+    texel_bytes: u32,
+) -> Result<PendingReadback, String> {
+    if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+        return Err("the texture to read back can't be copied from".to_string());
+    }
+
+    let size = [texture.width(), texture.height()];
+    let row_bytes = size[0] * texel_bytes;
+    let padded_row_bytes = row_bytes.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Texture Readback Buffer"),
+        size: (padded_row_bytes * size[1]) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Texture Readback Encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        wgpu::TexelCopyTextureInfo {
+            aspect,
+            ..texture.as_image_copy()
+        },
+        wgpu::TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_bytes),
+                rows_per_image: Some(size[1]),
+            },
+        },
+        texture.size(),
+    );
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let state = std::sync::Arc::new(std::sync::Mutex::new(MapState::default()));
+    let callback_state = state.clone();
+    buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+        let mut state = callback_state.lock().unwrap();
+        state.result = Some(result.map_err(|error| error.to_string()));
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    });
+
+    Ok(PendingReadback {
+        buffer,
+        row_bytes,
+        padded_row_bytes,
+        state,
+    })
+}
+
+#[derive(Default)]
+struct MapState {
+    result: Option<Result<(), String>>,
+    waker: Option<std::task::Waker>,
+}
+
+/// A texture copy started by [`read_texture_async`], resolving to its tightly packed rows once
+/// the GPU is done with it.
+pub struct PendingReadback {
+    buffer: wgpu::Buffer,
+    row_bytes: u32,
+    padded_row_bytes: u32,
+    state: std::sync::Arc<std::sync::Mutex<MapState>>,
+}
+
+impl PendingReadback {
+    /// Whether the copy has finished (or failed), so [`PendingReadback::wait`] won't block.
+    pub fn is_ready(&self) -> bool {
+        self.state.lock().unwrap().result.is_some()
+    }
+
+    /// Block until the copy has finished, and take its data.
+    pub fn wait(self, device: &wgpu::Device) -> Result<Vec<u8>, String> {
+        if !self.is_ready() {
+            device.poll(wgpu::Maintain::Wait);
+        }
+        let result = self.state.lock().unwrap().result.take();
+        match result {
+            Some(Ok(())) => Ok(self.take_data()),
+            Some(Err(error)) => Err(error),
+            None => Err("readback was never mapped".to_string()),
+        }
+    }
+
+    fn take_data(&self) -> Vec<u8> {
+        let data = self.buffer.slice(..).get_mapped_range()
+            .chunks(self.padded_row_bytes as usize)
+            .flat_map(|row| &row[..self.row_bytes as usize])
+            .copied()
+            .collect();
+        self.buffer.unmap();
+
+        data
+    }
+}
+
+impl std::future::Future for PendingReadback {
+    type Output = Result<Vec<u8>, String>;
+
+    fn poll(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => {
+                drop(state);
+                std::task::Poll::Ready(result.map(|()| self.take_data()))
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                std::task::Poll::Pending
+            }
+        }
+    }
+}
+
+/// Draw `quad` (in [`CoordinateSpace::Ndc`]) with `color` into a fresh, transparent `size`
+/// target of [`Texture::FORMAT`], and read back the pixel at its center.
+///
+/// This is meant for tests: `shader` must draw [`Vertex`]es into a single target of
+/// [`Texture::FORMAT`], so the result goes through the same sRGB encoding as the surface does.
+///
+/// # Panics
+///
+/// If the pass can't be recorded or the pixel can't be read back.
+///
+/// [`CoordinateSpace::Ndc`]: crate::CoordinateSpace::Ndc
+/// [`Vertex`]: crate::Vertex
+pub fn render_single_quad_readback(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    shader: &Shader,
+    quad: &Quad,
+    color: [f32; 3],
+    size: [u32; 2],
+) -> [u8; 4] {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Single Quad Texture"),
+        size: wgpu::Extent3d {
+            width: size[0].max(1),
+            height: size[1].max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: Texture::FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+
+    let mut renderer = Renderer::start();
+    renderer.add_quad(quad, color);
+    let batch = renderer.finish(device);
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Single Quad Encoder"),
+    });
+    render_to(
+        &mut encoder,
+        &[RenderTarget::from_texture(&texture)],
+        Some(wgpu::Color::TRANSPARENT),
+        &[DrawCommand {
+            shader,
+            mesh: &batch.solid,
+            bind_groups: &[],
+            instances: None,
+            scissor: None,
+        }],
+    ).unwrap();
+    queue.submit(std::iter::once(encoder.finish()));
+
+    read_pixel(device, queue, &texture, texture.width() / 2, texture.height() / 2).unwrap()
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    /// A [`testing::target`] of `size` with a quad in the top-left quarter of it.
+    fn quarter_quad(device: &wgpu::Device, queue: &wgpu::Queue, size: [u32; 2]) -> wgpu::Texture {
+        let shader = Shader::solid(device, Texture::FORMAT, 1).unwrap();
+        let mut renderer = Renderer::start();
+        renderer.add_quad(&Quad::new([-1.0, 0.0], [1.0, 1.0]), [1.0, 0.0, 0.0]);
+        let batch = renderer.finish(device);
+
+        let texture = testing::target(device, size);
+        let mut encoder = device.create_command_encoder(&Default::default());
+        render_to(
+            &mut encoder,
+            &[RenderTarget::from_texture(&texture)],
+            Some(wgpu::Color::TRANSPARENT),
+            &[DrawCommand {
+                shader: &shader,
+                mesh: &batch.solid,
+                bind_groups: &[],
+                instances: None,
+                scissor: None,
+            }],
+        ).unwrap();
+        queue.submit(std::iter::once(encoder.finish()));
+
+        texture
+    }
+
+    #[test]
+    fn async_readbacks_match_blocking_ones() {
+        let Some((_adapter, device, queue)) = testing::gpu() else {
+            return;
+        };
+        // NOTE: Rows of 6 texels are padded out to 256 bytes in the copy.
+        let texture = quarter_quad(&device, &queue, [6, 4]);
+        let expected = read_texture(&device, &queue, &texture).unwrap();
+        assert_eq!(expected.len(), 6 * 4 * 4);
+        assert_eq!(expected[..4], [255, 0, 0, 255]);
+        assert_eq!(expected[(6 * 4 - 1) * 4..], [0; 4]);
+
+        let pending = read_texture_async(&device, &queue, &texture).unwrap();
+        for _ in 0..1000 {
+            if pending.is_ready() {
+                break;
+            }
+            device.poll(wgpu::Maintain::Poll);
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert!(pending.is_ready());
+        assert_eq!(pending.wait(&device).unwrap(), expected);
+
+        let pending = read_texture_async(&device, &queue, &texture).unwrap();
+        assert_eq!(pending.wait(&device).unwrap(), expected);
+
+        let pending = read_texture_async(&device, &queue, &texture).unwrap();
+        device.poll(wgpu::Maintain::Wait);
+        assert_eq!(futures::executor::block_on(pending).unwrap(), expected);
+    }
+
+    #[test]
+    fn single_quads_read_back_their_color() {
+        let Some((_adapter, device, queue)) = testing::gpu() else {
+            return;
+        };
+        let shader = Shader::solid(&device, Texture::FORMAT, 1).unwrap();
+        let quad = Quad::new([-0.5, -0.5], [1.0, 1.0]);
+        let read =
+            |color| render_single_quad_readback(&device, &queue, &shader, &quad, color, [8, 8]);
+
+        assert_eq!(read([1.0, 0.0, 0.0]), [255, 0, 0, 255]);
+        assert_eq!(read([0.0, 1.0, 0.0]), [0, 255, 0, 255]);
+        assert_eq!(read([0.0, 0.0, 1.0]), [0, 0, 255, 255]);
+
+        let off_center = Quad::new([0.5, 0.5], [0.5, 0.5]);
+        let pixel =
+            render_single_quad_readback(&device, &queue, &shader, &off_center, [1.0; 3], [8, 8]);
+        assert_eq!(pixel, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn single_quad_colors_are_srgb_encoded() {
+        let Some((_adapter, device, queue)) = testing::gpu() else {
+            return;
+        };
+        let shader = Shader::solid(&device, Texture::FORMAT, 1).unwrap();
+        let quad = Quad::new([-1.0, -1.0], [2.0, 2.0]);
+
+        // NOTE: Linear 0.5 is about 0.735 in sRGB, and 0.2 about 0.485.
+        let [r, g, b, a] =
+            render_single_quad_readback(&device, &queue, &shader, &quad, [0.5, 0.2, 0.0], [4, 4]);
+        assert!(r.abs_diff(188) <= 1, "{r}");
+        assert!(g.abs_diff(124) <= 1, "{g}");
+        assert_eq!([b, a], [0, 255]);
+    }
+}
@@ -0,0 +1,182 @@
+//! Shader files
+
+
+
+use std::path::{Path, PathBuf};
+
+use crate::{Shader, ShaderDescriptor, ShaderError};
+
+
+
+impl Shader {
+    /// Like [`Shader::new`], but with WGSL read from the file at `path` in place of
+    /// [`ShaderDescriptor::source`]. See [`ShaderFile`] to reload it as it changes.
+    pub fn from_file(
+        device: &wgpu::Device,
+        path: impl AsRef<Path>,
+        desc: ShaderDescriptor,
+    ) -> Result<Self, ShaderError> {
+        let path = path.as_ref();
+        let source = read_source(path)?;
+        build_checked(device, path, &source, desc)
+    }
+}
+
+/// A [`Shader`] built from a WGSL file, which can be rebuilt from it while the app runs, for
+/// iterating on shaders without restarting.
+///
+/// Reloads that fail (say, on a typo) keep the last pipeline that built, so drawing carries on
+/// while the file is fixed.
+pub struct ShaderFile<'a> {
+    path: PathBuf,
+    /// Everything but the source, which comes from the file.
+    desc: ShaderDescriptor<'a>,
+    shader: Shader,
+    /// When the file was last changed, as of the last (re)load.
+    modified: Option<std::time::SystemTime>,
+}
+
+impl<'a> ShaderFile<'a> {
+    /// Build a shader from the file at `path`, using `desc` for everything but the source (its
+    /// [`ShaderDescriptor::source`] and [`ShaderDescriptor::spirv`] are ignored).
+    pub fn new(
+        device: &wgpu::Device,
+        path: impl Into<PathBuf>,
+        desc: ShaderDescriptor<'a>,
+    ) -> Result<Self, ShaderError> {
+        let path = path.into();
+        let modified = modified_time(&path);
+        let source = read_source(&path)?;
+        let desc = ShaderDescriptor {
+            source: wgpu::ShaderSource::Dummy(std::marker::PhantomData),
+            spirv: None,
+            ..desc
+        };
+        let shader = build_checked(device, &path, &source, desc.clone())?;
+
+        Ok(Self {
+            path,
+            desc,
+            shader,
+            modified,
+        })
+    }
+
+    /// The last shader that built successfully.
+    pub fn shader(&self) -> &Shader {
+        &self.shader
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read the file again and rebuild the shader from it. On failure, the previous shader is
+    /// kept and the error is returned (for logging, or showing on screen).
+    pub fn reload(&mut self, device: &wgpu::Device) -> Result<(), ShaderError> {
+        // NOTE: The time is updated even if this fails, so that a broken file isn't retried
+        //       (and reported) every frame until it changes again.
+        self.modified = modified_time(&self.path);
+        let source = read_source(&self.path)?;
+        self.shader = build_checked(device, &self.path, &source, self.desc.clone())?;
+
+        Ok(())
+    }
+
+    /// [`ShaderFile::reload`] if the file has been modified since the last (re)load, or return
+    /// `None` if it hasn't. Meant to be polled (every frame, say), which only costs a
+    /// `metadata` call.
+    pub fn reload_if_changed(
+        &mut self,
+        device: &wgpu::Device,
+    ) -> Option<Result<(), ShaderError>> {
+        let modified = modified_time(&self.path);
+        (modified != self.modified).then(|| self.reload(device))
+    }
+}
+
+fn read_source(path: &Path) -> Result<String, ShaderError> {
+    std::fs::read_to_string(path)
+        .map_err(|error| ShaderError::Io(format!("{}: {error}", path.display())))
+}
+
+fn modified_time(path: &Path) -> Option<std::time::SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Build `desc` from `source`, turning compile errors into [`ShaderError::Compile`] instead of
+/// leaving them to wgpu's error handler (which panics by default).
+fn build_checked(
+    device: &wgpu::Device,
+    path: &Path,
+    source: &str,
+    desc: ShaderDescriptor,
+) -> Result<Shader, ShaderError> {
+    // NOTE: Naga's own parse errors point at the offending lines of the file.
+    if let Err(error) = wgpu::naga::front::wgsl::parse_str(source) {
+        return Err(ShaderError::Compile(error.emit_to_string_with_path(source, path)));
+    }
+
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let result = Shader::new(device, ShaderDescriptor {
+        source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(source)),
+        // NOTE: wgpu's errors name the module by its label.
+        label: desc.label.or(path.to_str()),
+        ..desc
+    });
+    match (result, pop_error_scope(device)) {
+        (Err(error), _) => Err(error),
+        (Ok(_), Some(error)) => Err(ShaderError::Compile(error.to_string())),
+        (Ok(shader), None) => Ok(shader),
+    }
+}
+
+/// The error caught by the innermost error scope, if any.
+///
+/// NOTE: Native backends have the result ready straight away, so the future is only polled
+///       once. On the web, where it isn't, errors go uncaught.
+pub(crate) fn pop_error_scope(device: &wgpu::Device) -> Option<wgpu::Error> {
+    let mut future = std::pin::pin!(device.pop_error_scope());
+    let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+    match future.as_mut().poll(&mut context) {
+        std::task::Poll::Ready(error) => error,
+        std::task::Poll::Pending => None,
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SOLID_WGSL, Texture, Vertex, testing};
+
+    #[test]
+    fn broken_reloads_keep_the_last_shader() {
+        let Some((_adapter, device, _queue)) = testing::gpu() else {
+            return;
+        };
+        let path = std::env::temp_dir().join(format!("bog-{}-reload.wgsl", std::process::id()));
+        std::fs::write(&path, SOLID_WGSL).unwrap();
+
+        let vertex_buffers = [Vertex::desc()];
+        let fragment_targets = [Some(wgpu::ColorTargetState::from(Texture::FORMAT))];
+        let mut file = ShaderFile::new(&device, &path, ShaderDescriptor {
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &vertex_buffers,
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &fragment_targets,
+            ..Default::default()
+        }).unwrap();
+        let pipeline = file.shader().pipeline.clone();
+        assert!(file.reload_if_changed(&device).is_none());
+
+        std::fs::write(&path, SOLID_WGSL.replacen("fn ", "fn fn ", 1)).unwrap();
+        let result = file.reload(&device);
+        assert!(matches!(result, Err(ShaderError::Compile(_))), "{result:?}");
+        assert!(file.shader().pipeline == pipeline);
+        assert!(file.reload_if_changed(&device).is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,104 @@
+//! Built-in shaders
+
+
+
+/// Draws [`Vertex`](crate::Vertex)es in their color. Used by
+/// [`Shader::solid`](crate::Shader::solid).
+pub const SOLID_WGSL: &str = include_str!("shaders/solid.wgsl");
+
+/// The quad shader, writing the primitive color to `@location(0)` and a coverage mask to
+/// `@location(1)`. Use it with two fragment targets.
+pub const QUAD_MRT_WGSL: &str = include_str!("shaders/quad_mrt.wgsl");
+
+/// Writes each vertex's pick id to an `R32Uint` target. Used by [`Picker`](crate::Picker).
+pub const PICK_WGSL: &str = include_str!("shaders/pick.wgsl");
+
+/// Simulates (`cs_main`) and draws (`vs_main`/`fs_main`) a [`ParticleSystem`](crate::ParticleSystem).
+pub const PARTICLES_WGSL: &str = include_str!("shaders/particles.wgsl");
+
+/// Draws the [`GradientVertex`](crate::GradientVertex)es of radial gradients.
+pub const RADIAL_GRADIENT_WGSL: &str = include_str!("shaders/radial_gradient.wgsl");
+
+/// Draws the [`LinearGradientVertex`](crate::LinearGradientVertex)es of linear gradients.
+pub const LINEAR_GRADIENT_WGSL: &str = include_str!("shaders/linear_gradient.wgsl");
+
+/// A fullscreen FXAA pass over `color_texture`. Used by [`Fxaa`](crate::Fxaa).
+pub const FXAA_WGSL: &str = concat!(
+    include_str!("shaders/fullscreen_triangle.wgsl"),
+    include_str!("shaders/fxaa.wgsl"),
+);
+
+/// Draws [`TexturedVertex`](crate::TexturedVertex)es, sampling the texture bound at `@group(0)`
+/// and multiplying it by their color.
+pub const TEXTURED_WGSL: &str = include_str!("shaders/textured.wgsl");
+
+/// Draws [`Vertex`](crate::Vertex)es once per [`Transform2D`](crate::Transform2D) instance.
+pub const INSTANCED_WGSL: &str = include_str!("shaders/instanced.wgsl");
+
+/// Draws the line lists of [`Batch::wireframe`](crate::Batch::wireframe) in the inverse of each
+/// vertex's color.
+pub const WIREFRAME_WGSL: &str = include_str!("shaders/wireframe.wgsl");
+
+/// Draws [`TexturedVertex`](crate::TexturedVertex)es in their color, using the red channel of
+/// the mask bound at `@group(0)` as alpha.
+pub const MASK_WGSL: &str = include_str!("shaders/mask.wgsl");
+
+/// Draws the [`CircleVertex`](crate::CircleVertex)es of anti-aliased circles, feathered by the
+/// [`EdgeFeather`](crate::EdgeFeather) bound at `@group(0)`.
+pub const CIRCLE_WGSL: &str = include_str!("shaders/circle.wgsl");
+
+/// Draws [`ClippedVertex`](crate::ClippedVertex)es in their color, discarding (or fading out)
+/// the fragments outside of each one's clip rect.
+pub const CLIPPED_WGSL: &str = include_str!("shaders/clipped.wgsl");
+
+/// One pass of the separable box blur done by [`blur_texture`](crate::blur_texture) (`cs_main`).
+pub const BLUR_WGSL: &str = include_str!("shaders/blur.wgsl");
+
+/// Fades the previous contents of a [`FadingTarget`](crate::FadingTarget) towards its
+/// background, with a single triangle covering the whole target.
+pub const FADE_WGSL: &str = concat!(
+    include_str!("shaders/fullscreen_triangle.wgsl"),
+    include_str!("shaders/fade.wgsl"),
+);
+
+/// Draws [`TextureArrayVertex`](crate::TextureArrayVertex)es, sampling the texture they index in
+/// the binding array of a [`TextureSet`](crate::TextureSet) bound at `@group(0)`.
+pub const TEXTURE_ARRAY_WGSL: &str = include_str!("shaders/texture_array.wgsl");
+
+/// Draws [`Vertex`](crate::Vertex)es in their color, transformed by the
+/// [`ModelMatrix`](crate::ModelMatrix) bound at `@group(0)`.
+pub const SOLID_MODEL_WGSL: &str = include_str!("shaders/solid_model.wgsl");
+
+/// Fills a whole target with a vertical gradient between the two colors of a
+/// [`BackgroundGradient`](crate::BackgroundGradient), with a single triangle and no vertex
+/// buffer.
+pub const BACKGROUND_GRADIENT_WGSL: &str = concat!(
+    include_str!("shaders/fullscreen_triangle.wgsl"),
+    include_str!("shaders/background_gradient.wgsl"),
+);
+
+/// The vertex stage of fullscreen passes: `vs_fullscreen` draws a single triangle covering the
+/// whole target (see [`draw_fullscreen_triangle`](crate::draw_fullscreen_triangle)), passing a
+/// `FullscreenVertex` with the `uv` of each fragment to the fragment stage. The
+/// `fullscreen_triangle` function it's built on can be used by custom vertex stages too.
+///
+/// WGSL has no includes, so put this in front of a fragment shader with
+/// [`fullscreen_wgsl`](crate::fullscreen_wgsl) (or `concat!`).
+pub const FULLSCREEN_TRIANGLE_WGSL: &str = include_str!("shaders/fullscreen_triangle.wgsl");
+
+/// Draws [`Vertex`](crate::Vertex)es like [`SOLID_WGSL`], tinted by the
+/// [`SolidUniforms`](crate::SolidUniforms) bound at `@group(0)`. Used by
+/// [`Shader::solid_uniforms`](crate::Shader::solid_uniforms), and meant to be copied for custom
+/// effects on the same vertices.
+pub const SOLID_UNIFORMS_WGSL: &str = include_str!("shaders/solid_uniforms.wgsl");
+
+/// Draws [`Vertex`](crate::Vertex)es like [`SOLID_WGSL`], but at the depth of their layer (see
+/// [`Renderer::next_layer`](crate::Renderer::next_layer)) instead of the far plane.
+pub const SOLID_LAYERED_WGSL: &str = include_str!("shaders/solid_layered.wgsl");
+
+/// Replaces some channels of a whole target with the color of a
+/// [`ChannelClear`](crate::ChannelClear), leaving the others alone.
+pub const CHANNEL_CLEAR_WGSL: &str = concat!(
+    include_str!("shaders/fullscreen_triangle.wgsl"),
+    include_str!("shaders/channel_clear.wgsl"),
+);
@@ -0,0 +1,15 @@
+// Fragment shader (drawn with `vs_fullscreen`)
+
+struct Colors {
+    top: vec4<f32>,
+    bottom: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> colors: Colors;
+
+@fragment
+fn fs_main(in: FullscreenVertex) -> @location(0) vec4<f32> {
+    // NOTE: `uv.y` goes from `0.0` at the top to `1.0` at the bottom.
+    return mix(colors.top, colors.bottom, clamp(in.uv.y, 0.0, 1.0));
+}
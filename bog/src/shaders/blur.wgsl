@@ -0,0 +1,33 @@
+// One pass of a separable box blur
+
+struct Params {
+    // `(1, 0)` for the horizontal pass, `(0, 1)` for the vertical one.
+    direction: vec2<i32>,
+    radius: i32,
+    _padding: i32,
+};
+
+@group(0) @binding(0)
+var input: texture_2d<f32>;
+@group(0) @binding(1)
+var output: texture_storage_2d<rgba8unorm, write>;
+@group(0) @binding(2)
+var<uniform> params: Params;
+
+@compute @workgroup_size(8, 8)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = vec2<i32>(textureDimensions(input));
+    let pos = vec2<i32>(id.xy);
+    if pos.x >= size.x || pos.y >= size.y {
+        return;
+    }
+
+    // Texels beyond the edges repeat the edge, so the borders don't darken.
+    var sum = vec4<f32>(0.0);
+    for (var i = -params.radius; i <= params.radius; i++) {
+        let texel = clamp(pos + params.direction * i, vec2<i32>(0), size - 1);
+        sum += textureLoad(input, texel, 0);
+    }
+
+    textureStore(output, pos, sum / f32(2 * params.radius + 1));
+}
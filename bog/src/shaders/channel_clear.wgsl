@@ -0,0 +1,10 @@
+// Fragment shader (drawn with `vs_fullscreen`)
+
+@group(0) @binding(0)
+var<uniform> color: vec4<f32>;
+
+// Only the channels in the pipeline's write mask are actually replaced.
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return color;
+}
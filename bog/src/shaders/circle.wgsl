@@ -0,0 +1,65 @@
+// Vertex shader
+
+struct Feather {
+    // The size of the target, in physical pixels.
+    viewport: vec2<f32>,
+    // The width of the anti-aliased band along each edge, in physical pixels.
+    width: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> feather: Feather;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) center: vec2<f32>,
+    @location(2) local: vec2<f32>,
+    @location(3) color: vec3<f32>,
+    @location(4) inner: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) local: vec2<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) inner: f32,
+};
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    // Grow the quad by the feather width, so the outer half of the edge's band isn't cut off.
+    let extent = abs(model.position - model.center);
+    let grow = feather.width * 2.0 / feather.viewport;
+
+    var out: VertexOutput;
+    out.local = model.local * (extent + grow) / max(extent, vec2<f32>(1e-6));
+    out.color = model.color;
+    out.inner = model.inner;
+    out.clip_position = vec4<f32>(
+        model.position + sign(model.position - model.center) * grow,
+        1.0,
+        1.0,
+    );
+    return out;
+}
+
+
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // `local` is the offset from the center, in radii, so this is how far one physical pixel
+    // moves in those units.
+    let r = length(in.local);
+    let band = max(fwidth(r) * feather.width, 1e-6);
+
+    var coverage = clamp(0.5 + (1.0 - r) / band, 0.0, 1.0);
+    if in.inner > 0.0 {
+        coverage *= clamp(0.5 + (r - in.inner) / band, 0.0, 1.0);
+    }
+
+    return vec4<f32>(in.color, coverage);
+}
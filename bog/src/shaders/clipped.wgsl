@@ -0,0 +1,58 @@
+// Vertex shader
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) clip_a: vec2<f32>,
+    @location(3) clip_b: vec2<f32>,
+    @location(4) smooth_edge: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) position: vec2<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) clip_min: vec2<f32>,
+    @location(3) clip_max: vec2<f32>,
+    @location(4) smooth_edge: f32,
+};
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = model.position;
+    out.color = model.color;
+    // The corners can be in either order once they've been flipped into clip space.
+    out.clip_min = min(model.clip_a, model.clip_b);
+    out.clip_max = max(model.clip_a, model.clip_b);
+    out.smooth_edge = model.smooth_edge;
+    out.clip_position = vec4<f32>(model.position, 1.0, 1.0);
+    return out;
+}
+
+
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // How far inside the clip rect this fragment is, along the nearest edge.
+    let inside = min(in.position - in.clip_min, in.clip_max - in.position);
+
+    var coverage = 1.0;
+    if in.smooth_edge > 0.0 {
+        // Fade over the width of one pixel, centered on the edge.
+        let pixel = max(fwidth(in.position), vec2<f32>(1e-6));
+        let edge = clamp(0.5 + inside / pixel, vec2<f32>(0.0), vec2<f32>(1.0));
+        coverage = edge.x * edge.y;
+    } else if inside.x < 0.0 || inside.y < 0.0 {
+        discard;
+    }
+    if coverage <= 0.0 {
+        discard;
+    }
+
+    return vec4<f32>(in.color, coverage);
+}
@@ -0,0 +1,15 @@
+// Fragment shader (drawn with `vs_fullscreen`)
+
+struct FadeParams {
+    color: vec3<f32>,
+    fade: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> params: FadeParams;
+
+// Blended over the previous contents, this moves them `fade` of the way towards `color`.
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(params.color, params.fade);
+}
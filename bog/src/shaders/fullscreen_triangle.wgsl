@@ -0,0 +1,28 @@
+// Fullscreen triangle
+
+struct FullscreenVertex {
+    @builtin(position) clip_position: vec4<f32>,
+    // Where this is in the target, from `(0.0, 0.0)` at the top left to `(1.0, 1.0)` at the
+    // bottom right (like texture coordinates).
+    @location(0) uv: vec2<f32>,
+};
+
+// The corner of a single triangle covering the whole target for the given `vertex_index` (of
+// `0..3`). The triangle winds clockwise.
+fn fullscreen_triangle(index: u32) -> FullscreenVertex {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: FullscreenVertex;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@vertex
+fn vs_fullscreen(
+    @builtin(vertex_index) index: u32,
+) -> FullscreenVertex {
+    return fullscreen_triangle(index);
+}
+
+
+
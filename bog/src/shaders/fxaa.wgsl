@@ -0,0 +1,49 @@
+// Fragment shader (drawn with `vs_fullscreen`)
+
+@group(0) @binding(0)
+var color_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var color_sampler: sampler;
+
+const SPAN_MAX: f32 = 8.0;
+const REDUCE_MUL: f32 = 1.0 / 8.0;
+const REDUCE_MIN: f32 = 1.0 / 128.0;
+const LUMA: vec3<f32> = vec3<f32>(0.299, 0.587, 0.114);
+
+fn sample(uv: vec2<f32>) -> vec3<f32> {
+    return textureSampleLevel(color_texture, color_sampler, uv, 0.0).rgb;
+}
+
+@fragment
+fn fs_main(in: FullscreenVertex) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(color_texture));
+
+    let luma_nw = dot(sample(in.uv + vec2<f32>(-1.0, -1.0) * texel), LUMA);
+    let luma_ne = dot(sample(in.uv + vec2<f32>(1.0, -1.0) * texel), LUMA);
+    let luma_sw = dot(sample(in.uv + vec2<f32>(-1.0, 1.0) * texel), LUMA);
+    let luma_se = dot(sample(in.uv + vec2<f32>(1.0, 1.0) * texel), LUMA);
+    let luma_m = dot(sample(in.uv), LUMA);
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // Blur along the edge, which runs perpendicular to the luma gradient.
+    var dir = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    let rcp_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * rcp_dir_min, vec2<f32>(-SPAN_MAX), vec2<f32>(SPAN_MAX)) * texel;
+
+    let rgb_a = 0.5 * (
+        sample(in.uv + dir * (1.0 / 3.0 - 0.5))
+        + sample(in.uv + dir * (2.0 / 3.0 - 0.5))
+    );
+    let rgb_b = rgb_a * 0.5 + 0.25 * (sample(in.uv - dir * 0.5) + sample(in.uv + dir * 0.5));
+    let luma_b = dot(rgb_b, LUMA);
+
+    // NOTE: If the wider blur picked up colors from outside the local range, it crossed another
+    //       edge, so fall back to the narrower one.
+    let color = select(rgb_b, rgb_a, luma_b < luma_min || luma_b > luma_max);
+    return vec4<f32>(color, 1.0);
+}
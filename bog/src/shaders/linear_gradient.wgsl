@@ -0,0 +1,61 @@
+// Vertex shader
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) local: vec2<f32>,
+    @location(2) axis: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) local: vec2<f32>,
+    @location(1) @interpolate(flat) axis: vec2<f32>,
+};
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.local = model.local;
+    out.axis = model.axis;
+    out.clip_position = vec4<f32>(model.position, 1.0, 1.0);
+    return out;
+}
+
+
+
+// Fragment shader
+
+struct Stops {
+    positions: vec4<f32>,
+    colors: array<vec4<f32>, 4>,
+    count: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> stops: Stops;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Project the fragment (relative to the quad's center) onto the gradient's axis, which is
+    // pre-scaled so that the quad's extent along it maps to `-0.5..=0.5`.
+    let t = dot(in.local, in.axis) + 0.5;
+
+    var color = stops.colors[0].rgb;
+    for (var i = 1u; i < stops.count; i += 1u) {
+        let start = stops.positions[i - 1u];
+        let end = stops.positions[i];
+        if t >= end {
+            color = stops.colors[i].rgb;
+        } else if t > start {
+            color = mix(
+                stops.colors[i - 1u].rgb,
+                stops.colors[i].rgb,
+                (t - start) / max(end - start, 1e-6),
+            );
+        }
+    }
+
+    return vec4<f32>(color, 1.0);
+}
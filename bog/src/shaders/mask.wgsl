@@ -0,0 +1,39 @@
+// Vertex shader
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.uv = model.uv;
+    out.color = model.color;
+    out.clip_position = vec4<f32>(model.position, 1.0, 1.0);
+    return out;
+}
+
+
+
+// Fragment shader
+
+@group(0) @binding(0)
+var mask_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var mask_sampler: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let coverage = textureSample(mask_texture, mask_sampler, in.uv).r;
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
@@ -0,0 +1,96 @@
+struct Particle {
+    position: vec2<f32>,
+    velocity: vec2<f32>,
+    color: vec3<f32>,
+    size: f32,
+};
+
+struct Params {
+    gravity: vec2<f32>,
+    dt: f32,
+    count: u32,
+};
+
+@group(0) @binding(0) var<storage, read_write> particles: array<Particle>;
+@group(0) @binding(1) var<uniform> params: Params;
+
+
+
+// Compute shader
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.count {
+        return;
+    }
+
+    var particle = particles[id.x];
+    particle.velocity += params.gravity * params.dt;
+    particle.position += particle.velocity * params.dt;
+
+    // Bounce off the edges of clip space.
+    if abs(particle.position.x) > 1.0 {
+        particle.position.x = clamp(particle.position.x, -1.0, 1.0);
+        particle.velocity.x = -particle.velocity.x;
+    }
+    if abs(particle.position.y) > 1.0 {
+        particle.position.y = clamp(particle.position.y, -1.0, 1.0);
+        particle.velocity.y = -particle.velocity.y;
+    }
+
+    particles[id.x] = particle;
+}
+
+
+
+// Vertex shader
+
+struct InstanceInput {
+    @location(0) position: vec2<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) size: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+};
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    instance: InstanceInput,
+) -> VertexOutput {
+    // Two triangles covering a square centered on the particle.
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-0.5, -0.5),
+        vec2<f32>(0.5, -0.5),
+        vec2<f32>(-0.5, 0.5),
+        vec2<f32>(-0.5, 0.5),
+        vec2<f32>(0.5, -0.5),
+        vec2<f32>(0.5, 0.5),
+    );
+
+    var out: VertexOutput;
+    out.color = instance.color;
+    out.clip_position = vec4<f32>(
+        instance.position + corners[vertex_index] * instance.size,
+        1.0,
+        1.0,
+    );
+    // Rather than rasterizing zero-area triangles, move (nearly) zero-sized particles entirely
+    // outside of clip space.
+    if instance.size <= 1e-6 {
+        out.clip_position = vec4<f32>(2.0, 2.0, 2.0, 1.0);
+    }
+    return out;
+}
+
+
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(in.color, 1.0);
+}
@@ -0,0 +1,68 @@
+// Vertex shader
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) local: vec2<f32>,
+    @location(2) inner_color: vec3<f32>,
+    @location(3) outer_color: vec3<f32>,
+    @location(4) flags: u32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) local: vec2<f32>,
+    @location(1) inner_color: vec3<f32>,
+    @location(2) outer_color: vec3<f32>,
+    @location(3) @interpolate(flat) flags: u32,
+};
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.local = model.local;
+    out.inner_color = model.inner_color;
+    out.outer_color = model.outer_color;
+    out.flags = model.flags;
+    out.clip_position = vec4<f32>(model.position, 1.0, 1.0);
+    return out;
+}
+
+
+
+// Fragment shader
+
+const GAMMA_CORRECT: u32 = 1u;
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // `local` is the offset from the center, in radii.
+    let t = clamp(length(in.local), 0.0, 1.0);
+
+    var color: vec3<f32>;
+    if (in.flags & GAMMA_CORRECT) != 0u {
+        color = mix(in.inner_color, in.outer_color, t);
+    } else {
+        // Blend the way most design tools do: between the sRGB-encoded colors.
+        color = srgb_to_linear(mix(
+            linear_to_srgb(in.inner_color),
+            linear_to_srgb(in.outer_color),
+            t,
+        ));
+    }
+
+    return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,42 @@
+
+
+
+// Vertex shader
+
+struct ModelUniform {
+    matrix: mat4x4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> model: ModelUniform;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(
+    vertex: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.color = vertex.color;
+    let position = model.matrix * vec4<f32>(vertex.position, 0.0, 1.0);
+    // NOTE: Like `solid.wgsl`, everything is drawn at the far plane.
+    out.clip_position = vec4<f32>(position.xy, position.w, position.w);
+    return out;
+}
+
+
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
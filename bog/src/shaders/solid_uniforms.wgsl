@@ -0,0 +1,46 @@
+// Uniforms
+
+// Bound at `@group(0)`. Copies of this shader can add fields after `time` (or bind more groups)
+// for their own effects, as long as the layouts they're built with match.
+struct SolidUniforms {
+    // Multiplies the color of every vertex.
+    tint: vec4<f32>,
+    // Seconds since some point of the app's choosing, for animations.
+    time: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> uniforms: SolidUniforms;
+
+
+
+// Vertex shader
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.color = model.color * uniforms.tint;
+    out.clip_position = vec4<f32>(model.position, 1.0, 1.0);
+    return out;
+}
+
+
+
+// Fragment shader
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
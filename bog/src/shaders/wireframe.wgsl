@@ -1,6 +1,3 @@
-
-
-
 // Vertex shader
 
 struct VertexInput {
@@ -18,7 +15,8 @@ fn vs_main(
     model: VertexInput,
 ) -> VertexOutput {
     var out: VertexOutput;
-    out.color = model.color;
+    // NOTE: Inverting the fill color keeps the outline visible on top of it.
+    out.color = vec3<f32>(1.0) - model.color;
     out.clip_position = vec4<f32>(model.position, 1.0, 1.0);
     return out;
 }
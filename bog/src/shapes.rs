@@ -0,0 +1,816 @@
+//! Shape primitives
+
+
+
+use crate::{CoordinateSpace, Quad, Renderer, Texture, TexturedVertex, Vertex};
+
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PolygonError {
+    /// A polygon needs at least three points; this many were given.
+    TooFewPoints(usize),
+    /// No more triangles could be clipped off, so the polygon crosses (or touches) itself.
+    NotSimple,
+}
+
+impl std::fmt::Display for PolygonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooFewPoints(count) => {
+                write!(f, "a polygon needs at least 3 points, but {count} were given")
+            }
+            Self::NotSimple => write!(f, "the polygon intersects itself"),
+        }
+    }
+}
+
+impl std::error::Error for PolygonError {}
+
+/// How the ends of open strokes are drawn (see [`Renderer::set_stroke_cap`]).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum StrokeCap {
+    /// End exactly where the stroke does.
+    #[default]
+    Flat,
+    /// Extend each end by a half-disc as wide as the stroke.
+    Round,
+}
+
+/// Which way a [`Renderer::add_chevron`] points.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    /// Towards the top of the target, whichever way Y points in the renderer's space.
+    Up,
+    /// Towards the bottom of the target.
+    Down,
+    Left,
+    Right,
+    /// An angle in radians, counter-clockwise on the target from [`Direction::Right`], so
+    /// `Angle(FRAC_PI_2)` is [`Direction::Up`] whichever way Y points in the renderer's space.
+    Angle(f32),
+}
+
+impl Direction {
+    /// The unit vector pointing this way in `space`.
+    pub fn vector(self, space: CoordinateSpace) -> [f32; 2] {
+        let up = match space {
+            CoordinateSpace::Ndc => 1.0,
+            CoordinateSpace::Pixels { .. } => -1.0,
+        };
+        match self {
+            Self::Up => [0.0, up],
+            Self::Down => [0.0, -up],
+            Self::Left => [-1.0, 0.0],
+            Self::Right => [1.0, 0.0],
+            Self::Angle(angle) => {
+                let (sin, cos) = angle.sin_cos();
+                [cos, sin * up]
+            }
+        }
+    }
+}
+
+/// Twice the signed area of the polygon. Positive when its points wind counter-clockwise (with
+/// Y pointing up).
+pub(crate) fn signed_area_doubled(points: &[[f32; 2]]) -> f32 {
+    let mut area = 0.0;
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        area += a[0] * b[1] - b[0] * a[1];
+    }
+
+    area
+}
+
+/// Twice the signed area of the triangle `abc`. Positive when it turns counter-clockwise.
+fn cross(a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> f32 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+/// Whether any two edges of the polygon that don't share a point cross each other.
+fn has_crossing_edges(points: &[[f32; 2]]) -> bool {
+    let count = points.len();
+    let edge = |i: usize| (points[i], points[(i + 1) % count]);
+
+    (0..count).any(|i| {
+        // NOTE: The last edge shares a point with the first, so it's skipped for `i == 0`.
+        (i + 2..count - usize::from(i == 0)).any(|j| {
+            let ((a, b), (c, d)) = (edge(i), edge(j));
+            cross(a, b, c) * cross(a, b, d) < 0.0 && cross(c, d, a) * cross(c, d, b) < 0.0
+        })
+    })
+}
+
+/// Triangulate a simple (possibly concave) polygon by ear clipping, returning the indices of
+/// `n - 2` triangles into `points` (fewer when some points are collinear with their neighbors).
+///
+/// The points may wind either way, and the triangles always wind like [`Quad`]'s. The polygon
+/// can't have holes, and it **must not** cross or touch itself. Crossing edges are reported as
+/// [`PolygonError::NotSimple`], but a polygon that only touches itself is only caught when that
+/// stops the clipping; otherwise the triangles are wrong.
+///
+/// NOTE: Every edge is checked against every other, and each ear against every remaining point,
+///       so this is quadratic (cubic at worst) in the number of points. That's plenty for UI
+///       shapes, but not for huge outlines.
+pub fn triangulate_polygon(points: &[[f32; 2]]) -> Result<Vec<u32>, PolygonError> {
+    if points.len() < 3 {
+        return Err(PolygonError::TooFewPoints(points.len()));
+    }
+    if has_crossing_edges(points) {
+        return Err(PolygonError::NotSimple);
+    }
+
+    // Clip counter-clockwise (with Y pointing up), so convex corners turn left.
+    let mut remaining: Vec<u32> = (0..points.len() as u32).collect();
+    if signed_area_doubled(points) < 0.0 {
+        remaining.reverse();
+    }
+
+    let mut indices = Vec::with_capacity((points.len() - 2) * 3);
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let corner = |i: usize| {
+            [remaining[(i + count - 1) % count], remaining[i], remaining[(i + 1) % count]]
+        };
+        let is_ear = |i: usize| {
+            let corner = corner(i);
+            let [a, b, c] = corner.map(|index| points[index as usize]);
+            cross(a, b, c) > 0.0 && !remaining.iter().any(|index| {
+                let p = points[*index as usize];
+                !corner.contains(index)
+                    && cross(a, b, p) >= 0.0
+                    && cross(b, c, p) >= 0.0
+                    && cross(c, a, p) >= 0.0
+            })
+        };
+
+        if let Some(i) = (0..count).find(|&i| {
+            let [a, b, c] = corner(i).map(|index| points[index as usize]);
+            cross(a, b, c) == 0.0
+        }) {
+            // NOTE: A point on the line between its neighbors adds no area, so it's dropped
+            //       without a triangle.
+            remaining.remove(i);
+        } else if let Some(i) = (0..count).find(|&i| is_ear(i)) {
+            indices.extend(corner(i));
+            remaining.remove(i);
+        } else {
+            return Err(PolygonError::NotSimple);
+        }
+    }
+    indices.extend_from_slice(&remaining);
+
+    Ok(indices)
+}
+
+impl Renderer {
+    /// The number of segments [`Renderer::add_ring`] splits its band into.
+    pub const RING_SEGMENTS: u32 = 64;
+
+    /// Fill a convex polygon, triangulated as a fan around its first point.
+    ///
+    /// The points may wind either way, but they **must** describe a convex polygon; anything
+    /// else is drawn incorrectly (see [`Renderer::add_polygon`] for those).
+    pub fn add_convex_polygon(
+        &mut self,
+        points: &[[f32; 2]],
+        color: [f32; 3],
+    ) -> Result<(), PolygonError> {
+        if points.len() < 3 {
+            return Err(PolygonError::TooFewPoints(points.len()));
+        }
+
+        self.reserve(points.len(), (points.len() - 2) * 3);
+
+        // Emit triangles with the same winding as `Quad`, whichever way the points go.
+        let flip = signed_area_doubled(points) < 0.0;
+        self.add_mesh(
+            points.iter().map(|&point| Vertex::new(point, color)),
+            (1..points.len() as u32 - 1).flat_map(|i| {
+                if flip { [0, i + 1, i] } else { [0, i, i + 1] }
+            }),
+        );
+
+        Ok(())
+    }
+
+    /// Fill a simple (possibly concave) polygon, like a speech bubble, triangulated with
+    /// [`triangulate_polygon`]. Nothing is drawn if that fails.
+    ///
+    /// See [`Renderer::add_convex_polygon`] for a cheaper fan over convex polygons.
+    pub fn add_polygon(
+        &mut self,
+        points: &[[f32; 2]],
+        color: [f32; 3],
+    ) -> Result<(), PolygonError> {
+        let indices = triangulate_polygon(points)?;
+
+        self.reserve(points.len(), indices.len());
+        self.add_mesh(points.iter().map(|&point| Vertex::new(point, color)), indices);
+
+        Ok(())
+    }
+
+    /// Fill the band between `inner_radius` and `outer_radius` around `center`, with
+    /// [`Renderer::RING_SEGMENTS`] segments. Nothing is drawn unless `inner_radius` is smaller.
+    pub fn add_ring(
+        &mut self,
+        center: [f32; 2],
+        inner_radius: f32,
+        outer_radius: f32,
+        color: [f32; 3],
+    ) {
+        self.add_ring_with_segments(center, inner_radius, outer_radius, Self::RING_SEGMENTS, color);
+    }
+
+    /// Like [`Renderer::add_ring`], but with the band split into `segments` (at least `3`)
+    /// quads.
+    pub fn add_ring_with_segments(
+        &mut self,
+        center: [f32; 2],
+        inner_radius: f32,
+        outer_radius: f32,
+        segments: u32,
+        color: [f32; 3],
+    ) {
+        let inner_radius = inner_radius.max(0.0);
+        if inner_radius >= outer_radius {
+            return;
+        }
+        let segments = segments.max(3);
+
+        self.reserve(segments as usize * 2, segments as usize * 6);
+
+        // Alternate outer and inner points, going counter-clockwise (with Y pointing up).
+        let vertices = (0..segments).flat_map(|i| {
+            let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+            let (sin, cos) = angle.sin_cos();
+            [outer_radius, inner_radius].map(|radius| {
+                Vertex::new([center[0] + cos * radius, center[1] + sin * radius], color)
+            })
+        });
+        self.add_mesh(
+            vertices,
+            (0..segments).flat_map(|i| {
+                let [outer, inner] = [i * 2, i * 2 + 1];
+                let [next_outer, next_inner] = [(i + 1) % segments * 2, (i + 1) % segments * 2 + 1];
+                [inner, outer, next_outer, inner, next_outer, next_inner]
+            }),
+        );
+    }
+
+    /// Choose how the ends of open strokes (like [`Renderer::add_arc_stroke`]s) are drawn from
+    /// now on. Defaults to [`StrokeCap::Flat`].
+    pub fn set_stroke_cap(&mut self, cap: StrokeCap) {
+        self.stroke_cap = cap;
+    }
+
+    pub fn stroke_cap(&self) -> StrokeCap {
+        self.stroke_cap
+    }
+
+    /// Stroke the arc of the circle of `radius` around `center` from `start_angle` to
+    /// `end_angle`, `thickness` wide (centered on the circle), with
+    /// [`Renderer::stroke_cap`]s at its ends. Useful for circular progress bars.
+    ///
+    /// The angles are in radians, counter-clockwise from the positive X axis (with Y pointing
+    /// up). The arc always goes counter-clockwise from `start_angle`, so an `end_angle` before it
+    /// wraps around (drawing from 350° to 10° is a 20° arc). Arcs spanning a full turn or more are
+    /// drawn as a closed ring without caps, and nothing is drawn when the angles are equal.
+    pub fn add_arc_stroke(
+        &mut self,
+        center: [f32; 2],
+        radius: f32,
+        thickness: f32,
+        start_angle: f32,
+        end_angle: f32,
+        color: [f32; 3],
+    ) {
+        use std::f32::consts::{PI, TAU};
+
+        let half = thickness.max(0.0) / 2.0;
+        let [inner_radius, outer_radius] = [(radius - half).max(0.0), radius + half];
+        let sweep = end_angle - start_angle;
+        let sweep = if sweep.abs() >= TAU { TAU } else { sweep.rem_euclid(TAU) };
+        if inner_radius >= outer_radius || sweep == 0.0 {
+            return;
+        }
+        let closed = sweep == TAU;
+        let segments = ((sweep / TAU * Self::RING_SEGMENTS as f32).ceil() as u32).max(1);
+
+        self.reserve((segments as usize + 1) * 2, segments as usize * 6);
+
+        // Alternate outer and inner points, going counter-clockwise (with Y pointing up).
+        let vertices = (0..=segments).flat_map(|i| {
+            let angle = start_angle + i as f32 / segments as f32 * sweep;
+            let (sin, cos) = angle.sin_cos();
+            [outer_radius, inner_radius].map(|radius| {
+                Vertex::new([center[0] + cos * radius, center[1] + sin * radius], color)
+            })
+        });
+        self.add_mesh(
+            vertices,
+            (0..segments).flat_map(|i| {
+                let [outer, inner, next_outer, next_inner] = [0, 1, 2, 3].map(|j| i * 2 + j);
+                [inner, outer, next_outer, inner, next_outer, next_inner]
+            }),
+        );
+
+        if self.stroke_cap == StrokeCap::Round && !closed {
+            let end_angle = start_angle + sweep;
+            for (angle, cap_sweep) in [(start_angle, -PI), (end_angle, PI)] {
+                let (sin, cos) = angle.sin_cos();
+                let cap_center = [center[0] + cos * radius, center[1] + sin * radius];
+                self.add_fan(cap_center, half, angle, cap_sweep, Self::RING_SEGMENTS / 4, color);
+            }
+        }
+    }
+
+    /// Fill the slice of the disc of `radius` around `center` from `start_angle`, `sweep` radians
+    /// (either way) around, as a fan of `segments` triangles winding like [`Quad`]'s.
+    fn add_fan(
+        &mut self,
+        center: [f32; 2],
+        radius: f32,
+        start_angle: f32,
+        sweep: f32,
+        segments: u32,
+        color: [f32; 3],
+    ) {
+        self.reserve(segments as usize + 2, segments as usize * 3);
+
+        let points = (0..=segments).map(|i| {
+            let (sin, cos) = (start_angle + i as f32 / segments as f32 * sweep).sin_cos();
+            Vertex::new([center[0] + cos * radius, center[1] + sin * radius], color)
+        });
+        let flip = sweep < 0.0;
+        self.add_mesh(
+            std::iter::once(Vertex::new(center, color)).chain(points),
+            (1..=segments).flat_map(|i| if flip { [0, i + 1, i] } else { [0, i, i + 1] }),
+        );
+    }
+
+    /// Draw an arrow from `from` to `to`: a shaft `thickness` wide, ending in a triangular head
+    /// that's `head_size` long and wide with its tip at `to`. Arrows shorter than their head are
+    /// all head.
+    pub fn add_arrow(
+        &mut self,
+        from: [f32; 2],
+        to: [f32; 2],
+        thickness: f32,
+        head_size: f32,
+        color: [f32; 3],
+    ) {
+        let delta = [to[0] - from[0], to[1] - from[1]];
+        let length = delta[0].hypot(delta[1]);
+        if length <= f32::EPSILON {
+            return;
+        }
+        let direction = [delta[0] / length, delta[1] / length];
+        let normal = [-direction[1], direction[0]];
+        let offset = |point: [f32; 2], along: f32, across: f32| {
+            [
+                point[0] + direction[0] * along + normal[0] * across,
+                point[1] + direction[1] * along + normal[1] * across,
+            ]
+        };
+
+        let head_size = head_size.clamp(0.0, length);
+        let shaft_length = length - head_size;
+        let half = thickness.max(0.0) / 2.0;
+        if shaft_length > 0.0 && half > 0.0 {
+            // NOTE: These are convex, so the results can't be errors.
+            let _ = self.add_convex_polygon(&[
+                offset(from, 0.0, -half),
+                offset(from, shaft_length, -half),
+                offset(from, shaft_length, half),
+                offset(from, 0.0, half),
+            ], color);
+        }
+        if head_size > 0.0 {
+            let _ = self.add_convex_polygon(&[
+                offset(to, -head_size, -head_size / 2.0),
+                to,
+                offset(to, -head_size, head_size / 2.0),
+            ], color);
+        }
+    }
+
+    /// Draw a right-angled V spanning the `size` square around `center`, with its tip pointing
+    /// in `direction` and arms `thickness` wide (at most a quarter of `size`), like the
+    /// disclosure indicators of tree views.
+    pub fn add_chevron(
+        &mut self,
+        center: [f32; 2],
+        size: f32,
+        direction: Direction,
+        thickness: f32,
+        color: [f32; 3],
+    ) {
+        if size <= 0.0 || thickness <= 0.0 {
+            return;
+        }
+        let direction = direction.vector(self.space());
+        let normal = [-direction[1], direction[0]];
+        let offset = |along: f32, across: f32| {
+            [
+                center[0] + direction[0] * along + normal[0] * across,
+                center[1] + direction[1] * along + normal[1] * across,
+            ]
+        };
+
+        // The tip is a quarter of the way from the center to the edge it points at. Each arm's
+        // outline is offset by `half` along its normal (which is diagonal), so the miter at the
+        // tip sits `half * √2` from it, and the arms are shortened by `half / √2` so that their
+        // ends stay inside the square.
+        let half = thickness.min(size / 4.0) / 2.0;
+        let quarter = size / 4.0;
+        let diagonal = half * std::f32::consts::FRAC_1_SQRT_2;
+        let miter = half * std::f32::consts::SQRT_2;
+        let arm = size / 2.0 - diagonal;
+        let outer_tip = offset(quarter + miter, 0.0);
+        let inner_tip = offset(quarter - miter, 0.0);
+        for side in [-1.0, 1.0] {
+            let _ = self.add_convex_polygon(&[
+                outer_tip,
+                offset(quarter - arm + diagonal, side * (arm + diagonal)),
+                offset(quarter - arm - diagonal, side * (arm - diagonal)),
+                inner_tip,
+            ], color);
+        }
+    }
+
+    /// Draw a line exactly one physical pixel wide from the pixel containing `start` to the one
+    /// containing `end` (both included), for crisp borders and separators at any scale factor.
+    ///
+    /// `scale_factor` is the number of physical pixels per unit of [`CoordinateSpace::Pixels`]
+    /// (the window's scale factor, for logical pixels), whether or not
+    /// [`Renderer::set_pixel_snap`] is on. The ends are moved to the centers of their pixels, so
+    /// horizontal and vertical hairlines cover whole rows and columns of them. Other angles can't
+    /// be crisp, but still come out one pixel wide. This only works in
+    /// [`CoordinateSpace::Pixels`], and draws nothing in [`CoordinateSpace::Ndc`] (whose pixel
+    /// size isn't known) or for a `scale_factor` that isn't positive.
+    pub fn add_hairline(
+        &mut self,
+        start: [f32; 2],
+        end: [f32; 2],
+        scale_factor: f32,
+        color: [f32; 3],
+    ) {
+        if !self.space().is_y_down() || scale_factor.is_nan() || scale_factor <= 0.0 {
+            return;
+        }
+        let scale = scale_factor;
+        let center = |v: f32| ((v * scale).floor() + 0.5) / scale;
+        let [start, end] = [start.map(center), end.map(center)];
+
+        let delta = [end[0] - start[0], end[1] - start[1]];
+        let length = delta[0].hypot(delta[1]);
+        let half = 0.5 / scale;
+        let [dx, dy] = if length > 0.0 {
+            [delta[0] / length * half, delta[1] / length * half]
+        } else {
+            [half, 0.0]
+        };
+
+        // NOTE: The ends are extended by half a pixel, so that they cover their pixels fully.
+        //       This is convex, so the result can't be an error.
+        let _ = self.add_convex_polygon(&[
+            [start[0] - dx + dy, start[1] - dy - dx],
+            [end[0] + dx + dy, end[1] + dy - dx],
+            [end[0] + dx - dy, end[1] + dy + dx],
+            [start[0] - dx - dy, start[1] - dy + dx],
+        ], color);
+    }
+
+    /// Draw a line from `start` to `end`, filled with `pattern` repeated along its length.
+    ///
+    /// Each repeat of the pattern spans `scale` units of the line's length, and the pattern's
+    /// height spans its `thickness`. The pattern's sampler should repeat horizontally (see
+    /// [`SamplerConfig::repeat`](crate::SamplerConfig::repeat)).
+    ///
+    /// These are collected into [`Batch::textured`](crate::Batch::textured).
+    pub fn add_patterned_line(
+        &mut self,
+        start: [f32; 2],
+        end: [f32; 2],
+        thickness: f32,
+        pattern: &Texture,
+        scale: f32,
+    ) {
+        let delta = [end[0] - start[0], end[1] - start[1]];
+        let length = delta[0].hypot(delta[1]);
+        if length <= f32::EPSILON || thickness <= 0.0 || scale <= 0.0 {
+            return;
+        }
+
+        let half = thickness * 0.5 / length;
+        let offset = [-delta[1] * half, delta[0] * half];
+        let repeats = length / scale;
+
+        let geometry = self.textured_geometry(&pattern.view, &pattern.sampler);
+        geometry.add(
+            [
+                TexturedVertex::new([start[0] + offset[0], start[1] + offset[1]], [0.0, 0.0]),
+                TexturedVertex::new([end[0] + offset[0], end[1] + offset[1]], [repeats, 0.0]),
+                TexturedVertex::new([start[0] - offset[0], start[1] - offset[1]], [0.0, 1.0]),
+                TexturedVertex::new([end[0] - offset[0], end[1] - offset[1]], [repeats, 1.0]),
+            ],
+            Quad::indices_u32(),
+        );
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DrawCommand, Shader, testing};
+
+    const L_SHAPE: [[f32; 2]; 6] = [
+        [0.0, 0.0],
+        [2.0, 0.0],
+        [2.0, 1.0],
+        [1.0, 1.0],
+        [1.0, 2.0],
+        [0.0, 2.0],
+    ];
+
+    fn triangles(points: &[[f32; 2]], indices: &[u32]) -> Vec<[[f32; 2]; 3]> {
+        indices
+            .chunks(3)
+            .map(|triangle| [0, 1, 2].map(|i| points[triangle[i] as usize]))
+            .collect()
+    }
+
+    #[test]
+    fn concave_polygons_are_fully_triangulated() {
+        let indices = triangulate_polygon(&L_SHAPE).unwrap();
+        assert_eq!(indices.len(), 3 * (L_SHAPE.len() - 2));
+
+        let area: f32 = triangles(&L_SHAPE, &indices)
+            .iter()
+            .map(|&[a, b, c]| cross(a, b, c) * 0.5)
+            .sum();
+        assert_eq!(area, 3.0);
+    }
+
+    #[test]
+    fn either_winding_gives_counter_clockwise_triangles() {
+        let mut clockwise = L_SHAPE;
+        clockwise.reverse();
+
+        for points in [L_SHAPE, clockwise] {
+            let indices = triangulate_polygon(&points).unwrap();
+            for [a, b, c] in triangles(&points, &indices) {
+                assert!(cross(a, b, c) > 0.0, "{a:?} {b:?} {c:?} winds clockwise");
+            }
+        }
+    }
+
+    #[test]
+    fn collinear_points_are_dropped() {
+        let points = [[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]];
+        let indices = triangulate_polygon(&points).unwrap();
+
+        assert_eq!(indices.len(), 6);
+        for [a, b, c] in triangles(&points, &indices) {
+            assert_ne!(cross(a, b, c), 0.0);
+        }
+    }
+
+    #[test]
+    fn too_few_points() {
+        assert_eq!(triangulate_polygon(&[]), Err(PolygonError::TooFewPoints(0)));
+        assert_eq!(
+            triangulate_polygon(&[[0.0, 0.0], [1.0, 1.0]]),
+            Err(PolygonError::TooFewPoints(2)),
+        );
+    }
+
+    fn positions(renderer: &Renderer) -> Vec<[f32; 2]> {
+        renderer.vertices().iter().map(|vertex| vertex.pos).collect()
+    }
+
+    fn close(a: [f32; 2], b: [f32; 2]) -> bool {
+        (a[0] - b[0]).abs() + (a[1] - b[1]).abs() < 1e-6
+    }
+
+    fn assert_contains(points: &[[f32; 2]], expected: &[[f32; 2]]) {
+        for &pos in expected {
+            assert!(points.iter().any(|&p| close(p, pos)), "{pos:?} is missing from {points:?}");
+        }
+    }
+
+    #[test]
+    fn arrows_have_a_shaft_and_a_head() {
+        let mut renderer = Renderer::start();
+        renderer.add_arrow([0.0, 0.0], [0.5, 0.0], 0.1, 0.2, [1.0; 3]);
+        let points = positions(&renderer);
+
+        assert_eq!(points.len(), 7);
+        assert_contains(&points[..4], &[[0.0, -0.05], [0.3, -0.05], [0.3, 0.05], [0.0, 0.05]]);
+        assert_contains(&points[4..], &[[0.3, -0.1], [0.5, 0.0], [0.3, 0.1]]);
+
+        let mut renderer = Renderer::start();
+        renderer.add_arrow([0.0, 0.0], [0.0, 0.1], 0.1, 0.2, [1.0; 3]);
+        let points = positions(&renderer);
+        assert_eq!(points.len(), 3);
+        assert_contains(&points, &[[0.05, 0.0], [0.0, 0.1], [-0.05, 0.0]]);
+
+        renderer.add_arrow([0.0, 0.0], [0.0, 0.0], 0.1, 0.2, [1.0; 3]);
+        assert_eq!(renderer.vertices().len(), 3);
+    }
+
+    #[test]
+    fn angles_point_like_directions_in_either_space() {
+        use std::f32::consts::{FRAC_PI_2, PI};
+
+        for space in [CoordinateSpace::Ndc, CoordinateSpace::Pixels { width: 8.0, height: 8.0 }] {
+            for (angle, direction) in [
+                (0.0, Direction::Right),
+                (FRAC_PI_2, Direction::Up),
+                (PI, Direction::Left),
+                (-FRAC_PI_2, Direction::Down),
+            ] {
+                let [a, b] = [Direction::Angle(angle), direction].map(|d| d.vector(space));
+                assert!(close(a, b), "{angle} points at {a:?} instead of {b:?} in {space:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn chevrons_fit_their_square_and_point_their_way() {
+        let center = [4.0, 4.0];
+        for space in [CoordinateSpace::Ndc, CoordinateSpace::Pixels { width: 8.0, height: 8.0 }] {
+            for direction in [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+                Direction::Angle(1.0),
+            ] {
+                let mut renderer = Renderer::with_space(space);
+                renderer.add_chevron(center, 2.0, direction, 0.4, [1.0; 3]);
+                let points = positions(&renderer);
+                assert_eq!(points.len(), 8);
+
+                for pos in &points {
+                    assert!(
+                        (0..2).all(|axis| (pos[axis] - center[axis]).abs() <= 1.0 + 1e-6),
+                        "{pos:?} is outside the square of {direction:?} in {space:?}",
+                    );
+                }
+                // NOTE: The tip is the furthest point the way the chevron points.
+                let [x, y] = direction.vector(space);
+                let along = |pos: &[f32; 2]| (pos[0] - center[0]) * x + (pos[1] - center[1]) * y;
+                let tip = points.iter().map(along).fold(f32::MIN, f32::max);
+                assert!(tip > 0.0 && points.iter().all(|pos| along(pos) > -0.5 - 1e-6));
+            }
+        }
+    }
+
+    fn arc(cap: StrokeCap, start_angle: f32, end_angle: f32) -> Renderer {
+        let mut renderer = Renderer::start();
+        renderer.set_stroke_cap(cap);
+        renderer.add_arc_stroke([0.0, 0.0], 0.5, 0.1, start_angle, end_angle, [1.0; 3]);
+        renderer
+    }
+
+    #[test]
+    fn arc_strokes_wrap_around() {
+        let degrees = |angle: f32| angle.to_radians();
+        let wrapped = positions(&arc(StrokeCap::Flat, degrees(350.0), degrees(10.0)));
+        let unwrapped = positions(&arc(StrokeCap::Flat, degrees(350.0), degrees(370.0)));
+
+        // NOTE: 20° is 3.6 of the ring's 64 segments, so it takes 4 (and 5 pairs of points).
+        assert_eq!(wrapped.len(), 10);
+        assert_contains(&wrapped, &unwrapped);
+        for [x, y] in wrapped {
+            assert!(y.atan2(x).abs() <= degrees(10.0) + 1e-6, "{:?}", [x, y]);
+        }
+    }
+
+    #[test]
+    fn arc_strokes_are_clamped_to_a_full_turn() {
+        use std::f32::consts::TAU;
+
+        for end_angle in [TAU, 3.0 * TAU, -TAU, -2.5 * TAU] {
+            let renderer = arc(StrokeCap::Round, 0.0, end_angle);
+            // NOTE: Closed rings don't have caps.
+            assert_eq!(renderer.vertices().len(), (Renderer::RING_SEGMENTS as usize + 1) * 2);
+            assert_eq!(renderer.indices().len(), Renderer::RING_SEGMENTS as usize * 6);
+        }
+    }
+
+    #[test]
+    fn equal_angles_draw_nothing() {
+        for cap in [StrokeCap::Flat, StrokeCap::Round] {
+            assert!(arc(cap, 1.0, 1.0).vertices().is_empty());
+        }
+    }
+
+    #[test]
+    fn round_caps_extend_arc_strokes() {
+        use std::f32::consts::FRAC_PI_2;
+
+        // NOTE: A quarter turn is 16 segments, and each cap a fan of another 16.
+        let flat = positions(&arc(StrokeCap::Flat, 0.0, FRAC_PI_2));
+        let round = positions(&arc(StrokeCap::Round, 0.0, FRAC_PI_2));
+        assert_eq!(flat.len(), 17 * 2);
+        assert_eq!(round.len(), flat.len() + 2 * (16 + 2));
+        assert_eq!(round[..flat.len()], flat[..]);
+
+        // NOTE: The arc starts on the X axis and ends on the Y axis, which the caps stick out
+        //       past by half its thickness.
+        let min = |points: &[[f32; 2]]| {
+            points.iter().fold([f32::MAX; 2], |min, pos| [min[0].min(pos[0]), min[1].min(pos[1])])
+        };
+        assert!(close(min(&flat), [0.0, 0.0]), "{:?}", min(&flat));
+        assert!(close(min(&round), [-0.05, -0.05]), "{:?}", min(&round));
+    }
+
+    #[test]
+    fn fans_wind_like_quads_either_way() {
+        let winding = |renderer: &Renderer| {
+            let points = positions(renderer);
+            triangles(&points, renderer.indices())
+                .into_iter()
+                .map(|[a, b, c]| cross(a, b, c).signum())
+                .collect::<Vec<f32>>()
+        };
+        let mut quad = Renderer::start();
+        quad.add_quad(&Quad::new([0.0, 0.0], [0.5, 0.5]), [1.0; 3]);
+        let quad = winding(&quad)[0];
+
+        for sweep in [1.0, -1.0] {
+            let mut renderer = Renderer::start();
+            renderer.add_fan([0.0, 0.0], 0.5, 0.0, sweep, 4, [1.0; 3]);
+            assert_eq!(renderer.vertices().len(), 4 + 2);
+            assert_eq!(winding(&renderer), [quad; 4]);
+        }
+    }
+
+    /// The rows and columns of a [`testing::render`] of `size` with anything drawn in them.
+    fn covered(texels: &[u8], size: [u32; 2]) -> (Vec<u32>, Vec<u32>) {
+        let mut rows = Vec::new();
+        let mut columns = Vec::new();
+        for (i, texel) in texels.chunks(4).enumerate() {
+            let [x, y] = [i as u32 % size[0], i as u32 / size[0]];
+            if texel[3] != 0 {
+                rows.push(y);
+                columns.push(x);
+            }
+        }
+        rows.sort_unstable();
+        rows.dedup();
+        columns.sort_unstable();
+        columns.dedup();
+
+        (rows, columns)
+    }
+
+    #[test]
+    fn hairlines_cover_one_physical_pixel() {
+        let Some((_adapter, device, queue)) = testing::gpu() else {
+            return;
+        };
+        let shader = Shader::solid(&device, Texture::FORMAT, 1).unwrap();
+
+        for scale in [1.0, 2.0] {
+            let space = CoordinateSpace::Pixels { width: 8.0, height: 8.0 };
+            let mut horizontal = Renderer::with_space(space);
+            let mut vertical = Renderer::with_space(space);
+            horizontal.add_hairline([1.0, 3.2], [6.0, 3.2], scale, [1.0; 3]);
+            vertical.add_hairline([4.7, 1.0], [4.7, 6.0], scale, [1.0; 3]);
+
+            let size = [(8.0 * scale) as u32; 2];
+            let render = |renderer: Renderer| {
+                let batch = renderer.finish(&device);
+                testing::render(&device, &queue, size, &[DrawCommand {
+                    shader: &shader,
+                    mesh: &batch.solid,
+                    bind_groups: &[],
+                    instances: None,
+                    scissor: None,
+                }])
+            };
+
+            let (rows, columns) = covered(&render(horizontal), size);
+            assert_eq!(rows, [(3.2 * scale) as u32], "scale {scale}");
+            assert_eq!(columns.len(), (6.0 * scale) as usize - (1.0 * scale) as usize + 1);
+
+            let (rows, columns) = covered(&render(vertical), size);
+            assert_eq!(columns, [(4.7 * scale) as u32], "scale {scale}");
+            assert_eq!(rows.len(), (6.0 * scale) as usize - (1.0 * scale) as usize + 1);
+        }
+    }
+
+    #[test]
+    fn bow_ties_are_not_simple() {
+        let points = [[0.0, 0.0], [2.0, 2.0], [2.0, 0.0], [0.0, 2.0]];
+        assert_eq!(triangulate_polygon(&points), Err(PolygonError::NotSimple));
+    }
+}
@@ -0,0 +1,293 @@
+//! Triangle strips
+
+
+
+use wgpu::util::DeviceExt as _;
+
+use crate::{
+    CoordinateSpace, Mesh, SOLID_WGSL, Shader, ShaderDescriptor, ShaderError, Vertex,
+};
+
+
+
+/// Builds [`Vertex`] geometry as triangle strips instead of lists, for connected shapes like
+/// ribbons and trails: each vertex after the first two adds a triangle, rather than each three
+/// indices.
+///
+/// Separate strips are joined into one mesh with primitive restarts (the largest value of the
+/// index format), so the mesh must be drawn with a pipeline whose
+/// [`wgpu::PrimitiveState::strip_index_format`] is the same [`StripRenderer::index_format`],
+/// like [`Shader::solid_strip`].
+///
+/// NOTE: wgpu's GL backend doesn't turn primitive restart on, so there the strips are joined by
+///       stray triangles instead. See [`StripRenderer::set_restarts`] for a way around that.
+pub struct StripRenderer {
+    space: CoordinateSpace,
+    index_format: wgpu::IndexFormat,
+    /// Whether strips are separated by restarts, rather than by degenerate triangles.
+    restarts: bool,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+}
+
+impl StripRenderer {
+    /// Start a renderer working in `space`, storing indices as `index_format`. With
+    /// [`wgpu::IndexFormat::Uint16`], up to `65535` vertices can be added.
+    pub fn new(space: CoordinateSpace, index_format: wgpu::IndexFormat) -> Self {
+        Self {
+            space,
+            index_format,
+            restarts: true,
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    pub fn space(&self) -> CoordinateSpace {
+        self.space
+    }
+
+    pub fn index_format(&self) -> wgpu::IndexFormat {
+        self.index_format
+    }
+
+    /// Whether to separate strips with restarts (the default), or with degenerate triangles
+    /// that repeat the vertices on either side of each join. Those take two or three indices
+    /// instead of one, but work on every backend.
+    pub fn set_restarts(&mut self, restarts: bool) {
+        self.restarts = restarts;
+    }
+
+    pub fn restarts(&self) -> bool {
+        self.restarts
+    }
+
+    /// The index that ends one strip and starts the next.
+    pub const fn restart_index(index_format: wgpu::IndexFormat) -> u32 {
+        match index_format {
+            wgpu::IndexFormat::Uint16 => u16::MAX as u32,
+            wgpu::IndexFormat::Uint32 => u32::MAX,
+        }
+    }
+
+    /// The vertices added so far, in this renderer's coordinate space.
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
+    }
+
+    /// The indices into [`StripRenderer::vertices`] added so far, including restarts.
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Add a strip of `vertices`, where each one after the first two makes a triangle with the
+    /// two before it. Strips of fewer than three vertices are skipped.
+    pub fn add_strip(&mut self, vertices: impl IntoIterator<Item = Vertex>) {
+        let base = self.vertices.len();
+        self.vertices.extend(vertices);
+        let added = self.vertices.len() - base;
+        if added < 3 {
+            self.vertices.truncate(base);
+            return;
+        }
+
+        match self.indices.last() {
+            Some(_) if self.restarts => {
+                self.indices.push(Self::restart_index(self.index_format));
+            }
+            Some(&last) => {
+                // NOTE: Every other triangle of a strip winds the other way, so the new strip has
+                //       to start at an even index to keep its winding.
+                let repeats = if self.indices.len().is_multiple_of(2) { 1 } else { 2 };
+                self.indices.extend(std::iter::repeat_n(last, repeats));
+                self.indices.push(base as u32);
+            }
+            None => {}
+        }
+        self.indices.extend(base as u32..(base + added) as u32);
+    }
+
+    /// Add a ribbon `thickness` wide along `points` (like [`Renderer::add_arc_stroke`] does
+    /// along an arc), as a single strip. The ribbon bends halfway between the directions of the
+    /// segments at each point, so sharp turns come out thinner.
+    ///
+    /// [`Renderer::add_arc_stroke`]: crate::Renderer::add_arc_stroke
+    pub fn add_ribbon(&mut self, points: &[[f32; 2]], thickness: f32, color: [f32; 3]) {
+        if points.len() < 2 || thickness <= 0.0 {
+            return;
+        }
+        let half = thickness / 2.0;
+        let direction = |a: [f32; 2], b: [f32; 2]| {
+            let delta = [b[0] - a[0], b[1] - a[1]];
+            let length = delta[0].hypot(delta[1]);
+            if length > f32::EPSILON {
+                [delta[0] / length, delta[1] / length]
+            } else {
+                [0.0, 0.0]
+            }
+        };
+
+        let vertices = (0..points.len()).flat_map(|i| {
+            let before = direction(points[i.saturating_sub(1)], points[i]);
+            let after = direction(points[i], points[(i + 1).min(points.len() - 1)]);
+            let [x, y] = direction([0.0, 0.0], [before[0] + after[0], before[1] + after[1]]);
+            let point = points[i];
+            [half, -half].map(|offset| {
+                Vertex::new([point[0] - y * offset, point[1] + x * offset], color)
+            })
+        });
+        self.add_strip(vertices);
+    }
+
+    /// Upload everything added so far as a mesh in NDC, to be drawn with a pipeline for strips
+    /// of [`StripRenderer::index_format`].
+    ///
+    /// Fails if there are too many vertices to index in that format.
+    pub fn finish(&self, device: &wgpu::Device) -> Result<Mesh, String> {
+        let restart = Self::restart_index(self.index_format);
+        if self.vertices.len() > restart as usize {
+            return Err(format!(
+                "{} vertices can't be indexed as {:?}, whose largest index is a restart",
+                self.vertices.len(),
+                self.index_format,
+            ));
+        }
+
+        let mut vertices = self.vertices.clone();
+        for vertex in &mut vertices {
+            vertex.pos = self.space.to_ndc(vertex.pos);
+        }
+        let narrow: Vec<u16>;
+        let index_bytes: &[u8] = match self.index_format {
+            wgpu::IndexFormat::Uint16 => {
+                narrow = self.indices.iter().map(|&i| i as u16).collect();
+                bytemuck::cast_slice(&narrow)
+            }
+            wgpu::IndexFormat::Uint32 => bytemuck::cast_slice(&self.indices),
+        };
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Strip Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Strip Index Buffer"),
+            contents: index_bytes,
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Ok(Mesh {
+            vertex_buffer,
+            index_buffer: Some(index_buffer),
+            num_indices: self.indices.len() as u32,
+            index_format: self.index_format,
+        })
+    }
+}
+
+impl Shader {
+    /// Like [`Shader::solid`], but for the triangle strips of a [`StripRenderer`] with indices
+    /// of `index_format`.
+    pub fn solid_strip(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        index_format: wgpu::IndexFormat,
+    ) -> Result<Self, ShaderError> {
+        Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(SOLID_WGSL)),
+            label: Some("solid.wgsl"),
+            pipeline_label: Some("Solid Strip Pipeline"),
+            pipeline_layout_label: Some("Solid Strip Pipeline Layout"),
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &[Vertex::desc()],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                strip_index_format: Some(index_format),
+                // NOTE: Every other triangle of a strip is flipped back before culling, but
+                //       ribbons can still fold over themselves.
+                cull_mode: None,
+                ..ShaderDescriptor::default().primitive
+            },
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..ShaderDescriptor::default().multisample
+            },
+            ..Default::default()
+        })
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn strip(len: usize) -> Vec<Vertex> {
+        (0..len).map(|i| Vertex::new([i as f32, (i % 2) as f32], [1.0; 3])).collect()
+    }
+
+    fn strips(restarts: bool, lens: &[usize]) -> StripRenderer {
+        let space = CoordinateSpace::Pixels { width: 16.0, height: 16.0 };
+        let mut renderer = StripRenderer::new(space, wgpu::IndexFormat::Uint16);
+        renderer.set_restarts(restarts);
+        for &len in lens {
+            renderer.add_strip(strip(len));
+        }
+        renderer
+    }
+
+    #[test]
+    fn strips_are_joined_by_restarts() {
+        let restart = StripRenderer::restart_index(wgpu::IndexFormat::Uint16);
+        assert_eq!(restart, 0xFFFF);
+        assert_eq!(StripRenderer::restart_index(wgpu::IndexFormat::Uint32), u32::MAX);
+
+        let renderer = strips(true, &[3, 4]);
+        assert_eq!(renderer.vertices().len(), 7);
+        assert_eq!(renderer.indices(), [0, 1, 2, restart, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn strips_are_joined_by_degenerate_triangles_without_restarts() {
+        // NOTE: Each strip starts at an even index, so that its first triangle keeps its
+        //       winding.
+        assert_eq!(strips(false, &[3, 4]).indices(), [0, 1, 2, 2, 2, 3, 3, 4, 5, 6]);
+        assert_eq!(strips(false, &[4, 3]).indices(), [0, 1, 2, 3, 3, 4, 4, 5, 6]);
+    }
+
+    #[test]
+    fn short_strips_are_skipped() {
+        for restarts in [true, false] {
+            let renderer = strips(restarts, &[0, 1, 2]);
+            assert!(renderer.vertices().is_empty());
+            assert!(renderer.indices().is_empty());
+
+            let renderer = strips(restarts, &[3, 2, 1]);
+            assert_eq!(renderer.vertices().len(), 3);
+            assert_eq!(renderer.indices(), [0, 1, 2]);
+        }
+    }
+
+    #[test]
+    fn uint16_strips_are_limited_below_the_restart_index() {
+        let Some((_adapter, device, _queue)) = testing::gpu() else {
+            return;
+        };
+        let mut renderer = strips(true, &[0xFFFF]);
+        assert!(renderer.finish(&device).is_ok());
+
+        renderer.add_strip(strip(3));
+        assert_eq!(renderer.vertices().len(), 0x1_0002);
+        assert!(renderer.finish(&device).is_err());
+    }
+}
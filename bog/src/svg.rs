@@ -0,0 +1,164 @@
+//! SVG export
+
+
+
+use std::fmt::Write as _;
+
+use crate::{Quad, Renderer};
+
+
+
+impl Renderer {
+    /// Describe the solid primitives and circles added so far as an SVG image of `viewport`
+    /// pixels, for inspecting what the renderer is going to draw without a GPU.
+    ///
+    /// Quads become `<rect>`s, circles become `<ellipse>`s, and every other triangle becomes its
+    /// own `<polygon>`. Gradients and textured primitives are left out.
+    pub fn to_svg(&self, viewport: [f32; 2]) -> String {
+        let [width, height] = viewport;
+        let to_svg = |pos: [f32; 2]| {
+            let [x, y] = self.space().to_ndc(pos);
+            [(x + 1.0) * 0.5 * width, (1.0 - y) * 0.5 * height]
+        };
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+            viewBox=\"0 0 {width} {height}\">\n",
+        );
+
+        let mut triangles = self.indices.chunks_exact(3).peekable();
+        while let Some(triangle) = triangles.next() {
+            let rect = triangles.peek().and_then(|next| {
+                let base = triangle[0];
+                let quad = Quad::indices_u32().map(|i| base + i);
+                (triangle == &quad[..3] && *next == &quad[3..]).then_some(base as usize)
+            });
+            if let Some(base) = rect {
+                let corners = &self.vertices[base..base + 4];
+                let [a, b, c, d] = [0, 1, 2, 3].map(|i| to_svg(corners[i].pos));
+                let axis_aligned = a[1] == b[1] && a[0] == c[0] && d[0] == b[0] && d[1] == c[1];
+                if axis_aligned && corners.iter().all(|v| v.color == corners[0].color) {
+                    triangles.next();
+                    let _ = writeln!(
+                        svg,
+                        "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+                        a[0].min(d[0]),
+                        a[1].min(d[1]),
+                        (d[0] - a[0]).abs(),
+                        (d[1] - a[1]).abs(),
+                        hex(corners[0].color),
+                    );
+                    continue;
+                }
+            }
+
+            let points: Vec<_> = triangle
+                .iter()
+                .map(|&i| to_svg(self.vertices[i as usize].pos))
+                .map(|[x, y]| format!("{x},{y}"))
+                .collect();
+            let _ = writeln!(
+                svg,
+                "  <polygon points=\"{}\" fill=\"{}\"/>",
+                points.join(" "),
+                hex(self.vertices[triangle[0] as usize].color),
+            );
+        }
+
+        for corners in self.circles.vertices.chunks_exact(4) {
+            let [cx, cy] = to_svg(corners[0].center);
+            let [x, y] = to_svg(corners[0].pos);
+            let (rx, ry) = ((x - cx).abs(), (y - cy).abs());
+            let color = hex(corners[0].color);
+            let inner = corners[0].inner;
+            let _ = if inner > 0.0 {
+                // NOTE: A stroke is centered on its path, so the path runs between the edges.
+                let middle = (1.0 + inner) * 0.5;
+                writeln!(
+                    svg,
+                    "  <ellipse cx=\"{cx}\" cy=\"{cy}\" rx=\"{}\" ry=\"{}\" fill=\"none\" \
+                    stroke=\"{color}\" stroke-width=\"{}\"/>",
+                    rx * middle,
+                    ry * middle,
+                    rx * (1.0 - inner),
+                )
+            } else {
+                writeln!(
+                    svg,
+                    "  <ellipse cx=\"{cx}\" cy=\"{cy}\" rx=\"{rx}\" ry=\"{ry}\" fill=\"{color}\"/>",
+                )
+            };
+        }
+
+        svg.push_str("</svg>\n");
+
+        svg
+    }
+}
+
+/// `color` (in linear space) as an sRGB hex color.
+fn hex(color: [f32; 3]) -> String {
+    let [r, g, b] = color.map(|c| (crate::linear_to_srgb(c.clamp(0.0, 1.0)) * 255.0).round() as u8);
+
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CoordinateSpace;
+
+    fn pixels() -> Renderer {
+        Renderer::with_space(CoordinateSpace::Pixels { width: 64.0, height: 32.0 })
+    }
+
+    #[test]
+    fn quads_become_rects() {
+        let mut renderer = pixels();
+        renderer.add_quad(&Quad::new([8.0, 4.0], [16.0, 8.0]), [1.0, 0.0, 0.0]);
+
+        assert_eq!(
+            renderer.to_svg([64.0, 32.0]),
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"64\" height=\"32\" \
+            viewBox=\"0 0 64 32\">\n  \
+            <rect x=\"8\" y=\"4\" width=\"16\" height=\"8\" fill=\"#ff0000\"/>\n\
+            </svg>\n",
+        );
+    }
+
+    #[test]
+    fn other_triangles_become_polygons() {
+        let mut renderer = pixels();
+        renderer.add_convex_polygon(&[[0.0, 0.0], [16.0, 0.0], [0.0, 8.0]], [0.0, 0.0, 1.0])
+            .unwrap();
+        let svg = renderer.to_svg([64.0, 32.0]);
+        let polygons: Vec<&str> = svg.lines().filter(|line| line.contains("<polygon")).collect();
+        assert_eq!(polygons.len(), 1, "{svg}");
+        for point in ["0,0", "16,0", "0,8"] {
+            assert!(polygons[0].contains(point), "{point} is missing from {svg}");
+        }
+        assert!(polygons[0].ends_with("fill=\"#0000ff\"/>"));
+
+        // NOTE: Rotated quads (and ones with a gradient of corner colors) aren't rects.
+        let mut renderer = pixels();
+        renderer.add(Quad::new([8.0, 8.0], [16.0, 16.0]).rotated(0.5));
+        let svg = renderer.to_svg([64.0, 32.0]);
+        assert!(!svg.contains("<rect"));
+        assert_eq!(svg.matches("<polygon").count(), 2, "{svg}");
+    }
+
+    #[test]
+    fn svgs_are_scaled_to_their_viewport() {
+        let mut renderer = Renderer::start();
+        renderer.add_quad(&Quad::new([-1.0, 0.0], [1.0, 1.0]), [1.0, 1.0, 1.0]);
+        renderer.add_circle([0.5, -0.5], 0.25, [0.0, 0.0, 0.0]);
+        let svg = renderer.to_svg([200.0, 100.0]);
+
+        let rect = "<rect x=\"0\" y=\"0\" width=\"100\" height=\"50\" fill=\"#ffffff\"/>";
+        let ellipse = "<ellipse cx=\"150\" cy=\"75\" rx=\"25\" ry=\"12.5\" fill=\"#000000\"/>";
+        assert!(svg.contains(rect), "{svg}");
+        assert!(svg.contains(ellipse), "{svg}");
+    }
+}
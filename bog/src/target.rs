@@ -0,0 +1,588 @@
+//! Render targets
+
+
+
+use crate::{Instances, Mesh, Shader};
+
+
+
+pub struct RenderTarget {
+    pub view: wgpu::TextureView,
+    /// Where a multisampled `view` is resolved to at the end of each pass.
+    pub resolve_target: Option<wgpu::TextureView>,
+    /// The size of `view`, if known. Passes with scissored [`DrawCommand`]s need it.
+    pub size: Option<[u32; 2]>,
+    /// The number of samples per pixel of `view`, if known. Passes check it against each
+    /// [`DrawCommand`]'s shader.
+    pub sample_count: Option<u32>,
+    /// The format of `view`, if known. Passes check it against each [`DrawCommand`]'s shader.
+    pub format: Option<wgpu::TextureFormat>,
+}
+
+impl RenderTarget {
+    pub fn new(view: wgpu::TextureView) -> Self {
+        Self { view, resolve_target: None, size: None, sample_count: None, format: None }
+    }
+
+    /// Target a multisampled `view`, resolving it into the single-sampled `resolve_target`.
+    pub fn resolving(view: wgpu::TextureView, resolve_target: wgpu::TextureView) -> Self {
+        Self {
+            resolve_target: Some(resolve_target),
+            ..Self::new(view)
+        }
+    }
+
+    /// Target a `multisampled` texture, resolving it into a separate texture (rather than the
+    /// surface), which later passes can sample once this one is done.
+    ///
+    /// Fails unless `multisampled` has more than one sample, `resolve` has exactly one, and
+    /// both share a format and size (and can be rendered to).
+    pub fn resolving_into(
+        multisampled: &wgpu::Texture,
+        resolve: &wgpu::Texture,
+    ) -> Result<Self, String> {
+        if multisampled.sample_count() < 2 {
+            return Err("the multisampled texture only has one sample".to_string());
+        }
+        if resolve.sample_count() != 1 {
+            return Err(format!(
+                "the resolve texture has {} samples, but must have one",
+                resolve.sample_count(),
+            ));
+        }
+        if multisampled.format() != resolve.format() {
+            return Err(format!(
+                "can't resolve {:?} into {:?}",
+                multisampled.format(),
+                resolve.format(),
+            ));
+        }
+        if multisampled.size() != resolve.size() {
+            return Err(format!(
+                "can't resolve a {}x{} texture into a {}x{} one",
+                multisampled.width(),
+                multisampled.height(),
+                resolve.width(),
+                resolve.height(),
+            ));
+        }
+        let usage = multisampled.usage() & resolve.usage();
+        if !usage.contains(wgpu::TextureUsages::RENDER_ATTACHMENT) {
+            return Err("both textures need RENDER_ATTACHMENT usage".to_string());
+        }
+
+        Ok(Self {
+            view: multisampled.create_view(&wgpu::TextureViewDescriptor::default()),
+            resolve_target: Some(resolve.create_view(&wgpu::TextureViewDescriptor::default())),
+            size: Some([multisampled.width(), multisampled.height()]),
+            sample_count: Some(multisampled.sample_count()),
+            format: Some(multisampled.format()),
+        })
+    }
+
+    pub fn from_texture(texture: &wgpu::Texture) -> Self {
+        Self::new(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+            .with_size([texture.width(), texture.height()])
+            .with_sample_count(texture.sample_count())
+            .with_format(texture.format())
+    }
+
+    pub fn with_size(mut self, size: [u32; 2]) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = Some(sample_count);
+        self
+    }
+
+    pub fn with_format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Target a single layer of a 2D array texture.
+    pub fn array_layer(texture: &wgpu::Texture, layer: u32) -> Result<Self, String> {
+        let layers = texture.depth_or_array_layers();
+        if texture.dimension() != wgpu::TextureDimension::D2 || layer >= layers {
+            return Err(format!(
+                "layer {layer} is out of range for a {:?} texture with {layers} layers",
+                texture.dimension(),
+            ));
+        }
+
+        Ok(Self::new(texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Array Layer Target"),
+            dimension: Some(wgpu::TextureViewDimension::D2),
+            base_array_layer: layer,
+            array_layer_count: Some(1),
+            ..Default::default()
+        }))
+        .with_size([texture.width(), texture.height()])
+        .with_sample_count(texture.sample_count())
+        .with_format(texture.format()))
+    }
+}
+
+
+
+/// One draw in a pass recorded with [`render_to`] (or [`render_to_attachments`]).
+///
+/// Commands don't own their resources, so one [`Shader`] can be shared by any number of
+/// commands that each bind different groups (like one texture per material). Consecutive
+/// commands only switch pipelines and rebind groups where they actually differ.
+pub struct DrawCommand<'a> {
+    pub shader: &'a Shader,
+    pub mesh: &'a Mesh,
+    /// Bound at `@group(0)`, `@group(1)`, and so on. These must match the shader's layouts.
+    pub bind_groups: &'a [&'a wgpu::BindGroup],
+    /// Draw the mesh once per instance (see [`Mesh::draw_instances`]) instead of just once.
+    pub instances: Option<&'a Instances>,
+    /// Only draw within this rect (clamped to the target). Commands without one draw over the
+    /// whole target, whatever came before them.
+    pub scissor: Option<ScissorRect>,
+}
+
+/// A rect of a target, in physical pixels from its top-left corner.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScissorRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ScissorRect {
+    pub const fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// The part of this rect that's within a target of `size`.
+    pub const fn clamped(&self, size: [u32; 2]) -> Self {
+        let x = if self.x < size[0] { self.x } else { size[0] };
+        let y = if self.y < size[1] { self.y } else { size[1] };
+        let right = self.x.saturating_add(self.width);
+        let bottom = self.y.saturating_add(self.height);
+        let right = if right < size[0] { right } else { size[0] };
+        let bottom = if bottom < size[1] { bottom } else { size[1] };
+
+        Self::new(x, y, right - x, bottom - y)
+    }
+
+    /// Whether the rect covers no pixels at all.
+    pub const fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// The smallest rect containing both this one and `other`. Empty rects don't cover
+    /// anything, so they're left out wherever they are.
+    pub const fn union(&self, other: &Self) -> Self {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+        let x = if self.x < other.x { self.x } else { other.x };
+        let y = if self.y < other.y { self.y } else { other.y };
+        let right = self.x.saturating_add(self.width);
+        let other_right = other.x.saturating_add(other.width);
+        let right = if right > other_right { right } else { other_right };
+        let bottom = self.y.saturating_add(self.height);
+        let other_bottom = other.y.saturating_add(other.height);
+        let bottom = if bottom > other_bottom { bottom } else { other_bottom };
+
+        Self::new(x, y, right - x, bottom - y)
+    }
+}
+
+/// How one target is used by a pass recorded with [`render_to_attachments`].
+pub struct ColorAttachment<'a> {
+    pub target: &'a RenderTarget,
+    pub load: wgpu::LoadOp<wgpu::Color>,
+    pub store: wgpu::StoreOp,
+    /// Whether to resolve into the target's `resolve_target` (if it has one) at the end of
+    /// the pass. Skipping it lets later passes keep drawing into the multisampled view, and
+    /// resolve once they're done.
+    pub resolve: bool,
+}
+
+impl<'a> ColorAttachment<'a> {
+    /// Keep `target`'s previous contents.
+    pub const fn load(target: &'a RenderTarget) -> Self {
+        Self {
+            target,
+            load: wgpu::LoadOp::Load,
+            store: wgpu::StoreOp::Store,
+            resolve: true,
+        }
+    }
+
+    /// Clear `target` to `color` first.
+    pub const fn clear(target: &'a RenderTarget, color: wgpu::Color) -> Self {
+        Self {
+            load: wgpu::LoadOp::Clear(color),
+            ..Self::load(target)
+        }
+    }
+
+    pub const fn without_resolve(mut self) -> Self {
+        self.resolve = false;
+        self
+    }
+}
+
+/// A depth buffer, and how a pass recorded with [`render_to_attachments_with_depth`] uses it.
+pub struct DepthAttachment<'a> {
+    /// A view of a [`DepthAttachment::FORMAT`] texture.
+    pub view: &'a wgpu::TextureView,
+    pub load: wgpu::LoadOp<f32>,
+    pub store: wgpu::StoreOp,
+}
+
+impl<'a> DepthAttachment<'a> {
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+    /// Clear `view` to `1.0` (the far plane) first, and discard it once the pass is done.
+    pub const fn new(view: &'a wgpu::TextureView) -> Self {
+        Self {
+            view,
+            load: wgpu::LoadOp::Clear(1.0),
+            store: wgpu::StoreOp::Discard,
+        }
+    }
+
+    pub const fn with_clear(mut self, depth: f32) -> Self {
+        self.load = wgpu::LoadOp::Clear(depth);
+        self
+    }
+
+    /// Keep the depth values left by earlier passes instead of clearing them.
+    pub const fn loaded(mut self) -> Self {
+        self.load = wgpu::LoadOp::Load;
+        self
+    }
+
+    /// Keep the depth values once the pass is done, for later passes (or sampling) to use.
+    pub const fn stored(mut self) -> Self {
+        self.store = wgpu::StoreOp::Store;
+        self
+    }
+
+    /// Create a [`DepthAttachment::FORMAT`] texture to attach to passes drawing into targets
+    /// of `size` with `sample_count` samples per pixel. Single-sampled ones can be read back
+    /// with [`read_depth`](crate::read_depth).
+    pub fn create_texture(
+        device: &wgpu::Device,
+        size: [u32; 2],
+        sample_count: u32,
+    ) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Texture"),
+            size: wgpu::Extent3d {
+                width: size[0].max(1),
+                height: size[1].max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::FORMAT,
+            // NOTE: Multisampled textures can't be copied from.
+            usage: if sample_count > 1 {
+                wgpu::TextureUsages::RENDER_ATTACHMENT
+            } else {
+                wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC
+            },
+            view_formats: &[],
+        })
+    }
+
+    /// The pipeline state for testing against (and writing to) this format with `compare`.
+    pub fn depth_stencil_state(compare: wgpu::CompareFunction) -> wgpu::DepthStencilState {
+        wgpu::DepthStencilState {
+            format: Self::FORMAT,
+            depth_write_enabled: true,
+            depth_compare: compare,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }
+    }
+}
+
+/// Check that `attachments` can be used together in one pass: each target may have its own
+/// format, but (as far as their [`RenderTarget::size`]s and [`RenderTarget::sample_count`]s are
+/// known) they must all share a size and sample count.
+///
+/// NOTE: To write something at a lower resolution too (like a bloom seed), write it at full
+///       size into its own target, then downsample that in a later pass.
+pub fn validate_color_attachments(attachments: &[ColorAttachment]) -> Result<(), String> {
+    let mut size: Option<(usize, [u32; 2])> = None;
+    let mut sample_count: Option<(usize, u32)> = None;
+    for (index, attachment) in attachments.iter().enumerate() {
+        if let Some(target_size) = attachment.target.size {
+            match size {
+                Some((first, first_size)) if first_size != target_size => {
+                    return Err(format!(
+                        "color target {index} is {}x{}, but color target {first} is {}x{}",
+                        target_size[0],
+                        target_size[1],
+                        first_size[0],
+                        first_size[1],
+                    ));
+                }
+                Some(_) => {}
+                None => size = Some((index, target_size)),
+            }
+        }
+        if let Some(target_samples) = attachment.target.sample_count {
+            match sample_count {
+                Some((first, first_samples)) if first_samples != target_samples => {
+                    return Err(format!(
+                        "color target {index} has {target_samples} samples per pixel, but color \
+                        target {first} has {first_samples}",
+                    ));
+                }
+                Some(_) => {}
+                None => sample_count = Some((index, target_samples)),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Record a render pass drawing `commands` (in order) into `targets`, one color attachment per
+/// target.
+///
+/// When `clear` is `None`, the targets' previous contents are kept. See
+/// [`render_to_attachments`] to choose this (and whether to resolve) per target.
+///
+/// Fails if any command's shader writes to a different number of color targets than were given.
+pub fn render_to(
+    encoder: &mut wgpu::CommandEncoder,
+    targets: &[RenderTarget],
+    clear: Option<wgpu::Color>,
+    commands: &[DrawCommand],
+) -> Result<(), String> {
+    let attachments: Vec<_> = targets
+        .iter()
+        .map(|target| match clear {
+            Some(color) => ColorAttachment::clear(target, color),
+            None => ColorAttachment::load(target),
+        })
+        .collect();
+
+    render_to_attachments(encoder, &attachments, commands)
+}
+
+/// Like [`render_to`], but with each attachment's load, store, and resolve behavior given
+/// separately.
+pub fn render_to_attachments(
+    encoder: &mut wgpu::CommandEncoder,
+    attachments: &[ColorAttachment],
+    commands: &[DrawCommand],
+) -> Result<(), String> {
+    render_to_attachments_with_depth(encoder, attachments, None, commands)
+}
+
+/// Like [`render_to_attachments`], but also testing against (and writing to) `depth`.
+///
+/// Fails if the attachments don't pass [`validate_color_attachments`], if any command's shader
+/// wasn't made for [`DepthAttachment::FORMAT`] (or for no depth at all, when `depth` is `None`)
+/// or for the targets' [`RenderTarget::sample_count`] and [`RenderTarget::format`]s (where
+/// they're known), or if any command is scissored but the first target's
+/// [`RenderTarget::size`] isn't known.
+pub fn render_to_attachments_with_depth(
+    encoder: &mut wgpu::CommandEncoder,
+    attachments: &[ColorAttachment],
+    depth: Option<&DepthAttachment>,
+    commands: &[DrawCommand],
+) -> Result<(), String> {
+    if let Some(command) = commands.iter().find(|c| c.shader.color_targets != attachments.len()) {
+        return Err(format!(
+            "shader writes to {} color targets, but {} were given",
+            command.shader.color_targets,
+            attachments.len(),
+        ));
+    }
+
+    let depth_format = depth.map(|_| DepthAttachment::FORMAT);
+    if let Some(command) = commands.iter().find(|c| c.shader.depth_format != depth_format) {
+        return Err(format!(
+            "shader expects a depth attachment of {:?}, but the pass has {:?}",
+            command.shader.depth_format,
+            depth_format,
+        ));
+    }
+
+    validate_color_attachments(attachments)?;
+
+    let sample_count = attachments.iter().find_map(|attachment| attachment.target.sample_count);
+    if let Some(sample_count) = sample_count
+        && let Some(command) = commands.iter().find(|c| c.shader.sample_count != sample_count)
+    {
+        return Err(format!(
+            "shader draws with {} samples per pixel, but the target has {sample_count}",
+            command.shader.sample_count,
+        ));
+    }
+    for (index, attachment) in attachments.iter().enumerate() {
+        let Some(format) = attachment.target.format else {
+            continue;
+        };
+        let mismatch = commands.iter().find_map(|command| {
+            command.shader.color_formats[index].filter(|expected| *expected != format)
+        });
+        if let Some(expected) = mismatch {
+            return Err(format!(
+                "shader writes {expected:?} to color target {index}, but it's {format:?}",
+            ));
+        }
+    }
+
+    let scissored = commands.iter().any(|command| command.scissor.is_some());
+    let size = attachments.first().and_then(|attachment| attachment.target.size);
+    let full_size = match size {
+        Some(size) => size,
+        None if scissored => {
+            return Err("scissored draw commands need the target's size".to_string());
+        }
+        None => [0, 0],
+    };
+
+    let color_attachments: Vec<_> = attachments
+        .iter()
+        .map(|attachment| Some(wgpu::RenderPassColorAttachment {
+            view: &attachment.target.view,
+            resolve_target: attachment.target.resolve_target
+                .as_ref()
+                .filter(|_| attachment.resolve),
+            ops: wgpu::Operations {
+                load: attachment.load,
+                store: attachment.store,
+            },
+        }))
+        .collect();
+
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Render Pass"),
+        color_attachments: &color_attachments,
+        depth_stencil_attachment: depth.map(|depth| wgpu::RenderPassDepthStencilAttachment {
+            view: depth.view,
+            depth_ops: Some(wgpu::Operations {
+                load: depth.load,
+                store: depth.store,
+            }),
+            stencil_ops: None,
+        }),
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    });
+
+    let mut scissor = None;
+    let mut pipeline: Option<&wgpu::RenderPipeline> = None;
+    let mut bound: Vec<Option<&wgpu::BindGroup>> = Vec::new();
+    for command in commands {
+        // NOTE: Only touch the scissor when it changes, resetting it to the whole target once a
+        //       scissored command is done so it doesn't clip the commands after it.
+        if scissored && command.scissor != scissor {
+            let rect = command.scissor.unwrap_or(ScissorRect::new(0, 0, u32::MAX, u32::MAX));
+            let rect = rect.clamped(full_size);
+            render_pass.set_scissor_rect(rect.x, rect.y, rect.width, rect.height);
+            scissor = command.scissor;
+        }
+        if pipeline != Some(&command.shader.pipeline) {
+            render_pass.set_pipeline(&command.shader.pipeline);
+            pipeline = Some(&command.shader.pipeline);
+            // NOTE: Groups bound for another pipeline layout may not be compatible with this
+            //       one, so they're always rebound after a switch.
+            bound.clear();
+        }
+        if bound.len() < command.bind_groups.len() {
+            bound.resize(command.bind_groups.len(), None);
+        }
+        for (index, bind_group) in command.bind_groups.iter().enumerate() {
+            if bound[index] != Some(*bind_group) {
+                render_pass.set_bind_group(index as u32, *bind_group, &[]);
+                bound[index] = Some(*bind_group);
+            }
+        }
+        match command.instances {
+            Some(instances) => command.mesh.draw_instances(&mut render_pass, instances),
+            None => command.mesh.draw(&mut render_pass),
+        }
+    }
+
+    Ok(())
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Quad, Renderer, Texture, testing};
+
+    #[test]
+    fn unions_cover_both_rects() {
+        let a = ScissorRect::new(2, 3, 4, 5);
+
+        let disjoint = ScissorRect::new(10, 1, 2, 2);
+        assert_eq!(a.union(&disjoint), ScissorRect::new(2, 1, 10, 7));
+        assert_eq!(disjoint.union(&a), a.union(&disjoint));
+
+        let nested = ScissorRect::new(3, 4, 1, 1);
+        assert_eq!(a.union(&nested), a);
+        assert_eq!(nested.union(&a), a);
+        assert_eq!(a.union(&a), a);
+
+        for empty in [ScissorRect::default(), ScissorRect::new(20, 20, 0, 8)] {
+            assert!(empty.is_empty());
+            assert_eq!(a.union(&empty), a);
+            assert_eq!(empty.union(&a), a);
+        }
+        assert!(ScissorRect::default().union(&ScissorRect::default()).is_empty());
+        assert_eq!(
+            ScissorRect::new(u32::MAX - 1, 0, 4, 1).union(&ScissorRect::new(0, 0, 1, 1)),
+            ScissorRect::new(0, 0, u32::MAX, 1),
+        );
+    }
+
+    #[test]
+    fn scissored_commands_stay_in_their_rects() {
+        let Some((_adapter, device, queue)) = testing::gpu() else {
+            return;
+        };
+        let shader = Shader::solid(&device, Texture::FORMAT, 1).unwrap();
+        let batch = |color| {
+            let mut renderer = Renderer::start();
+            renderer.add_quad(&Quad::new([-1.0, -1.0], [2.0, 2.0]), color);
+            renderer.finish(&device)
+        };
+        let (red, blue) = (batch([1.0, 0.0, 0.0]), batch([0.0, 0.0, 1.0]));
+
+        let pixels = testing::render(&device, &queue, [16, 8], &[
+            DrawCommand {
+                shader: &shader,
+                mesh: &red.solid,
+                bind_groups: &[],
+                instances: None,
+                scissor: Some(ScissorRect::new(0, 0, 8, 8)),
+            },
+            DrawCommand {
+                shader: &shader,
+                mesh: &blue.solid,
+                bind_groups: &[],
+                instances: None,
+                scissor: Some(ScissorRect::new(8, 0, 8, 8)),
+            },
+        ]);
+
+        for (i, texel) in pixels.chunks(4).enumerate() {
+            let expected = if i % 16 < 8 { [255, 0, 0, 255] } else { [0, 0, 255, 255] };
+            assert_eq!(texel, expected, "texel ({}, {})", i % 16, i / 16);
+        }
+    }
+}
@@ -4,24 +4,62 @@
 
 use wgpu::util::DeviceExt as _;
 
+use crate::{SOLID_LAYERED_WGSL, SOLID_WGSL};
+
 
 
 pub struct Shader {
     pub pipeline: wgpu::RenderPipeline,
+    /// The number of color targets the fragment stage writes to.
+    pub color_targets: usize,
+    /// The format of each of those targets (`None` for slots that aren't written to).
+    pub color_formats: Vec<Option<wgpu::TextureFormat>>,
+    /// The number of samples per pixel of the targets this can draw into.
+    pub sample_count: u32,
+    /// The format of the depth attachment this draws with, if any.
+    pub depth_format: Option<wgpu::TextureFormat>,
 }
 
 impl Shader {
-    // TODO: Error handling.
-    pub fn new(device: &wgpu::Device, desc: ShaderDescriptor) -> Result<Self, String> {
-        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: desc.label,
-            source: desc.source,
-        });
+    pub fn new(device: &wgpu::Device, desc: ShaderDescriptor) -> Result<Self, ShaderError> {
+        if desc.spirv.is_some()
+            && !device.features().contains(wgpu::Features::SPIRV_SHADER_PASSTHROUGH)
+        {
+            return Err(ShaderError::SpirVUnsupported);
+        }
+        let missing = desc.required_features() - device.features();
+        if !missing.is_empty() {
+            return Err(ShaderError::MissingFeatures(missing));
+        }
+        desc.validate_entry_points()?;
+
+        let module = match &desc.spirv {
+            // SAFETY: `ShaderDescriptor::from_spirv_passthrough`'s caller vouched for the
+            //         SPIR-V.
+            Some(words) => unsafe {
+                device.create_shader_module_spirv(&wgpu::ShaderModuleDescriptorSpirV {
+                    label: desc.label,
+                    source: std::borrow::Cow::Borrowed(&words.0),
+                })
+            },
+            None => device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: desc.label,
+                source: desc.source,
+            }),
+        };
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: desc.pipeline_layout_label,
-            bind_group_layouts: &[],
+            bind_group_layouts: desc.bind_group_layouts,
             push_constant_ranges: &[],
         });
+        let fragment_targets: Vec<_> = desc.fragment_targets
+            .iter()
+            .map(|target| target.clone().map(|target| wgpu::ColorTargetState {
+                write_mask: desc.write_mask.unwrap_or(target.write_mask),
+                blend: desc.blend_mode.map(crate::BlendMode::blend_state).or(target.blend),
+                ..target
+            }))
+            .collect();
         let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: desc.pipeline_label,
             layout: Some(&pipeline_layout),
@@ -34,181 +72,2684 @@ impl Shader {
             fragment: Some(wgpu::FragmentState {
                 module: &module,
                 entry_point: desc.fragment_entry_point,
-                targets: desc.fragment_targets,
+                targets: &fragment_targets,
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
             }),
             primitive: desc.primitive,
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
+            depth_stencil: desc.depth_stencil.clone(),
+            multisample: desc.multisample,
             multiview: None,
-            cache: None,
+            cache: desc.cache.map(crate::PipelineCache::get),
         });
 
         Ok(Self {
             pipeline,
+            color_targets: desc.fragment_targets.len(),
+            color_formats: desc.fragment_targets
+                .iter()
+                .map(|target| target.as_ref().map(|target| target.format))
+                .collect(),
+            sample_count: desc.multisample.count,
+            depth_format: desc.depth_stencil.map(|state| state.format),
+        })
+    }
+
+    /// The layout of the bind group this pipeline reads at `@group(index)`, for creating the
+    /// groups that [`DrawCommand`](crate::DrawCommand)s bind with it.
+    ///
+    /// Panics if the pipeline has no group at `index`.
+    pub fn bind_group_layout(&self, index: u32) -> wgpu::BindGroupLayout {
+        self.pipeline.get_bind_group_layout(index)
+    }
+
+    /// The built-in pipeline for drawing [`Batch::solid`] (and any other [`Vertex`] mesh) into a
+    /// target of `format` with `sample_count` samples per pixel.
+    ///
+    /// Nothing is culled, so quads show up whichever way they wind (Y-down pixel space flips
+    /// it). See [`Shader::solid_culled`] to cull faces anyway, and [`Shader::solid_uniforms`] for
+    /// a uniform block (e.g. for animations).
+    pub fn solid(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Result<Self, ShaderError> {
+        Self::solid_culled(device, format, sample_count, None)
+    }
+
+    /// Like [`Shader::solid`], but culling `cull_mode` faces. Front faces wind counter-clockwise
+    /// in clip space (Y-up).
+    pub fn solid_culled(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        cull_mode: Option<wgpu::Face>,
+    ) -> Result<Self, ShaderError> {
+        Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(SOLID_WGSL)),
+            label: Some("solid.wgsl"),
+            pipeline_label: Some("Solid Pipeline"),
+            pipeline_layout_label: Some("Solid Pipeline Layout"),
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &[Vertex::desc()],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            primitive: wgpu::PrimitiveState {
+                cull_mode,
+                ..ShaderDescriptor::default().primitive
+            },
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..ShaderDescriptor::default().multisample
+            },
+            ..Default::default()
+        })
+    }
+}
+
+impl Shader {
+    /// Like [`Shader::solid`], but drawing each vertex at the depth of its layer (see
+    /// [`Renderer::next_layer`]), and testing against a
+    /// [`DepthAttachment`](crate::DepthAttachment) cleared to `1.0` so that later layers end
+    /// up in front.
+    pub fn solid_layered(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Result<Self, ShaderError> {
+        Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(SOLID_LAYERED_WGSL)),
+            label: Some("solid_layered.wgsl"),
+            pipeline_label: Some("Solid Layered Pipeline"),
+            pipeline_layout_label: Some("Solid Layered Pipeline Layout"),
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &[Vertex::desc()],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            primitive: ShaderDescriptor::UNCULLED_PRIMITIVE,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..ShaderDescriptor::default().multisample
+            },
+            // NOTE: Primitives on the same layer share a depth, so they have to pass on equal
+            //       depths to be drawn in the order they were added.
+            depth_stencil: Some(crate::DepthAttachment::depth_stencil_state(
+                wgpu::CompareFunction::LessEqual,
+            )),
+            ..Default::default()
         })
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShaderError {
+    /// The pipeline uses options that need these features, but they weren't requested when the
+    /// device was created.
+    MissingFeatures(wgpu::Features),
+    /// The descriptor has [`ShaderDescriptor::spirv`], but the device can't take SPIR-V as is.
+    /// Only Vulkan supports [`wgpu::Features::SPIRV_SHADER_PASSTHROUGH`], so this is always the
+    /// case on WebGL, Metal, and DX12. [`ShaderDescriptor::from_spirv`] works everywhere.
+    SpirVUnsupported,
+    /// The bytes given to [`ShaderDescriptor::from_spirv`] (or
+    /// [`ShaderDescriptor::from_spirv_passthrough`]) aren't a SPIR-V module.
+    InvalidSpirV(String),
+    /// The WGSL source has no entry point with this name for the stage it was given for (a
+    /// [`ShaderDescriptor::vertex_entry_point`] that isn't a `@vertex` function, say).
+    MissingEntryPoint(String),
+    /// The shader's source file couldn't be read (see [`ShaderFile`](crate::ShaderFile)).
+    Io(String),
+    /// A [`ComputeShader`](crate::ComputeShader) was made on a device without compute shaders
+    /// (like WebGL).
+    ComputeUnsupported,
+    /// The source didn't compile, or the pipeline couldn't be built from it. The message is
+    /// wgpu's (or naga's, with the offending lines).
+    Compile(String),
+}
+
+impl std::fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingFeatures(features) => write!(
+                f,
+                "pipeline needs {features:?}, which the device wasn't created with",
+            ),
+            Self::SpirVUnsupported => write!(
+                f,
+                "SPIR-V shaders need SPIRV_SHADER_PASSTHROUGH (Vulkan only), which the device \
+                wasn't created with",
+            ),
+            Self::InvalidSpirV(reason) => write!(f, "invalid SPIR-V: {reason}"),
+            Self::MissingEntryPoint(name) => write!(
+                f,
+                "shader has no entry point called `{name}` for the stage it's used for",
+            ),
+            Self::Io(reason) => write!(f, "couldn't read shader: {reason}"),
+            Self::ComputeUnsupported => write!(f, "device doesn't support compute shaders"),
+            Self::Compile(reason) => write!(f, "shader failed to compile: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+#[derive(Clone)]
 pub struct ShaderDescriptor<'a> {
     pub source: wgpu::ShaderSource<'a>,
+    /// A precompiled module handed to the driver as is, instead of `source` (see
+    /// [`ShaderDescriptor::from_spirv_passthrough`]).
+    pub spirv: Option<SpirV<'a>>,
     pub label: Option<&'a str>,
     pub pipeline_label: Option<&'a str>,
     pub pipeline_layout_label: Option<&'a str>,
+    /// The layouts of the bind groups the shader uses, in `@group` order.
+    pub bind_group_layouts: &'a [&'a wgpu::BindGroupLayout],
     pub vertex_entry_point: Option<&'a str>,
     pub vertex_buffers: &'a [wgpu::VertexBufferLayout<'a>],
     pub fragment_entry_point: Option<&'a str>,
     pub fragment_targets: &'a [Option<wgpu::ColorTargetState>],
+    /// Replaces the `write_mask` of every fragment target, e.g. with [`wgpu::ColorWrites::ALPHA`]
+    /// to only draw into a mask.
+    pub write_mask: Option<wgpu::ColorWrites>,
+    /// Replaces the `blend` of every fragment target.
+    pub blend_mode: Option<crate::BlendMode>,
     pub primitive: wgpu::PrimitiveState,
+    /// Must match the sample count of the targets the pipeline draws into.
+    pub multisample: wgpu::MultisampleState,
+    /// How the pipeline tests against and writes to a depth attachment (see
+    /// [`DepthAttachment`](crate::DepthAttachment)). Passes with one can only use pipelines
+    /// made with this set.
+    pub depth_stencil: Option<wgpu::DepthStencilState>,
+    /// A cache to look the compiled pipeline up in (and add it to), to save compiling it again
+    /// on later runs.
+    pub cache: Option<&'a crate::PipelineCache>,
 }
 
 impl<'a> Default for ShaderDescriptor<'a> {
     fn default() -> Self {
         Self {
             source: wgpu::ShaderSource::Dummy(std::marker::PhantomData),
+            spirv: None,
             label: None,
             pipeline_label: None,
             pipeline_layout_label: None,
+            bind_group_layouts: &[],
             vertex_entry_point: None,
             vertex_buffers: &[],
             fragment_entry_point: None,
             fragment_targets: &[],
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                // NOTE: Setting this to anything other than `Fill` requires
-                //       `Features::NON_FILL_POLYGON_MODE`.
-                polygon_mode: wgpu::PolygonMode::Fill,
-                // NOTE: Requires `Features::DEPTH_CLIP_CONTROL`.
-                unclipped_depth: false,
-                // NOTE: Requires `Features::CONSERVATIVE_RASTERIZATION`.
-                conservative: false,
+            write_mask: None,
+            blend_mode: None,
+            primitive: Self::PRIMITIVE,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
             },
+            depth_stencil: None,
+            cache: None,
+        }
+    }
+}
+
+impl ShaderDescriptor<'_> {
+    const PRIMITIVE: wgpu::PrimitiveState = wgpu::PrimitiveState {
+        topology: wgpu::PrimitiveTopology::TriangleList,
+        strip_index_format: None,
+        front_face: wgpu::FrontFace::Ccw,
+        cull_mode: Some(wgpu::Face::Back),
+        // NOTE: `Line` (for wireframes) requires `Features::POLYGON_MODE_LINE`, and `Point`
+        //       requires `Features::POLYGON_MODE_POINT`. `Shader::new` checks for both.
+        polygon_mode: wgpu::PolygonMode::Fill,
+        // NOTE: Requires `Features::DEPTH_CLIP_CONTROL`.
+        unclipped_depth: false,
+        // NOTE: Requires `Features::CONSERVATIVE_RASTERIZATION`, which `Shader::new` checks for.
+        conservative: false,
+    };
+
+    /// The default [`ShaderDescriptor::primitive`], but drawing triangles of either winding.
+    ///
+    /// Pixel-space (Y-down) geometry winds clockwise once it's in clip space, and so does the
+    /// fullscreen triangle (see [`draw_fullscreen_triangle`](crate::draw_fullscreen_triangle)),
+    /// so the built-in pipelines that draw either of them use this.
+    pub const UNCULLED_PRIMITIVE: wgpu::PrimitiveState = wgpu::PrimitiveState {
+        cull_mode: None,
+        ..Self::PRIMITIVE
+    };
+
+    /// Describe a pipeline built from a precompiled SPIR-V module, with every other option left
+    /// at its default. The module is translated (and validated) by naga, like WGSL is, so this
+    /// works on every backend.
+    pub fn from_spirv(bytes: &[u8]) -> Result<Self, ShaderError> {
+        let words = SpirV::words_from_bytes(bytes)?;
+        spirv_module(&words)?;
+
+        Ok(Self {
+            source: wgpu::ShaderSource::SpirV(std::borrow::Cow::Owned(words)),
+            ..Self::default()
+        })
+    }
+
+    /// Like [`ShaderDescriptor::from_spirv`], but the module is handed to the driver as is
+    /// (see [`ShaderDescriptor::spirv`]), skipping naga. This needs
+    /// [`wgpu::Features::SPIRV_SHADER_PASSTHROUGH`], so it only works on Vulkan.
+    ///
+    /// # Safety
+    ///
+    /// See [`SpirV::from_bytes`].
+    pub unsafe fn from_spirv_passthrough(bytes: &[u8]) -> Result<Self, ShaderError> {
+        Ok(Self {
+            // SAFETY: Upheld by the caller.
+            spirv: Some(unsafe { SpirV::from_bytes(bytes) }?),
+            ..Self::default()
+        })
+    }
+}
+
+/// A precompiled SPIR-V module for [`ShaderDescriptor::spirv`].
+///
+/// It's handed to the driver as is, so (unlike with [`ShaderDescriptor::from_spirv`])
+/// [`Shader::new`] fails with
+/// [`ShaderError::SpirVUnsupported`] unless the device was created with
+/// [`wgpu::Features::SPIRV_SHADER_PASSTHROUGH`].
+#[derive(Clone, Debug)]
+pub struct SpirV<'a>(std::borrow::Cow<'a, [u32]>);
+
+impl SpirV<'_> {
+    const MAGIC: u32 = 0x0723_0203;
+
+    /// Read a module from its bytes, in either byte order.
+    ///
+    /// # Safety
+    ///
+    /// The module isn't validated by wgpu. It must be valid SPIR-V, and its entry points and
+    /// interface must match the rest of the descriptor it's used with.
+    pub unsafe fn from_bytes(bytes: &[u8]) -> Result<Self, ShaderError> {
+        Ok(Self(std::borrow::Cow::Owned(Self::words_from_bytes(bytes)?)))
+    }
+
+    /// The words of the module in `bytes`, in either byte order, checking only that it has
+    /// whole words and starts with the SPIR-V magic number.
+    pub fn words_from_bytes(bytes: &[u8]) -> Result<Vec<u32>, ShaderError> {
+        if bytes.is_empty() || !bytes.len().is_multiple_of(4) {
+            return Err(ShaderError::InvalidSpirV(format!(
+                "{} bytes isn't a whole number of words",
+                bytes.len(),
+            )));
+        }
+        let mut words: Vec<u32> = bytes
+            .chunks_exact(4)
+            .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+            .collect();
+        if words[0] == Self::MAGIC.swap_bytes() {
+            words.iter_mut().for_each(|word| *word = word.swap_bytes());
+        }
+        if words[0] != Self::MAGIC {
+            return Err(ShaderError::InvalidSpirV("missing the magic number".to_string()));
+        }
+
+        Ok(words)
+    }
+
+    pub fn words(&self) -> &[u32] {
+        &self.0
+    }
+}
+
+impl ShaderDescriptor<'_> {
+    /// The device features needed by the options this describes.
+    fn required_features(&self) -> wgpu::Features {
+        let mut features = wgpu::Features::empty();
+        if self.primitive.conservative {
+            features |= wgpu::Features::CONSERVATIVE_RASTERIZATION;
+        }
+        match self.primitive.polygon_mode {
+            wgpu::PolygonMode::Fill => {}
+            wgpu::PolygonMode::Line => features |= wgpu::Features::POLYGON_MODE_LINE,
+            wgpu::PolygonMode::Point => features |= wgpu::Features::POLYGON_MODE_POINT,
+        }
+
+        features
+    }
+
+    /// Check that the named entry points exist in a WGSL or SPIR-V source (for the stage
+    /// they're used for), so typos fail here instead of deep inside pipeline creation.
+    ///
+    /// NOTE: Sources that don't parse are left for wgpu to report, since its errors point at
+    ///       the problem. Passthrough SPIR-V isn't reflected at all.
+    fn validate_entry_points(&self) -> Result<(), ShaderError> {
+        if self.spirv.is_some() {
+            return Ok(());
+        }
+
+        validate_entry_points(&self.source, &[
+            (self.vertex_entry_point, wgpu::naga::ShaderStage::Vertex),
+            (self.fragment_entry_point, wgpu::naga::ShaderStage::Fragment),
+        ])
+    }
+}
+
+/// Check that `source` has each named entry point for its stage. Sources that naga can't parse
+/// (or that it isn't given) are left for wgpu to report.
+pub(crate) fn validate_entry_points(
+    source: &wgpu::ShaderSource,
+    entry_points: &[(Option<&str>, wgpu::naga::ShaderStage)],
+) -> Result<(), ShaderError> {
+    let module = match source {
+        wgpu::ShaderSource::Wgsl(source) => wgpu::naga::front::wgsl::parse_str(source).ok(),
+        wgpu::ShaderSource::SpirV(words) => spirv_module(words).ok(),
+        _ => None,
+    };
+    let Some(module) = module else {
+        return Ok(());
+    };
+
+    for &(name, stage) in entry_points {
+        let Some(name) = name else {
+            continue;
+        };
+        if !module.entry_points.iter().any(|e| e.name == name && e.stage == stage) {
+            return Err(ShaderError::MissingEntryPoint(name.to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Translate a SPIR-V module with naga, like wgpu does for [`wgpu::ShaderSource::SpirV`].
+fn spirv_module(words: &[u32]) -> Result<wgpu::naga::Module, ShaderError> {
+    let options = wgpu::naga::front::spv::Options::default();
+    wgpu::naga::front::spv::Frontend::new(words.iter().copied(), &options)
+        .parse()
+        .map_err(|error| ShaderError::InvalidSpirV(error.to_string()))
+}
+
+
+
+/// The space that a [`Renderer`]'s primitive positions and sizes are given in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CoordinateSpace {
+    /// Normalized device coordinates: `-1.0..=1.0` on both axes, with the origin at the center
+    /// and Y pointing up.
+    Ndc,
+    /// Pixels within a viewport of the given size, with the origin at the top-left and Y
+    /// pointing down.
+    ///
+    /// NOTE: Flipping Y reverses the winding of the emitted triangles once they're converted to
+    ///       clip space.
+    Pixels { width: f32, height: f32 },
+}
+
+impl CoordinateSpace {
+    pub fn to_ndc(&self, pos: [f32; 2]) -> [f32; 2] {
+        match *self {
+            Self::Ndc => pos,
+            Self::Pixels { width, height } => [
+                pos[0] / width * 2.0 - 1.0,
+                1.0 - pos[1] / height * 2.0,
+            ],
+        }
+    }
+
+    pub fn from_ndc(&self, pos: [f32; 2]) -> [f32; 2] {
+        match *self {
+            Self::Ndc => pos,
+            Self::Pixels { width, height } => [
+                (pos[0] + 1.0) * 0.5 * width,
+                (1.0 - pos[1]) * 0.5 * height,
+            ],
+        }
+    }
+
+    pub const fn is_y_down(&self) -> bool {
+        matches!(self, Self::Pixels { .. })
+    }
+
+    /// [`CoordinateSpace::to_ndc`] as a (column-major) matrix.
+    pub const fn to_ndc_matrix(&self) -> [[f32; 4]; 4] {
+        match *self {
+            Self::Ndc => crate::IDENTITY_MATRIX,
+            Self::Pixels { width, height } => crate::ortho_pixels(width, height, true),
+        }
+    }
+
+    /// [`CoordinateSpace::from_ndc`] as a (column-major) matrix.
+    pub const fn from_ndc_matrix(&self) -> [[f32; 4]; 4] {
+        match *self {
+            Self::Ndc => crate::IDENTITY_MATRIX,
+            Self::Pixels { width, height } => [
+                [width * 0.5, 0.0, 0.0, 0.0],
+                [0.0, -height * 0.5, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [width * 0.5, height * 0.5, 0.0, 1.0],
+            ],
         }
     }
 }
 
 
 
+/// A batch of primitives, collected on the CPU and uploaded with [`Renderer::finish`].
+///
+/// Every position and size passed to a renderer is interpreted in its [`CoordinateSpace`],
+/// which is fixed when the renderer is created (though a pixel space's viewport can be resized,
+/// see [`Renderer::set_viewport`]). Positions are converted to clip space in `finish`.
+///
+/// # Ordering
+///
+/// For a given sequence of `add_*` calls, the output is always the same, so it can be compared
+/// against golden snapshots:
+///
+/// - Each kind of primitive is collected into its own geometry, in the order it was added, and
+///   each primitive emits its vertices and indices in a fixed order (e.g. a [`Quad`]'s corners
+///   are its [`Quad::pos`] corner, then along its width, then along its height, then opposite).
+/// - [`Renderer::append`] puts the other renderer's geometry after this one's.
+/// - Deduplication (see [`Renderer::set_deduplicate_vertices`]) keeps each vertex where it first
+///   appeared.
+/// - Textured geometries are kept in the order their textures were first used.
+#[derive(Clone)]
 pub struct Renderer {
-    vertices: Vec<Vertex>,
-    indices: Vec<u32>,
+    space: CoordinateSpace,
+    pub(crate) vertices: Vec<Vertex>,
+    pub(crate) indices: Vec<u32>,
+    /// The vertex index at which each pick id (set by [`Renderer::set_pick_id`]) starts applying.
+    pick_ids: Vec<(usize, u32)>,
+    /// The vertex index at which each layer (started by [`Renderer::next_layer`]) starts.
+    layers: Vec<(usize, u32)>,
+    /// The vertices given a depth of their own (with
+    /// [`QuadBuilder::at_depth`](crate::QuadBuilder::at_depth)), which layers don't change.
+    pub(crate) depths: Vec<(std::ops::Range<usize>, f32)>,
+    deduplicate: bool,
+    index_width: IndexWidth,
+    /// Whether meshes are uploaded with index buffers (see [`Renderer::set_indexed`]).
+    indexed: bool,
+    /// The scale factor to snap quads to physical pixels with, if any.
+    pixel_snap: Option<f32>,
+    view_bounds: Option<Quad>,
+    culled: usize,
+    /// See [`Renderer::set_model_matrix`].
+    model_matrix: [[f32; 4]; 4],
+    /// See [`Renderer::set_keep_front_facing`].
+    keep_front_facing: bool,
+    /// How open strokes end (see [`Renderer::set_stroke_cap`]).
+    pub(crate) stroke_cap: crate::StrokeCap,
+    /// How many quads have been added, for [`Renderer::stats`].
+    pub(crate) quads: usize,
+    /// Named ranges of `indices`.
+    ranges: Vec<(String, std::ops::Range<u32>)>,
+    /// The name and start of the range currently being recorded.
+    open_range: Option<(String, u32)>,
+    /// The ranges of `indices` to outline in the debug wireframe.
+    pub(crate) wireframed: Vec<std::ops::Range<u32>>,
+    /// The start of the wireframed range currently being recorded.
+    pub(crate) wireframe_start: Option<u32>,
+    pub(crate) circles: Geometry<crate::CircleVertex>,
+    pub(crate) clipped: Geometry<crate::ClippedVertex>,
+    pub(crate) radial_gradients: Geometry<crate::GradientVertex>,
+    pub(crate) linear_gradients: Vec<crate::LinearGradientGeometry>,
+    pub(crate) textured: Vec<crate::TexturedGeometry>,
+    pub(crate) tinted_icons: Vec<crate::TexturedGeometry>,
+    pub(crate) texture_arrays: Vec<crate::TextureArrayGeometry>,
 }
 
 impl Renderer {
+    /// Start a renderer working in [`CoordinateSpace::Ndc`].
     pub fn start() -> Self {
+        Self::with_space(CoordinateSpace::Ndc)
+    }
+
+    pub fn with_space(space: CoordinateSpace) -> Self {
         Self {
+            space,
             vertices: Vec::new(),
             indices: Vec::new(),
+            pick_ids: Vec::new(),
+            layers: Vec::new(),
+            depths: Vec::new(),
+            deduplicate: false,
+            index_width: IndexWidth::Auto,
+            indexed: true,
+            pixel_snap: None,
+            view_bounds: None,
+            culled: 0,
+            model_matrix: crate::IDENTITY_MATRIX,
+            keep_front_facing: false,
+            stroke_cap: crate::StrokeCap::Flat,
+            quads: 0,
+            ranges: Vec::new(),
+            open_range: None,
+            wireframed: Vec::new(),
+            wireframe_start: None,
+            circles: Geometry::new(),
+            clipped: Geometry::new(),
+            radial_gradients: Geometry::new(),
+            linear_gradients: Vec::new(),
+            textured: Vec::new(),
+            tinted_icons: Vec::new(),
+            texture_arrays: Vec::new(),
         }
     }
 
-    pub fn finish(self, device: &wgpu::Device, ) -> (wgpu::Buffer, wgpu::Buffer, u32) {
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Vertex Buffer"),
-            contents: bytemuck::cast_slice(&self.vertices),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Index Buffer"),
-            contents: bytemuck::cast_slice(&self.indices),
-            usage: wgpu::BufferUsages::INDEX,
-        });
+    pub fn space(&self) -> CoordinateSpace {
+        self.space
+    }
 
-        (vertex_buffer, index_buffer, self.indices.len() as u32)
+    /// Change the size of the viewport that [`CoordinateSpace::Pixels`] positions are given in,
+    /// e.g. when the window is resized. Everything (including what was added before this call)
+    /// is converted with the size given last. This does nothing in [`CoordinateSpace::Ndc`].
+    pub fn set_viewport(&mut self, width: u32, height: u32) {
+        if let CoordinateSpace::Pixels { .. } = self.space {
+            self.space = CoordinateSpace::Pixels {
+                width: width as f32,
+                height: height as f32,
+            };
+        }
     }
 
-    pub fn add_quad(&mut self, quad: &Quad, color: [f32; 3]) {
-        self.indices.reserve_exact(Quad::num_indices() as usize);
-        self.vertices.reserve_exact(Quad::num_vertices() as usize);
+    /// The size of the viewport for [`CoordinateSpace::Pixels`] (rounded to whole pixels), or
+    /// `None` in [`CoordinateSpace::Ndc`].
+    pub fn viewport(&self) -> Option<(u32, u32)> {
+        match self.space {
+            CoordinateSpace::Ndc => None,
+            CoordinateSpace::Pixels { width, height } => {
+                Some((width.round() as u32, height.round() as u32))
+            }
+        }
+    }
 
-        self.indices.extend(Quad::indices_u32().into_iter());
-        quad.push_with_color(color, &mut self.vertices);
+    /// The solid-color vertices added so far, in this renderer's coordinate space.
+    pub fn vertices(&self) -> &[Vertex] {
+        &self.vertices
     }
 
-    pub fn add_quads(&mut self, quads: &[Quad], color: [f32; 3]) {
-        self.indices.reserve_exact(quads.len() * Quad::num_indices() as usize);
-        self.vertices.reserve_exact(quads.len() * Quad::num_vertices() as usize);
+    /// The indices into [`Renderer::vertices`] added so far.
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
 
-        self.indices.extend(Quad::indices_u32().repeat(quads.len()));
-        for quad in quads {
-            quad.push_with_color(color, &mut self.vertices);
+    /// Tag every primitive added after this call with `id` (until the next call), so that it can
+    /// be found with a [`Picker`](crate::Picker). An id of `0` means "not pickable".
+    pub fn set_pick_id(&mut self, id: u32) {
+        self.pick_ids.push((self.vertices.len(), id));
+    }
+
+    fn current_pick_id(&self) -> u32 {
+        self.pick_ids.last().map_or(0, |(_, id)| *id)
+    }
+
+    /// The last layer with a depth of its own (see [`Renderer::layer_depth`]).
+    pub const MAX_LAYER: u32 = 1 << 16;
+
+    /// Put every solid primitive added after this call on a new layer, in front of everything
+    /// added before it, and return that layer's number (counting up from `0`).
+    ///
+    /// Layers set the depth of [`Batch::solid`]'s vertices, so drawing it with
+    /// [`Shader::solid_layered`] and a [`DepthAttachment`](crate::DepthAttachment) keeps this
+    /// painter's order even when it's drawn out of order (like in several batches, or after
+    /// meshes meant to go on top). Primitives on the same layer are still drawn in the order
+    /// they were added.
+    ///
+    /// NOTE: Translucent primitives blend with whatever was drawn before them, so the depth
+    ///       test only puts them in front of what's behind them if that was drawn first.
+    pub fn next_layer(&mut self) -> u32 {
+        let layer = self.layer() + 1;
+        self.layers.push((self.vertices.len(), layer));
+        layer
+    }
+
+    /// The layer that solid primitives are being added to.
+    pub fn layer(&self) -> u32 {
+        self.layers.last().map_or(0, |(_, layer)| *layer)
+    }
+
+    /// The depth of the vertices on `layer`, from `1.0` (the far plane, where the first layer
+    /// is) towards `0.0` at [`Renderer::MAX_LAYER`] (and beyond).
+    pub const fn layer_depth(layer: u32) -> f32 {
+        let layer = if layer < Self::MAX_LAYER { layer } else { Self::MAX_LAYER };
+        1.0 - layer as f32 / Self::MAX_LAYER as f32
+    }
+
+    /// Start recording the solid primitives added from now on as a range of
+    /// [`Batch::solid`]'s indices called `name`, ending any range that was already open. The
+    /// ranges can be found with [`Batch::range`] after `finish`.
+    pub fn begin_range(&mut self, name: impl Into<String>) {
+        self.end_range();
+        self.open_range = Some((name.into(), self.indices.len() as u32));
+    }
+
+    /// Stop recording the current range, if any.
+    pub fn end_range(&mut self) {
+        if let Some((name, start)) = self.open_range.take() {
+            self.ranges.push((name, start..self.indices.len() as u32));
         }
     }
-}
 
+    /// Whether [`Renderer::finish`] should merge identical vertices (same position, color, and
+    /// pick id) before uploading. Off by default, since it costs a hash per vertex.
+    pub fn set_deduplicate_vertices(&mut self, deduplicate: bool) {
+        self.deduplicate = deduplicate;
+    }
 
+    /// Round the edges of quads to whole physical pixels, given the number of physical pixels per
+    /// unit of this renderer's [`CoordinateSpace::Pixels`] (the window's scale factor, for
+    /// logical pixels). This keeps thin borders crisp. `None` (the default) leaves quads alone,
+    /// and this has no effect in [`CoordinateSpace::Ndc`].
+    pub fn set_pixel_snap(&mut self, scale_factor: Option<f32>) {
+        self.pixel_snap = scale_factor.filter(|scale| *scale > 0.0);
+    }
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug)]
-#[derive(bytemuck::Pod, bytemuck::Zeroable)]
-pub struct Vertex {
-    pos: [f32; 2],
-    color: [f32; 3],
-}
+    pub fn pixel_snap(&self) -> Option<f32> {
+        self.pixel_snap
+    }
 
-impl Vertex {
-    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
-        wgpu::VertexBufferLayout {
-            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: &[
-                wgpu::VertexAttribute {
-                    offset: 0,
-                    shader_location: 0,
-                    format: wgpu::VertexFormat::Float32x2,
-                },
-                wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
-                    shader_location: 1,
-                    format: wgpu::VertexFormat::Float32x3,
-                },
-            ]
+    /// `quad`, with its edges snapped to physical pixels if that's turned on.
+    pub(crate) fn snap(&self, quad: &Quad) -> Quad {
+        match self.pixel_snap {
+            Some(scale) if self.space.is_y_down() => {
+                let snap = |v: f32| (v * scale).round() / scale;
+                let min = quad.pos.map(snap);
+                let max = [quad.pos[0] + quad.size[0], quad.pos[1] + quad.size[1]].map(snap);
+                // NOTE: Quads thinner than a pixel are kept one pixel thick, rather than being
+                //       snapped away entirely.
+                let size = [0, 1].map(|axis| match max[axis] - min[axis] {
+                    0.0 if quad.size[axis] > 0.0 => 1.0 / scale,
+                    size => size,
+                });
+                Quad::new(min, size)
+            }
+            _ => *quad,
         }
     }
-}
 
-pub struct Quad {
-    pub pos: [f32; 2],
-    pub size: [f32; 2],
-}
+    /// Skip quads (and rounded quads) that lie entirely outside of `bounds`, given in this
+    /// renderer's space. `None` (the default) keeps everything.
+    pub fn set_view_bounds(&mut self, bounds: Option<Quad>) {
+        self.view_bounds = bounds;
+    }
 
-// Constants.
-impl Quad {
-    pub const fn num_indices() -> u32 {
-        6
+    pub fn view_bounds(&self) -> Option<Quad> {
+        self.view_bounds
     }
 
-    pub const fn num_vertices() -> u32 {
-        4
+    /// How many primitives have been skipped for being outside of the view bounds.
+    pub fn culled_count(&self) -> usize {
+        self.culled
     }
 
-    pub const fn indices_u16() -> [u16; 6] {
-        [0, 1, 2, 2, 1, 3]
+    /// A summary of everything that's been added so far.
+    pub fn stats(&self) -> RenderStats {
+        let index_size = self.index_width.format_for(&self.indices).byte_size();
+        let mut stats = RenderStats {
+            quads: self.quads,
+            vertices: self.vertices.len(),
+            indices: self.indices.len(),
+            draw_calls: 1,
+            buffer_bytes: self.vertices.len() * size_of::<Vertex>()
+                + self.indices.len() * index_size,
+        };
+        stats.add_geometry(&self.circles, self.index_width);
+        stats.add_geometry(&self.clipped, self.index_width);
+        stats.add_geometry(&self.radial_gradients, self.index_width);
+        for gradient in &self.linear_gradients {
+            stats.add_geometry(&gradient.geometry, self.index_width);
+        }
+        for textured in self.textured.iter().chain(&self.tinted_icons) {
+            stats.add_geometry(&textured.geometry, self.index_width);
+        }
+        for array in &self.texture_arrays {
+            stats.add_geometry(&array.geometry, self.index_width);
+        }
+
+        stats
     }
 
-    pub const fn indices_u32() -> [u32; 6] {
-        [0, 1, 2, 2, 1, 3]
+    /// The smallest and largest coordinates of every vertex added so far (of every kind of
+    /// primitive), in this renderer's coordinate space. `None` when nothing has been added.
+    ///
+    /// NOTE: This covers the geometry as it was emitted, so anti-aliased edges that are grown in
+    ///       their shaders (like circles') can reach slightly beyond it.
+    pub fn bounds(&self) -> Option<([f32; 2], [f32; 2])> {
+        let positions = self.vertices
+            .iter()
+            .map(Positioned::pos)
+            .chain(self.circles.positions())
+            .chain(self.clipped.positions())
+            .chain(self.radial_gradients.positions())
+            .chain(self.linear_gradients.iter().flat_map(|gradient| gradient.geometry.positions()))
+            .chain(self.textured.iter().chain(&self.tinted_icons).flat_map(|textured| {
+                textured.geometry.positions()
+            }))
+            .chain(self.texture_arrays.iter().flat_map(|array| array.geometry.positions()));
+
+        positions.fold(None, |bounds, [x, y]| {
+            let (min, max) = bounds.unwrap_or(([x, y], [x, y]));
+            Some(([min[0].min(x), min[1].min(y)], [max[0].max(x), max[1].max(y)]))
+        })
     }
-}
 
-impl Quad {
-    pub const fn new(pos: [f32; 2], size: [f32; 2]) -> Self {
-        Self { pos, size }
+    /// Whether `quad` should be skipped for being outside of the view bounds, counting it if so.
+    pub(crate) fn cull(&mut self, quad: &Quad) -> bool {
+        let culled = self.view_bounds.is_some_and(|bounds| !bounds.overlaps(quad));
+        self.culled += culled as usize;
+
+        culled
     }
 
-    pub fn push_with_color(&self, color: [f32; 3], out: &mut Vec<Vertex>) {
-        out.extend([
-            Vertex { pos: self.pos, color },
-            Vertex { pos: [self.pos[0] + self.size[0], self.pos[1]], color },
-            Vertex { pos: [self.pos[0], self.pos[1] + self.size[1]], color },
-            Vertex { pos: [self.pos[0] + self.size[0], self.pos[1] + self.size[1]], color },
-        ]);
+    /// Choose the width of the indices uploaded by [`Renderer::finish`]. Defaults to
+    /// [`IndexWidth::Auto`].
+    pub fn set_index_width(&mut self, width: IndexWidth) {
+        self.index_width = width;
+    }
+
+    pub fn index_width(&self) -> IndexWidth {
+        self.index_width
+    }
+
+    /// The format to pass to `set_index_buffer` for meshes produced by this renderer.
+    ///
+    /// With [`IndexWidth::Auto`], each mesh has its own (see [`Mesh::index_format`]), and this
+    /// is only the widest of them.
+    pub fn index_format(&self) -> wgpu::IndexFormat {
+        self.index_width.format()
+    }
+
+    /// Choose whether [`Renderer::finish`] uploads meshes with index buffers (the default), or
+    /// expands them into one vertex per index (see [`Mesh::unindexed`]) for backends with
+    /// broken index support.
+    pub fn set_indexed(&mut self, indexed: bool) {
+        self.indexed = indexed;
+    }
+
+    pub fn indexed(&self) -> bool {
+        self.indexed
+    }
+
+    /// Set the matrix that the finished batch is drawn with (see [`Batch::model`]), in this
+    /// renderer's coordinate space. Defaults to the identity.
+    ///
+    /// NOTE: This isn't baked into the vertices, so it only applies when drawing with
+    ///       [`Shader::solid_model`](crate::Shader::solid_model).
+    pub fn set_model_matrix(&mut self, matrix: [[f32; 4]; 4]) {
+        self.model_matrix = matrix;
+    }
+
+    pub fn model_matrix(&self) -> [[f32; 4]; 4] {
+        self.model_matrix
+    }
+
+    /// Whether to reverse the winding of every triangle in `finish` when this renderer's space
+    /// is Y-down, so that what winds counter-clockwise on screen still does in clip space and
+    /// survives back-face culling (like [`Shader::solid_culled`] with [`wgpu::Face::Back`]). Off
+    /// by default, which keeps the index order exactly as added. This does nothing in Y-up
+    /// spaces.
+    pub fn set_keep_front_facing(&mut self, keep: bool) {
+        self.keep_front_facing = keep;
+    }
+
+    pub fn keep_front_facing(&self) -> bool {
+        self.keep_front_facing
+    }
+
+    /// The width meshes are uploaded with, or `None` for unindexed meshes.
+    fn mesh_width(&self) -> Option<IndexWidth> {
+        self.indexed.then_some(self.index_width)
+    }
+
+    /// Upload everything that's been added. Finish a clone to keep the renderer around, e.g. to
+    /// upload the same layout again after [`Renderer::set_viewport`].
+    ///
+    /// # Panics
+    ///
+    /// If any kind of primitive fails [`validate_indices`], e.g. with [`IndexWidth::U16`] and
+    /// more vertices than a `u16` can index.
+    pub fn finish(mut self, device: &wgpu::Device) -> Batch {
+        if self.space != CoordinateSpace::Ndc {
+            for vertex in &mut self.vertices {
+                vertex.pos = self.space.to_ndc(vertex.pos);
+            }
+        }
+        self.tag_vertices();
+        if self.deduplicate {
+            self.deduplicate_vertices();
+        }
+        self.end_range();
+        self.set_debug_wireframe(false);
+
+        self.circles.convert(self.space, CoordinateSpace::Ndc);
+        self.clipped.convert(self.space, CoordinateSpace::Ndc);
+        self.radial_gradients.convert(self.space, CoordinateSpace::Ndc);
+        for gradient in &mut self.linear_gradients {
+            gradient.geometry.convert(self.space, CoordinateSpace::Ndc);
+        }
+        for textured in self.textured.iter_mut().chain(&mut self.tinted_icons) {
+            textured.geometry.convert(self.space, CoordinateSpace::Ndc);
+        }
+        for array in &mut self.texture_arrays {
+            array.geometry.convert(self.space, CoordinateSpace::Ndc);
+        }
+        self.keep_front_faces();
+
+        let width = self.mesh_width();
+        let solid_format = width.map(|width| width.format_for(&self.indices));
+        Batch {
+            solid: Mesh::upload(device, &self.vertices, &self.indices, solid_format, Some("Solid")),
+            circles: self.circles.upload(device, width, "Circle"),
+            clipped: self.clipped.upload(device, width, "Clipped"),
+            radial_gradients: self.radial_gradients.upload(device, width, "Radial Gradient"),
+            linear_gradients: crate::LinearGradientMesh::upload_all(
+                &self.linear_gradients,
+                device,
+                width,
+            ),
+            textured: crate::TexturedMesh::upload_all(&self.textured, device, width, "Textured"),
+            tinted_icons: crate::TexturedMesh::upload_all(
+                &self.tinted_icons,
+                device,
+                width,
+                "Tinted Icons",
+            ),
+            texture_arrays: crate::TexturedMesh::upload_arrays(&self.texture_arrays, device, width),
+            wireframe: self.upload_wireframe(device, width),
+            ranges: self.ranges,
+            model: std::sync::OnceLock::new(),
+            model_source: (self.space, self.model_matrix),
+        }
+    }
+
+    /// Reverse the winding of every triangle if [`Renderer::set_keep_front_facing`] asks for it.
+    pub(crate) fn keep_front_faces(&mut self) {
+        if !self.keep_front_facing || !self.space.is_y_down() {
+            return;
+        }
+        flip_winding(&mut self.indices);
+        flip_winding(&mut self.circles.indices);
+        flip_winding(&mut self.clipped.indices);
+        flip_winding(&mut self.radial_gradients.indices);
+        for gradient in &mut self.linear_gradients {
+            flip_winding(&mut gradient.geometry.indices);
+        }
+        for textured in self.textured.iter_mut().chain(&mut self.tinted_icons) {
+            flip_winding(&mut textured.geometry.indices);
+        }
+        for array in &mut self.texture_arrays {
+            flip_winding(&mut array.geometry.indices);
+        }
+    }
+
+    /// Write the pick id and depth that each vertex ended up with into it.
+    pub(crate) fn tag_vertices(&mut self) {
+        for (i, &(start, id)) in self.pick_ids.iter().enumerate() {
+            let end = self.pick_ids.get(i + 1).map_or(self.vertices.len(), |(end, _)| *end);
+            for vertex in &mut self.vertices[start..end] {
+                vertex.id = id;
+            }
+        }
+        for (i, &(start, layer)) in self.layers.iter().enumerate() {
+            let end = self.layers.get(i + 1).map_or(self.vertices.len(), |(end, _)| *end);
+            for vertex in &mut self.vertices[start..end] {
+                vertex.depth = Self::layer_depth(layer);
+            }
+        }
+        for (range, depth) in &self.depths {
+            for vertex in &mut self.vertices[range.clone()] {
+                vertex.depth = *depth;
+            }
+        }
+    }
+
+    /// Keep only the first occurrence of each distinct vertex, pointing every index at it.
+    fn deduplicate_vertices(&mut self) {
+        // NOTE: The map is only used for lookups, so the output order (that of each vertex's
+        //       first appearance) doesn't depend on its iteration order.
+        let mut unique = Vec::with_capacity(self.vertices.len());
+        let mut seen = std::collections::HashMap::with_capacity(self.vertices.len());
+        let remap: Vec<u32> = self.vertices
+            .iter()
+            .map(|vertex| {
+                let key: [u32; std::mem::size_of::<Vertex>() / 4] = bytemuck::cast(*vertex);
+                *seen.entry(key).or_insert_with(|| {
+                    unique.push(*vertex);
+                    unique.len() as u32 - 1
+                })
+            })
+            .collect();
+
+        for index in &mut self.indices {
+            *index = remap[*index as usize];
+        }
+        self.vertices = unique;
+    }
+
+    /// Move all of `other`'s geometry onto the end of this batch, converting it into this
+    /// renderer's coordinate space if needed. This renderer's model matrix is kept, and
+    /// `other`'s layers are put in front of this renderer's (see [`Renderer::next_layer`]).
+    pub fn append(&mut self, mut other: Renderer) {
+        other.end_range();
+        other.set_debug_wireframe(false);
+        let index_base = self.indices.len() as u32;
+        self.ranges.extend(other.ranges.drain(..).map(|(name, range)| {
+            (name, range.start + index_base..range.end + index_base)
+        }));
+        self.wireframed.extend(other.wireframed.drain(..).map(|range| {
+            range.start + index_base..range.end + index_base
+        }));
+
+        let base = self.vertices.len() as u32;
+        let resume_id = self.current_pick_id();
+        self.pick_ids.push((base as usize, 0));
+        self.pick_ids.extend(other.pick_ids.iter().map(|(start, id)| (base as usize + start, *id)));
+        if other.space == self.space {
+            self.vertices.extend(other.vertices);
+        } else {
+            self.vertices.extend(other.vertices.into_iter().map(|vertex| Vertex {
+                pos: self.space.from_ndc(other.space.to_ndc(vertex.pos)),
+                ..vertex
+            }));
+        }
+        self.indices.extend(other.indices.into_iter().map(|i| base + i));
+        self.set_pick_id(resume_id);
+        // NOTE: The other renderer's layers go in front of the current one (so its layer `0`
+        //       is this one's next layer), and the ones added after this carry on from them.
+        let layer = self.layer() + 1;
+        self.layers.extend(
+            std::iter::once((0, 0))
+                .chain(other.layers)
+                .map(|(start, other_layer)| (base as usize + start, layer + other_layer)),
+        );
+        self.depths.extend(other.depths.into_iter().map(|(range, depth)| {
+            (range.start + base as usize..range.end + base as usize, depth)
+        }));
+        self.culled += other.culled;
+        self.quads += other.quads;
+
+        self.circles.append(other.circles, other.space, self.space);
+        self.clipped.append(other.clipped, other.space, self.space);
+        self.radial_gradients.append(other.radial_gradients, other.space, self.space);
+        let space = self.space;
+        for gradient in other.linear_gradients {
+            crate::stops_geometry_for(&mut self.linear_gradients, gradient.stops)
+                .append(gradient.geometry, other.space, space);
+        }
+        for textured in other.textured {
+            self.textured_geometry(&textured.view, &textured.sampler)
+                .append(textured.geometry, other.space, space);
+        }
+        for icons in other.tinted_icons {
+            crate::geometry_for(&mut self.tinted_icons, &icons.view, &icons.sampler)
+                .append(icons.geometry, other.space, space);
+        }
+        for array in other.texture_arrays {
+            crate::array_geometry_for(&mut self.texture_arrays, &array.bind_group)
+                .append(array.geometry, other.space, space);
+        }
+    }
+
+    pub(crate) fn reserve(&mut self, vertices: usize, indices: usize) {
+        self.vertices.reserve_exact(vertices);
+        self.indices.reserve_exact(indices);
+    }
+
+    /// Add a mesh whose `indices` are relative to the start of `vertices`.
+    pub(crate) fn add_mesh(
+        &mut self,
+        vertices: impl IntoIterator<Item = Vertex>,
+        indices: impl IntoIterator<Item = u32>,
+    ) {
+        let base = self.vertices.len() as u32;
+        self.vertices.extend(vertices);
+        self.indices.extend(indices.into_iter().map(|i| base + i));
+    }
+
+    /// Add a quad. Degenerate quads (see [`Quad::is_degenerate`]) are skipped.
+    pub fn add_quad(&mut self, quad: &Quad, color: [f32; 3]) {
+        let quad = &self.snap(quad);
+        if quad.is_degenerate() || self.cull(quad) {
+            return;
+        }
+
+        self.indices.reserve_exact(Quad::num_indices() as usize);
+        self.vertices.reserve_exact(Quad::num_vertices() as usize);
+
+        let base = self.vertices.len() as u32;
+        self.indices.extend(Quad::indices_u32().map(|i| base + i));
+        quad.push_with_color(color, &mut self.vertices);
+        self.quads += 1;
+    }
+
+    /// Like [`Renderer::add_quad`], but returning an id that the quad's color can be changed
+    /// with after `finish` (see [`Mesh::update_quad_color`]), or `None` if it was skipped.
+    ///
+    /// NOTE: Ids are only valid for the [`Batch::solid`] mesh of this renderer, and not if its
+    ///       vertices are deduplicated (which shares them between quads).
+    pub fn add_quad_with_id(&mut self, quad: &Quad, color: [f32; 3]) -> Option<QuadId> {
+        let first_vertex = self.vertices.len() as u32;
+        self.add_quad(quad, color);
+
+        (self.vertices.len() as u32 > first_vertex).then_some(QuadId { first_vertex })
+    }
+
+    pub fn add_quads(&mut self, quads: &[Quad], color: [f32; 3]) {
+        self.indices.reserve_exact(quads.len() * Quad::num_indices() as usize);
+        self.vertices.reserve_exact(quads.len() * Quad::num_vertices() as usize);
+
+        for quad in quads {
+            let quad = &self.snap(quad);
+            if quad.is_degenerate() || self.cull(quad) {
+                continue;
+            }
+
+            let base = self.vertices.len() as u32;
+            self.indices.extend(Quad::indices_u32().map(|i| base + i));
+            quad.push_with_color(color, &mut self.vertices);
+            self.quads += 1;
+        }
+    }
+
+    /// Add a rounded quad. Like [`Renderer::add_quad`], degenerate quads are skipped.
+    pub fn add_rounded_quad(&mut self, quad: &RoundedQuad, color: [f32; 3]) {
+        let bounds = Quad::new(quad.pos, quad.size);
+        if bounds.is_degenerate() || self.cull(&bounds) {
+            return;
+        }
+
+        self.indices.reserve_exact(RoundedQuad::num_indices() as usize);
+        self.vertices.reserve_exact(RoundedQuad::num_vertices() as usize);
+
+        if self.space.is_y_down() {
+            // NOTE: `RoundedQuad` names its corners assuming Y points up.
+            let [tl, tr, br, bl] = quad.radii;
+            let flipped = RoundedQuad::with_radii(quad.pos, quad.size, [bl, br, tr, tl]);
+            flipped.push_with_color(color, &mut self.vertices, &mut self.indices);
+        } else {
+            quad.push_with_color(color, &mut self.vertices, &mut self.indices);
+        }
+        self.quads += 1;
+    }
+
+    /// Add many rounded quads of the same `radius` (each given as its position and size),
+    /// clamped per quad like [`RoundedQuad::clamped_radii`]. This emits exactly what calling
+    /// [`Renderer::add_rounded_quad`] for each of them would, but only tessellates the corners
+    /// once.
+    pub fn add_rounded_quads(
+        &mut self,
+        quads: &[([f32; 2], [f32; 2])],
+        radius: f32,
+        color: [f32; 3],
+    ) {
+        const SEGMENTS: usize = RoundedQuad::CORNER_SEGMENTS as usize;
+        const PERIMETER: usize = 4 * (SEGMENTS + 1);
+
+        self.indices.reserve_exact(quads.len() * RoundedQuad::num_indices() as usize);
+        self.vertices.reserve_exact(quads.len() * RoundedQuad::num_vertices() as usize);
+
+        // NOTE: Every corner is the same arc, just scaled and moved, so the unit offsets along
+        //       the perimeter (in `RoundedQuad::push_with_color`'s order) and the indices of the
+        //       fan are the same for every quad. With equal radii, flipping Y changes nothing.
+        let mut offsets = [[0.0; 2]; PERIMETER];
+        for (corner, start) in [-0.5, 0.0, 0.5, 1.0].into_iter().enumerate() {
+            for i in 0..=SEGMENTS {
+                let angle = std::f32::consts::PI * start
+                    + std::f32::consts::FRAC_PI_2 * (i as f32 / SEGMENTS as f32);
+                offsets[corner * (SEGMENTS + 1) + i] = [angle.cos(), angle.sin()];
+            }
+        }
+        let mut fan = [0; 3 * PERIMETER];
+        for i in 0..PERIMETER {
+            let i_u32 = i as u32;
+            fan[3 * i..3 * i + 3]
+                .copy_from_slice(&[0, 1 + i_u32, 1 + (i_u32 + 1) % PERIMETER as u32]);
+        }
+
+        for &(pos, size) in quads {
+            let bounds = Quad::new(pos, size);
+            if bounds.is_degenerate() || self.cull(&bounds) {
+                continue;
+            }
+
+            let [x, y] = pos;
+            let [w, h] = size;
+            let r = radius.max(0.0).min(w.abs() * 0.5).min(h.abs() * 0.5);
+            let centers = [
+                [x + w - r, y + r],
+                [x + w - r, y + h - r],
+                [x + r, y + h - r],
+                [x + r, y + r],
+            ];
+
+            let base = self.vertices.len() as u32;
+            self.vertices.push(Vertex::new([x + w * 0.5, y + h * 0.5], color));
+            for (i, offset) in offsets.iter().enumerate() {
+                let center = centers[i / (SEGMENTS + 1)];
+                self.vertices.push(Vertex::new(
+                    [center[0] + r * offset[0], center[1] + r * offset[1]],
+                    color,
+                ));
+            }
+            self.indices.extend(fan.iter().map(|i| base + i));
+            self.quads += 1;
+        }
+    }
+}
+
+
+
+/// Reverse the winding of each triangle in a triangle list, keeping the triangles in order.
+fn flip_winding(indices: &mut [u32]) {
+    for triangle in indices.chunks_exact_mut(3) {
+        triangle.swap(1, 2);
+    }
+}
+
+/// What a [`Renderer`] will draw, as reported by [`Renderer::stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    /// Quads and rounded quads, including the ones that make up text.
+    pub quads: usize,
+    /// Vertices of every kind of primitive.
+    pub vertices: usize,
+    /// Indices of every kind of primitive.
+    pub indices: usize,
+    /// The meshes [`Renderer::finish`] will produce, which each take one draw call. The solid
+    /// mesh is always counted, even when it's empty.
+    pub draw_calls: usize,
+    /// The estimated size of the uploaded vertex and index buffers.
+    pub buffer_bytes: usize,
+}
+
+impl RenderStats {
+    fn add_geometry<V>(&mut self, geometry: &Geometry<V>, width: IndexWidth) {
+        if geometry.indices.is_empty() {
+            return;
+        }
+        self.vertices += geometry.vertices.len();
+        self.indices += geometry.indices.len();
+        self.draw_calls += 1;
+        self.buffer_bytes += geometry.vertices.len() * size_of::<V>()
+            + geometry.indices.len() * width.format_for(&geometry.indices).byte_size();
+    }
+}
+
+
+
+/// A vertex type with a position in a renderer's [`CoordinateSpace`].
+pub(crate) trait Positioned: bytemuck::Pod {
+    fn pos(&self) -> [f32; 2];
+    fn pos_mut(&mut self) -> &mut [f32; 2];
+
+    /// Move this vertex from the `from` space into the `to` space.
+    fn convert(&mut self, from: CoordinateSpace, to: CoordinateSpace) {
+        let pos = self.pos_mut();
+        *pos = to.from_ndc(from.to_ndc(*pos));
+    }
+}
+
+impl Positioned for Vertex {
+    fn pos(&self) -> [f32; 2] {
+        self.pos
+    }
+
+    fn pos_mut(&mut self) -> &mut [f32; 2] {
+        &mut self.pos
+    }
+}
+
+/// CPU-side geometry for one kind of vertex.
+#[derive(Clone)]
+pub(crate) struct Geometry<V> {
+    pub(crate) vertices: Vec<V>,
+    pub(crate) indices: Vec<u32>,
+}
+
+impl<V> Geometry<V> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    /// Add a mesh whose `indices` are relative to the start of `vertices`.
+    pub(crate) fn add(
+        &mut self,
+        vertices: impl IntoIterator<Item = V>,
+        indices: impl IntoIterator<Item = u32>,
+    ) {
+        let base = self.vertices.len() as u32;
+        self.vertices.extend(vertices);
+        self.indices.extend(indices.into_iter().map(|i| base + i));
+    }
+}
+
+impl<V: Positioned> Geometry<V> {
+    pub(crate) fn positions(&self) -> impl Iterator<Item = [f32; 2]> + '_ {
+        self.vertices.iter().map(Positioned::pos)
+    }
+
+    pub(crate) fn convert(&mut self, from: CoordinateSpace, to: CoordinateSpace) {
+        if from == to {
+            return;
+        }
+        for vertex in &mut self.vertices {
+            vertex.convert(from, to);
+        }
+    }
+
+    /// Move `other`'s geometry (in the `from` space) onto the end of this (in the `to` space).
+    pub(crate) fn append(&mut self, mut other: Self, from: CoordinateSpace, to: CoordinateSpace) {
+        other.convert(from, to);
+        self.add(other.vertices, other.indices);
+    }
+
+    /// `None` if there's nothing to upload. Unindexed when `width` is `None` (see
+    /// [`Mesh::upload`]).
+    pub(crate) fn upload(
+        &self,
+        device: &wgpu::Device,
+        width: Option<IndexWidth>,
+        label: &str,
+    ) -> Option<Mesh> {
+        let format = width.map(|width| width.format_for(&self.indices));
+        (!self.indices.is_empty()).then(|| {
+            Mesh::upload(device, &self.vertices, &self.indices, format, Some(label))
+        })
+    }
+}
+
+
+
+/// Everything produced by [`Renderer::finish`]. Each kind of primitive is uploaded as its own
+/// [`Mesh`], which must be drawn with a shader made for its vertex type.
+pub struct Batch {
+    /// Solid-color primitives, made of [`Vertex`]es.
+    pub solid: Mesh,
+    /// Radial gradients, made of [`GradientVertex`](crate::GradientVertex)es. Draw this with
+    /// [`Shader::radial_gradient`](crate::Shader::radial_gradient).
+    pub radial_gradients: Option<Mesh>,
+    /// Anti-aliased circles and outlines, made of [`CircleVertex`](crate::CircleVertex)es. Draw
+    /// this with [`Shader::circle`](crate::Shader::circle), binding an
+    /// [`EdgeFeather`](crate::EdgeFeather).
+    pub circles: Option<Mesh>,
+    /// Quads clipped to their own rects, made of [`ClippedVertex`](crate::ClippedVertex)es.
+    /// Draw this with [`Shader::clipped`](crate::Shader::clipped).
+    pub clipped: Option<Mesh>,
+    /// Linear gradients, one mesh per set of color stops. Draw these with
+    /// [`Shader::linear_gradient`](crate::Shader::linear_gradient), binding each one's
+    /// `bind_group`.
+    pub linear_gradients: Vec<crate::LinearGradientMesh>,
+    /// Textured primitives, one mesh per texture. Draw these with
+    /// [`Shader::textured`](crate::Shader::textured), binding each one's `bind_group`.
+    pub textured: Vec<crate::TexturedMesh>,
+    /// Tinted alpha masks, one mesh per mask texture. Draw these with
+    /// [`Shader::tinted_mask`](crate::Shader::tinted_mask), binding each one's `bind_group`.
+    pub tinted_icons: Vec<crate::TexturedMesh>,
+    /// Quads sampling batched [`TextureSet`](crate::TextureSet)s, one mesh per set. Draw these
+    /// with [`Shader::texture_array`](crate::Shader::texture_array) (made for the set's size),
+    /// binding each one's `bind_group`.
+    pub texture_arrays: Vec<crate::TexturedMesh>,
+    /// Line lists outlining the triangles of `solid` added with
+    /// [`Renderer::set_debug_wireframe`] on. Draw this with
+    /// [`Shader::wireframe`](crate::Shader::wireframe), after `solid`.
+    pub wireframe: Option<Mesh>,
+    /// The named ranges of `solid`'s indices recorded with [`Renderer::begin_range`].
+    pub ranges: Vec<(String, std::ops::Range<u32>)>,
+    /// See [`Batch::model`].
+    model: std::sync::OnceLock<crate::ModelMatrix>,
+    /// The space and matrix that `model` is uploaded with.
+    model_source: (CoordinateSpace, [[f32; 4]; 4]),
+}
+
+impl Batch {
+    /// The matrix set with [`Renderer::set_model_matrix`]. Bind this when drawing `solid` with
+    /// [`Shader::solid_model`](crate::Shader::solid_model).
+    ///
+    /// NOTE: Most batches are never drawn with a model matrix, so its uniform is only created
+    ///       the first time this (or [`Batch::model_mut`]) is called.
+    pub fn model(&self, device: &wgpu::Device) -> &crate::ModelMatrix {
+        self.model.get_or_init(|| {
+            let (space, matrix) = self.model_source;
+            crate::ModelMatrix::new(device, space, matrix)
+        })
+    }
+
+    /// [`Batch::model`], to [`ModelMatrix::set`] it and move the batch without rebuilding it.
+    ///
+    /// [`ModelMatrix::set`]: crate::ModelMatrix::set
+    pub fn model_mut(&mut self, device: &wgpu::Device) -> &mut crate::ModelMatrix {
+        self.model(device);
+        self.model.get_mut().expect("the model matrix was just uploaded")
+    }
+
+    /// The indices of `solid` that were added while the range called `name` was open.
+    pub fn range(&self, name: &str) -> Option<std::ops::Range<u32>> {
+        self.ranges
+            .iter()
+            .find_map(|(range_name, range)| (range_name == name).then(|| range.clone()))
+    }
+}
+
+/// The size of each index in a [`Mesh`]'s index buffer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum IndexWidth {
+    /// Half the memory, but limited to 65,535 vertices per mesh (`0xFFFF` is the strip restart
+    /// index, see [`StripRenderer::restart_index`](crate::StripRenderer::restart_index)).
+    U16,
+    U32,
+    /// [`IndexWidth::U16`] for each mesh whose indices fit in one, and [`IndexWidth::U32`] for
+    /// the rest. The choice is kept in [`Mesh::index_format`].
+    #[default]
+    Auto,
+}
+
+impl IndexWidth {
+    /// The format of meshes with this width. [`IndexWidth::Auto`] gives the widest format it
+    /// can choose, see [`IndexWidth::format_for`] for the one it chooses.
+    pub const fn format(self) -> wgpu::IndexFormat {
+        match self {
+            IndexWidth::U16 => wgpu::IndexFormat::Uint16,
+            IndexWidth::U32 | IndexWidth::Auto => wgpu::IndexFormat::Uint32,
+        }
+    }
+
+    /// The format that a mesh with `indices` is uploaded as.
+    pub fn format_for(self, indices: &[u32]) -> wgpu::IndexFormat {
+        match self {
+            IndexWidth::Auto if indices.iter().all(|&index| index < u16::MAX as u32) => {
+                wgpu::IndexFormat::Uint16
+            }
+            width => width.format(),
+        }
+    }
+}
+
+/// Why a set of indices can't be drawn as a triangle list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexError {
+    /// This many indices isn't a whole number of triangles.
+    IncompleteTriangle(usize),
+    /// The index at `position` points past the end of the vertices.
+    OutOfBounds { position: usize, index: u32, vertex_count: usize },
+    /// The index at `position` can't be stored as `format`, or is its restart index.
+    TooWide { position: usize, index: u32, format: wgpu::IndexFormat },
+}
+
+impl std::fmt::Display for IndexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IncompleteTriangle(len) => {
+                write!(f, "{len} indices isn't a multiple of 3")
+            }
+            Self::OutOfBounds { position, index, vertex_count } => write!(
+                f,
+                "index {index} (at {position}) is out of bounds for {vertex_count} vertices",
+            ),
+            Self::TooWide { position, index, format } => {
+                write!(f, "index {index} (at {position}) doesn't fit in {format:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for IndexError {}
+
+/// Check that `indices` form a triangle list over `vertex_count` vertices, storable as `format`.
+pub fn validate_indices(
+    indices: &[u32],
+    vertex_count: usize,
+    format: wgpu::IndexFormat,
+) -> Result<(), IndexError> {
+    if !indices.len().is_multiple_of(3) {
+        return Err(IndexError::IncompleteTriangle(indices.len()));
+    }
+    // NOTE: The largest value of each format restarts strips, so it's never a vertex.
+    let max = crate::StripRenderer::restart_index(format) - 1;
+    for (position, &index) in indices.iter().enumerate() {
+        if index as usize >= vertex_count {
+            return Err(IndexError::OutOfBounds { position, index, vertex_count });
+        }
+        if index > max {
+            return Err(IndexError::TooWide { position, index, format });
+        }
+    }
+
+    Ok(())
+}
+
+/// GPU vertex & index buffers.
+pub struct Mesh {
+    pub vertex_buffer: wgpu::Buffer,
+    /// `None` for meshes made with [`Mesh::unindexed`], which are drawn straight from
+    /// `vertex_buffer`.
+    pub index_buffer: Option<wgpu::Buffer>,
+    /// The number of indices, or of vertices for unindexed meshes.
+    pub num_indices: u32,
+    /// The format of `index_buffer`'s contents.
+    pub index_format: wgpu::IndexFormat,
+}
+
+impl Mesh {
+    pub fn new<V: bytemuck::Pod>(
+        device: &wgpu::Device,
+        vertices: &[V],
+        indices: &[u32],
+        label: Option<&str>,
+    ) -> Self {
+        Self::with_index_format(device, vertices, indices, wgpu::IndexFormat::Uint32, label)
+    }
+
+    /// Like [`Mesh::new`], but storing `indices` as `format`.
+    ///
+    /// # Panics
+    ///
+    /// If the indices fail [`validate_indices`].
+    pub fn with_index_format<V: bytemuck::Pod>(
+        device: &wgpu::Device,
+        vertices: &[V],
+        indices: &[u32],
+        format: wgpu::IndexFormat,
+        label: Option<&str>,
+    ) -> Self {
+        match Self::from_raw(device, vertices, indices, format, label) {
+            Ok(mesh) => mesh,
+            Err(error) => panic!("invalid mesh: {error}"),
+        }
+    }
+
+    /// Like [`Mesh::with_index_format`] when `format` is given, or [`Mesh::unindexed`] when it's
+    /// `None`.
+    ///
+    /// # Panics
+    ///
+    /// If the indices fail [`validate_indices`].
+    pub fn upload<V: bytemuck::Pod>(
+        device: &wgpu::Device,
+        vertices: &[V],
+        indices: &[u32],
+        format: Option<wgpu::IndexFormat>,
+        label: Option<&str>,
+    ) -> Self {
+        match format {
+            Some(format) => Self::with_index_format(device, vertices, indices, format, label),
+            None => match Self::unindexed(device, vertices, indices, label) {
+                Ok(mesh) => mesh,
+                Err(error) => panic!("invalid mesh: {error}"),
+            },
+        }
+    }
+
+    /// Upload geometry without an index buffer, by copying out the vertex at each index.
+    ///
+    /// This takes more memory (a quad needs six vertices instead of four), but works on
+    /// backends that mishandle index buffers. Index `i` becomes vertex `i`, so ranges of indices
+    /// (like [`Batch::range`]s) can still be drawn with [`Mesh::draw_range`].
+    pub fn unindexed<V: bytemuck::Pod>(
+        device: &wgpu::Device,
+        vertices: &[V],
+        indices: &[u32],
+        label: Option<&str>,
+    ) -> Result<Self, IndexError> {
+        validate_indices(indices, vertices.len(), wgpu::IndexFormat::Uint32)?;
+
+        let expanded: Vec<V> = indices.iter().map(|&i| vertices[i as usize]).collect();
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} Vertex Buffer", label.unwrap_or("Mesh"))),
+            contents: bytemuck::cast_slice(&expanded),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+
+        Ok(Self {
+            vertex_buffer,
+            index_buffer: None,
+            num_indices: indices.len() as u32,
+            index_format: wgpu::IndexFormat::Uint32,
+        })
+    }
+
+    /// Upload geometry built outside of a [`Renderer`], after checking it with
+    /// [`validate_indices`] (the same check [`Renderer::finish`] makes).
+    pub fn from_raw<V: bytemuck::Pod>(
+        device: &wgpu::Device,
+        vertices: &[V],
+        indices: &[u32],
+        format: wgpu::IndexFormat,
+        label: Option<&str>,
+    ) -> Result<Self, IndexError> {
+        validate_indices(indices, vertices.len(), format)?;
+
+        let narrow: Vec<u16>;
+        let index_bytes: &[u8] = match format {
+            wgpu::IndexFormat::Uint16 => {
+                narrow = indices.iter().map(|&i| i as u16).collect();
+                bytemuck::cast_slice(&narrow)
+            }
+            wgpu::IndexFormat::Uint32 => bytemuck::cast_slice(indices),
+        };
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} Vertex Buffer", label.unwrap_or("Mesh"))),
+            contents: bytemuck::cast_slice(vertices),
+            // NOTE: Copies into the buffer allow for updates like `Mesh::update_quad_color`.
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} Index Buffer", label.unwrap_or("Mesh"))),
+            contents: index_bytes,
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Ok(Self {
+            vertex_buffer,
+            index_buffer: Some(index_buffer),
+            num_indices: indices.len() as u32,
+            index_format: format,
+        })
+    }
+
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
+        self.draw_range(render_pass, 0..self.num_indices);
+    }
+
+    /// The offset of the first vertex of the quad added with `id`, in bytes.
+    fn quad_offset(&self, id: QuadId) -> Result<wgpu::BufferAddress, String> {
+        if self.index_buffer.is_none() {
+            return Err("quads can't be found in unindexed meshes".to_string());
+        }
+
+        let stride = size_of::<Vertex>() as wgpu::BufferAddress;
+        let first = id.first_vertex as wgpu::BufferAddress * stride;
+        let end = first + Quad::num_vertices() as wgpu::BufferAddress * stride;
+        if end > self.vertex_buffer.size() {
+            return Err(format!("{id:?} is out of bounds for this mesh"));
+        }
+
+        Ok(first)
+    }
+
+    /// Overwrite the color of the quad added with `id` (see [`Renderer::add_quad_with_id`]),
+    /// leaving its positions alone.
+    ///
+    /// Fails if this mesh doesn't have the quad's vertices, or is unindexed.
+    pub fn update_quad_color(
+        &self,
+        queue: &wgpu::Queue,
+        id: QuadId,
+        color: [f32; 3],
+    ) -> Result<(), String> {
+        let stride = size_of::<Vertex>() as wgpu::BufferAddress;
+        let vertices = Quad::num_vertices() as wgpu::BufferAddress;
+        let first = self.quad_offset(id)?;
+
+        // NOTE: This must match the offset of the color attribute in `Vertex::desc`.
+        let color_offset = size_of::<[f32; 2]>() as wgpu::BufferAddress;
+        for vertex in 0..vertices {
+            queue.write_buffer(
+                &self.vertex_buffer,
+                first + vertex * stride + color_offset,
+                bytemuck::cast_slice(&color),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Overwrite the opacity of the quad added with `id` (see [`Renderer::add_quad_with_id`]),
+    /// leaving its positions and color alone. Only shaders that read the color as a `vec4` (and
+    /// blend) are affected.
+    ///
+    /// Fails if this mesh doesn't have the quad's vertices, or is unindexed.
+    pub fn set_quad_opacity(
+        &self,
+        queue: &wgpu::Queue,
+        id: QuadId,
+        opacity: f32,
+    ) -> Result<(), String> {
+        let stride = size_of::<Vertex>() as wgpu::BufferAddress;
+        let vertices = Quad::num_vertices() as wgpu::BufferAddress;
+        let first = self.quad_offset(id)?;
+
+        // NOTE: This must match the offset of the color attribute's fourth component in
+        //       `Vertex::desc`.
+        let alpha_offset = size_of::<[f32; 5]>() as wgpu::BufferAddress;
+        for vertex in 0..vertices {
+            queue.write_buffer(
+                &self.vertex_buffer,
+                first + vertex * stride + alpha_offset,
+                bytemuck::bytes_of(&opacity.clamp(0.0, 1.0)),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Draw only the triangles made of `indices` (e.g. a [`Batch::range`]).
+    pub fn draw_range(&self, render_pass: &mut wgpu::RenderPass, indices: std::ops::Range<u32>) {
+        self.draw_range_instanced(render_pass, indices, 0..1);
+    }
+
+    pub(crate) fn draw_range_instanced(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        indices: std::ops::Range<u32>,
+        instances: std::ops::Range<u32>,
+    ) {
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        match &self.index_buffer {
+            Some(index_buffer) => {
+                render_pass.set_index_buffer(index_buffer.slice(..), self.index_format);
+                render_pass.draw_indexed(indices, 0, instances);
+            }
+            None => render_pass.draw(indices, instances),
+        }
+    }
+}
+
+
+
+/// A quad added with [`Renderer::add_quad_with_id`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct QuadId {
+    first_vertex: u32,
+}
+
+
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Vertex {
+    pub(crate) pos: [f32; 2],
+    pub(crate) color: [f32; 3],
+    /// Read along with `color` (as its fourth component) by shaders that take a `vec4`. This is
+    /// `1.0` unless it's changed with [`Mesh::set_quad_opacity`].
+    alpha: f32,
+    /// The pick id of the primitive this vertex belongs to, or `0` for none.
+    id: u32,
+    /// The depth of the layer this vertex is on (see [`Renderer::next_layer`]).
+    depth: f32,
+}
+
+impl Vertex {
+    pub const fn new(pos: [f32; 2], color: [f32; 3]) -> Self {
+        Self { pos, color, alpha: 1.0, id: 0, depth: 1.0 }
+    }
+
+    pub(crate) const fn with_alpha(mut self, alpha: f32) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    pub(crate) const fn with_depth(mut self, depth: f32) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Uint32,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 7]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32,
+                },
+            ]
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quad {
+    pub pos: [f32; 2],
+    pub size: [f32; 2],
+}
+
+// Constants.
+impl Quad {
+    pub const fn num_indices() -> u32 {
+        6
+    }
+
+    pub const fn num_vertices() -> u32 {
+        4
+    }
+
+    pub const fn indices_u16() -> [u16; 6] {
+        [0, 1, 2, 2, 1, 3]
+    }
+
+    pub const fn indices_u32() -> [u32; 6] {
+        [0, 1, 2, 2, 1, 3]
+    }
+}
+
+impl Quad {
+    pub const fn new(pos: [f32; 2], size: [f32; 2]) -> Self {
+        Self { pos, size }
+    }
+
+    /// Whether either side of the quad is (nearly) zero, so that it would only produce
+    /// zero-area triangles.
+    pub fn is_degenerate(&self) -> bool {
+        self.size[0].abs() <= f32::EPSILON || self.size[1].abs() <= f32::EPSILON
+    }
+
+    /// The quad's corners with the smallest and largest coordinates, whichever way its size
+    /// points.
+    pub(crate) const fn min_max(&self) -> ([f32; 2], [f32; 2]) {
+        let [x0, y0] = self.pos;
+        let [x1, y1] = [x0 + self.size[0], y0 + self.size[1]];
+
+        ([x0.min(x1), y0.min(y1)], [x0.max(x1), y0.max(y1)])
+    }
+
+    /// Whether `point` lies within the quad. The two edges with the smallest coordinates are
+    /// inside and the other two aren't, so a point on the border between two adjacent quads is
+    /// only in one of them.
+    pub const fn contains(&self, point: [f32; 2]) -> bool {
+        let (min, max) = self.min_max();
+
+        min[0] <= point[0] && point[0] < max[0] && min[1] <= point[1] && point[1] < max[1]
+    }
+
+    /// Whether the two quads share any area (or at least an edge).
+    pub const fn overlaps(&self, other: &Quad) -> bool {
+        let (min, max) = self.min_max();
+        let (other_min, other_max) = other.min_max();
+
+        min[0] <= other_max[0] && other_min[0] <= max[0]
+            && min[1] <= other_max[1] && other_min[1] <= max[1]
+    }
+
+    /// The same quad moved by `offset`.
+    pub const fn translated(&self, offset: [f32; 2]) -> Self {
+        Self::new([self.pos[0] + offset[0], self.pos[1] + offset[1]], self.size)
+    }
+
+    /// The quad scaled by `factor` towards (or away from) `center`, which stays put. Pass the
+    /// quad's own center to grow or shrink it in place, and a negative `factor` to mirror it.
+    pub const fn scaled_about(&self, center: [f32; 2], factor: f32) -> Self {
+        Self::new(
+            [
+                center[0] + (self.pos[0] - center[0]) * factor,
+                center[1] + (self.pos[1] - center[1]) * factor,
+            ],
+            [self.size[0] * factor, self.size[1] * factor],
+        )
+    }
+
+    /// The point halfway across the quad.
+    pub const fn center(&self) -> [f32; 2] {
+        [self.pos[0] + self.size[0] / 2.0, self.pos[1] + self.size[1] / 2.0]
+    }
+
+    /// NOTE: Unlike [`Renderer::add_quad`], this emits vertices even for degenerate quads.
+    pub fn push_with_color(&self, color: [f32; 3], out: &mut Vec<Vertex>) {
+        out.extend([
+            Vertex::new(self.pos, color),
+            Vertex::new([self.pos[0] + self.size[0], self.pos[1]], color),
+            Vertex::new([self.pos[0], self.pos[1] + self.size[1]], color),
+            Vertex::new([self.pos[0] + self.size[0], self.pos[1] + self.size[1]], color),
+        ]);
+    }
+}
+
+pub struct RoundedQuad {
+    pub pos: [f32; 2],
+    pub size: [f32; 2],
+    /// Corner radii, in the order top-left, top-right, bottom-right, bottom-left.
+    pub radii: [f32; 4],
+}
+
+// Constants.
+impl RoundedQuad {
+    pub const CORNER_SEGMENTS: u32 = 8;
+
+    pub const fn num_vertices() -> u32 {
+        // One center vertex plus the points along each corner arc.
+        1 + 4 * (Self::CORNER_SEGMENTS + 1)
+    }
+
+    pub const fn num_indices() -> u32 {
+        3 * 4 * (Self::CORNER_SEGMENTS + 1)
+    }
+}
+
+impl RoundedQuad {
+    pub const fn new(pos: [f32; 2], size: [f32; 2], radius: f32) -> Self {
+        Self { pos, size, radii: [radius; 4] }
+    }
+
+    pub const fn with_radii(pos: [f32; 2], size: [f32; 2], radii: [f32; 4]) -> Self {
+        Self { pos, size, radii }
+    }
+
+    /// The corner radii, scaled down (uniformly) so that adjacent corners never overlap along
+    /// any edge.
+    pub fn clamped_radii(&self) -> [f32; 4] {
+        let [w, h] = [self.size[0].abs(), self.size[1].abs()];
+        let [tl, tr, br, bl] = self.radii.map(|r| r.max(0.0));
+
+        let mut scale = 1.0f32;
+        for (edge, a, b) in [(w, tl, tr), (w, bl, br), (h, tl, bl), (h, tr, br)] {
+            if a + b > edge {
+                scale = scale.min(edge / (a + b));
+            }
+        }
+
+        [tl, tr, br, bl].map(|r| r * scale)
+    }
+
+    pub fn push_with_color(
+        &self,
+        color: [f32; 3],
+        vertices: &mut Vec<Vertex>,
+        indices: &mut Vec<u32>,
+    ) {
+        let [x, y] = self.pos;
+        let [w, h] = self.size;
+        let [tl, tr, br, bl] = self.clamped_radii();
+
+        // NOTE: The perimeter is walked counter-clockwise (matching `Quad`'s winding), starting
+        //       at the bottom-right corner. Each entry is the arc's center, its radius, and the
+        //       angle its sweep starts at.
+        let corners = [
+            ([x + w - br, y + br], br, -std::f32::consts::FRAC_PI_2),
+            ([x + w - tr, y + h - tr], tr, 0.0),
+            ([x + tl, y + h - tl], tl, std::f32::consts::FRAC_PI_2),
+            ([x + bl, y + bl], bl, std::f32::consts::PI),
+        ];
+
+        let center = vertices.len() as u32;
+        vertices.push(Vertex::new([x + w * 0.5, y + h * 0.5], color));
+
+        for (corner_center, radius, start) in corners {
+            for i in 0..=Self::CORNER_SEGMENTS {
+                let angle = start
+                    + std::f32::consts::FRAC_PI_2 * (i as f32 / Self::CORNER_SEGMENTS as f32);
+                vertices.push(Vertex::new(
+                    [
+                        corner_center[0] + radius * angle.cos(),
+                        corner_center[1] + radius * angle.sin(),
+                    ],
+                    color,
+                ));
+            }
+        }
+
+        let perimeter = 4 * (Self::CORNER_SEGMENTS + 1);
+        for i in 0..perimeter {
+            indices.extend([center, center + 1 + i, center + 1 + (i + 1) % perimeter]);
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DrawCommand, RenderTarget, Texture, read_pixel, render_to, testing};
+
+    fn draw_pixel_quad(shader: impl FnOnce(&wgpu::Device) -> Shader) -> Option<[u8; 4]> {
+        let (_adapter, device, queue) = testing::gpu()?;
+        let shader = shader(&device);
+        let texture = testing::target(&device, [16, 16]);
+
+        let mut renderer = Renderer::with_space(CoordinateSpace::Pixels {
+            width: 16.0,
+            height: 16.0,
+        });
+        renderer.add_quad(&Quad::new([4.0, 4.0], [8.0, 8.0]), [1.0, 0.0, 0.0]);
+        let batch = renderer.finish(&device);
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        render_to(
+            &mut encoder,
+            &[RenderTarget::from_texture(&texture)],
+            Some(wgpu::Color::TRANSPARENT),
+            &[DrawCommand {
+                shader: &shader,
+                mesh: &batch.solid,
+                bind_groups: &[],
+                instances: None,
+                scissor: None,
+            }],
+        ).unwrap();
+        queue.submit(std::iter::once(encoder.finish()));
+
+        Some(read_pixel(&device, &queue, &texture, 8, 8).unwrap())
+    }
+
+    #[test]
+    fn solid_draws_pixel_space_quads() {
+        let Some(pixel) = draw_pixel_quad(|device| {
+            Shader::solid(device, Texture::FORMAT, 1).unwrap()
+        }) else {
+            return;
+        };
+        assert_ne!(pixel[3], 0);
+    }
+
+    #[test]
+    fn back_face_culling_drops_pixel_space_quads() {
+        let Some(pixel) = draw_pixel_quad(|device| {
+            Shader::solid_culled(device, Texture::FORMAT, 1, Some(wgpu::Face::Back)).unwrap()
+        }) else {
+            return;
+        };
+        assert_eq!(pixel, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn rounded_quads_emit_the_template_once_per_quad() {
+        let quads = [
+            ([0.0, 0.0], [0.5, 0.5]),
+            ([-1.0, -1.0], [0.2, 0.4]),
+            ([0.1, 0.6], [0.3, 0.3]),
+        ];
+        let mut renderer = Renderer::start();
+        renderer.add_rounded_quads(&quads, 0.05, [1.0, 1.0, 1.0]);
+
+        let num_vertices = RoundedQuad::num_vertices() as usize;
+        let num_indices = RoundedQuad::num_indices() as usize;
+        assert_eq!(renderer.vertices().len(), quads.len() * num_vertices);
+        assert_eq!(renderer.indices().len(), quads.len() * num_indices);
+
+        let template = &renderer.indices()[..num_indices];
+        for (i, indices) in renderer.indices().chunks(num_indices).enumerate() {
+            let base = (i * num_vertices) as u32;
+            assert!(indices.iter().zip(template).all(|(index, first)| *index == first + base));
+        }
+    }
+
+    #[test]
+    fn rounded_quad_radii_are_clamped_per_quad() {
+        let quads = [([0.0, 0.0], [0.4, 0.1]), ([0.5, 0.5], [0.4, 0.4])];
+        let mut renderer = Renderer::start();
+        renderer.add_rounded_quads(&quads, 0.15, [1.0, 1.0, 1.0]);
+
+        let mut expected = Renderer::start();
+        expected.add_rounded_quad(&RoundedQuad::new(quads[0].0, quads[0].1, 0.05), [1.0; 3]);
+        expected.add_rounded_quad(&RoundedQuad::new(quads[1].0, quads[1].1, 0.15), [1.0; 3]);
+
+        assert_eq!(renderer.indices(), expected.indices());
+        for (vertex, expected) in renderer.vertices().iter().zip(expected.vertices()) {
+            assert!((vertex.pos[0] - expected.pos[0]).abs() < 1e-6);
+            assert!((vertex.pos[1] - expected.pos[1]).abs() < 1e-6);
+        }
+        for vertex in &renderer.vertices()[..RoundedQuad::num_vertices() as usize] {
+            assert!(Quad::new([-1e-6, -1e-6], [0.4 + 2e-6, 0.1 + 2e-6]).contains(vertex.pos));
+        }
+    }
+
+    #[test]
+    fn append_offsets_indices_past_existing_vertices() {
+        let mut renderer = Renderer::start();
+        renderer.add_quad(&Quad::new([0.0, 0.0], [0.5, 0.5]), [1.0, 0.0, 0.0]);
+
+        let mut other = Renderer::start();
+        other.add_quad(&Quad::new([-1.0, -1.0], [0.5, 0.5]), [0.0, 1.0, 0.0]);
+        other.add_quad(&Quad::new([0.5, -1.0], [0.5, 0.5]), [0.0, 0.0, 1.0]);
+        let other_vertices = other.vertices().to_vec();
+        let other_indices = other.indices().to_vec();
+
+        renderer.append(other);
+
+        assert_eq!(renderer.vertices().len(), 12);
+        assert_eq!(&renderer.vertices()[4..], &other_vertices[..]);
+        assert_eq!(renderer.indices()[..6], Quad::indices_u32());
+        let appended: Vec<u32> = other_indices.iter().map(|index| index + 4).collect();
+        assert_eq!(renderer.indices()[6..], appended[..]);
+    }
+
+    #[test]
+    fn append_converts_between_spaces() {
+        let mut renderer = Renderer::start();
+        renderer.add_quad(&Quad::new([0.0, 0.0], [0.5, 0.5]), [1.0, 0.0, 0.0]);
+
+        let space = CoordinateSpace::Pixels { width: 100.0, height: 100.0 };
+        let mut other = Renderer::with_space(space);
+        other.add_quad(&Quad::new([0.0, 0.0], [50.0, 50.0]), [0.0, 1.0, 0.0]);
+        let expected: Vec<[f32; 2]> =
+            other.vertices().iter().map(|vertex| space.to_ndc(vertex.pos)).collect();
+
+        renderer.append(other);
+
+        let appended: Vec<[f32; 2]> =
+            renderer.vertices()[4..].iter().map(|vertex| vertex.pos).collect();
+        assert_eq!(appended, expected);
+        for pos in [[-1.0, 1.0], [0.0, 1.0], [0.0, 0.0], [-1.0, 0.0]] {
+            assert!(appended.contains(&pos), "{pos:?} is missing from {appended:?}");
+        }
+        assert_eq!(renderer.indices()[6..], Quad::indices_u32().map(|index| index + 4));
+    }
+
+    #[test]
+    fn quads_outside_the_view_bounds_are_culled() {
+        let mut renderer = Renderer::start();
+        renderer.add_quad(&Quad::new([2.0, 2.0], [0.5, 0.5]), [1.0, 1.0, 1.0]);
+        assert_eq!(renderer.culled_count(), 0);
+        assert_eq!(renderer.vertices().len(), 4);
+
+        renderer.set_view_bounds(Some(Quad::new([-1.0, -1.0], [2.0, 2.0])));
+        renderer.add_quad(&Quad::new([-0.5, -0.5], [0.5, 0.5]), [1.0, 1.0, 1.0]);
+        renderer.add_quad(&Quad::new([0.75, 0.75], [0.5, 0.5]), [1.0, 1.0, 1.0]);
+        assert_eq!(renderer.culled_count(), 0);
+        assert_eq!(renderer.vertices().len(), 12);
+
+        renderer.add_quad(&Quad::new([2.0, 2.0], [0.5, 0.5]), [1.0, 1.0, 1.0]);
+        renderer.add_rounded_quad(&RoundedQuad::new([-3.0, 0.0], [1.0, 1.0], 0.25), [1.0; 3]);
+        assert_eq!(renderer.culled_count(), 2);
+        assert_eq!(renderer.vertices().len(), 12);
+    }
+
+    #[test]
+    fn front_faces_are_kept_in_y_down_spaces() {
+        let space = CoordinateSpace::Pixels { width: 16.0, height: 16.0 };
+        let quad = Quad::new([4.0, 4.0], [8.0, 8.0]);
+        let [a, b, c, d, e, f] = Quad::indices_u32();
+
+        for (space, keep, expected) in [
+            (space, false, [a, b, c, d, e, f]),
+            (space, true, [a, c, b, d, f, e]),
+            (CoordinateSpace::Ndc, true, [a, b, c, d, e, f]),
+        ] {
+            let mut renderer = Renderer::with_space(space);
+            renderer.set_keep_front_facing(keep);
+            renderer.add_quad(&quad, [1.0, 1.0, 1.0]);
+            renderer.add_circle_outline([8.0, 8.0], 4.0, 1.0, [1.0, 1.0, 1.0]);
+            let circle = renderer.circles.indices.clone();
+            renderer.keep_front_faces();
+
+            assert_eq!(renderer.indices(), expected, "{space:?}, keeping: {keep}");
+            assert!(!circle.is_empty());
+            let order = if keep && space.is_y_down() { [0, 2, 1] } else { [0, 1, 2] };
+            for (triangle, original) in renderer.circles.indices.chunks(3).zip(circle.chunks(3)) {
+                assert_eq!(triangle, order.map(|i| original[i]));
+            }
+        }
+    }
+
+    #[test]
+    fn model_matrices_are_uploaded_on_first_use() {
+        let Some((_adapter, device, queue)) = testing::gpu() else {
+            return;
+        };
+        let translation = [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.5, 0.0, 0.0, 1.0],
+        ];
+        let mut renderer = Renderer::start();
+        renderer.set_model_matrix(translation);
+        let mut batch = renderer.finish(&device);
+        assert!(batch.model.get().is_none());
+
+        assert_eq!(batch.model(&device).matrix(), translation);
+        assert_eq!(batch.model(&device).space(), CoordinateSpace::Ndc);
+        batch.model_mut(&device).set(&queue, crate::IDENTITY_MATRIX);
+        assert_eq!(batch.model(&device).matrix(), crate::IDENTITY_MATRIX);
+    }
+
+    #[test]
+    fn viewports_are_resized_in_place() {
+        let mut renderer = Renderer::start();
+        renderer.set_viewport(32, 32);
+        assert_eq!(renderer.viewport(), None);
+        assert_eq!(renderer.space(), CoordinateSpace::Ndc);
+
+        let mut renderer = Renderer::with_space(CoordinateSpace::Pixels {
+            width: 16.0,
+            height: 16.0,
+        });
+        assert_eq!(renderer.viewport(), Some((16, 16)));
+        renderer.add_quad(&Quad::new([0.0, 0.0], [8.0, 8.0]), [1.0, 1.0, 1.0]);
+        renderer.set_viewport(32, 24);
+        assert_eq!(renderer.viewport(), Some((32, 24)));
+        assert_eq!(renderer.space(), CoordinateSpace::Pixels { width: 32.0, height: 24.0 });
+
+        let Some((_adapter, device, queue)) = testing::gpu() else {
+            return;
+        };
+        let shader = Shader::solid(&device, Texture::FORMAT, 1).unwrap();
+        let batch = renderer.finish(&device);
+        let pixels = testing::render(&device, &queue, [32, 24], &[DrawCommand {
+            shader: &shader,
+            mesh: &batch.solid,
+            bind_groups: &[],
+            instances: None,
+            scissor: None,
+        }]);
+
+        // NOTE: The quad was added for a 16x16 viewport, but is drawn into the 32x24 one.
+        let drawn = |x: usize, y: usize| pixels[(y * 32 + x) * 4 + 3] != 0;
+        assert!(drawn(0, 0) && drawn(7, 7));
+        assert!(!drawn(8, 0) && !drawn(0, 8) && !drawn(15, 15));
+    }
+
+    #[test]
+    fn appended_layers_go_in_front() {
+        let mut renderer = Renderer::start();
+        quad(&mut renderer);
+        renderer.next_layer();
+        quad(&mut renderer);
+
+        let mut other = Renderer::start();
+        quad(&mut other);
+        other.next_layer();
+        quad(&mut other);
+
+        renderer.append(other);
+        assert_eq!(renderer.layer(), 3);
+        quad(&mut renderer);
+        renderer.tag_vertices();
+
+        let depths: Vec<f32> = renderer.vertices().chunks(4).map(|quad| quad[0].depth).collect();
+        assert_eq!(depths, [0, 1, 2, 3, 3].map(Renderer::layer_depth));
+    }
+
+    #[test]
+    fn finishing_writes_layer_depths() {
+        let mut renderer = Renderer::start();
+        quad(&mut renderer);
+        assert_eq!(renderer.next_layer(), 1);
+        quad(&mut renderer);
+        quad(&mut renderer);
+        assert_eq!(renderer.next_layer(), 2);
+        quad(&mut renderer);
+        renderer.tag_vertices();
+
+        let depths: Vec<f32> = renderer.vertices().iter().map(|vertex| vertex.depth).collect();
+        let expected = [(0, 4), (1, 8), (2, 4)]
+            .into_iter()
+            .flat_map(|(layer, count)| std::iter::repeat_n(Renderer::layer_depth(layer), count));
+        assert_eq!(depths, expected.collect::<Vec<f32>>());
+        assert_eq!(Renderer::layer_depth(0), 1.0);
+        assert_eq!(Renderer::layer_depth(Renderer::MAX_LAYER + 1), 0.0);
+    }
+
+    #[test]
+    fn later_layers_stay_in_front_when_drawn_first() {
+        let Some((_adapter, device, queue)) = testing::gpu() else {
+            return;
+        };
+        let shader = Shader::solid_layered(&device, Texture::FORMAT, 1).unwrap();
+        let space = CoordinateSpace::Pixels { width: 16.0, height: 16.0 };
+
+        let mut back = Renderer::with_space(space);
+        back.add_quad(&Quad::new([0.0, 0.0], [16.0, 16.0]), [1.0, 0.0, 0.0]);
+        let mut front = Renderer::with_space(space);
+        front.next_layer();
+        front.add_quad(&Quad::new([4.0, 4.0], [8.0, 8.0]), [0.0, 1.0, 0.0]);
+        let [back, front] = [back, front].map(|renderer| renderer.finish(&device));
+
+        let texture = testing::target(&device, [16, 16]);
+        let target = RenderTarget::from_texture(&texture);
+        let depth = crate::DepthAttachment::create_texture(&device, [16, 16], 1);
+        let depth_view = depth.create_view(&Default::default());
+        let commands = [&front, &back].map(|batch| DrawCommand {
+            shader: &shader,
+            mesh: &batch.solid,
+            bind_groups: &[],
+            instances: None,
+            scissor: None,
+        });
+        let mut encoder = device.create_command_encoder(&Default::default());
+        crate::render_to_attachments_with_depth(
+            &mut encoder,
+            &[crate::ColorAttachment::clear(&target, wgpu::Color::TRANSPARENT)],
+            Some(&crate::DepthAttachment::new(&depth_view)),
+            &commands,
+        ).unwrap();
+        queue.submit(std::iter::once(encoder.finish()));
+
+        assert_eq!(read_pixel(&device, &queue, &texture, 8, 8).unwrap(), [0, 255, 0, 255]);
+        assert_eq!(read_pixel(&device, &queue, &texture, 1, 1).unwrap(), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn geometry_is_emitted_in_call_order() {
+        const RED: [f32; 3] = [1.0, 0.0, 0.0];
+        const GREEN: [f32; 3] = [0.0, 1.0, 0.0];
+        const BLUE: [f32; 3] = [0.0, 0.0, 1.0];
+
+        let mut renderer = Renderer::with_space(CoordinateSpace::Pixels {
+            width: 100.0,
+            height: 100.0,
+        });
+        renderer.add_quad(&Quad::new([10.0, 10.0], [20.0, 30.0]), RED);
+        renderer.add_convex_polygon(&[[50.0, 10.0], [70.0, 10.0], [60.0, 30.0]], GREEN).unwrap();
+        // NOTE: At a size of 10 each dot is a pixel, and '-' is one run of 5 dots on row 3.
+        renderer.add_text([0.0, 50.0], "-", 10.0, BLUE);
+
+        assert_eq!(renderer.vertices(), [
+            Vertex::new([10.0, 10.0], RED),
+            Vertex::new([30.0, 10.0], RED),
+            Vertex::new([10.0, 40.0], RED),
+            Vertex::new([30.0, 40.0], RED),
+            Vertex::new([50.0, 10.0], GREEN),
+            Vertex::new([70.0, 10.0], GREEN),
+            Vertex::new([60.0, 30.0], GREEN),
+            Vertex::new([0.0, 54.0], BLUE),
+            Vertex::new([5.0, 54.0], BLUE),
+            Vertex::new([0.0, 55.0], BLUE),
+            Vertex::new([5.0, 55.0], BLUE),
+        ]);
+        assert_eq!(renderer.indices(), [
+            0, 1, 2, 2, 1, 3,
+            4, 5, 6,
+            7, 8, 9, 9, 8, 10,
+        ]);
+    }
+
+    #[test]
+    fn auto_index_width_never_uses_the_restart_index() {
+        use wgpu::IndexFormat::{Uint16, Uint32};
+
+        assert_eq!(IndexWidth::Auto.format_for(&[]), Uint16);
+        assert_eq!(IndexWidth::Auto.format_for(&[0, 65533, 65534]), Uint16);
+        assert_eq!(IndexWidth::Auto.format_for(&[0, 65535, 1]), Uint32);
+        assert_eq!(IndexWidth::Auto.format_for(&[65536]), Uint32);
+        assert_eq!(IndexWidth::U16.format_for(&[65536]), Uint16);
+        assert_eq!(IndexWidth::U32.format_for(&[0]), Uint32);
+    }
+
+    #[test]
+    fn restart_indices_are_too_wide() {
+        use wgpu::IndexFormat::{Uint16, Uint32};
+
+        assert_eq!(validate_indices(&[0, 1, 65534], 65536, Uint16), Ok(()));
+        assert_eq!(
+            validate_indices(&[0, 1, 65535], 65536, Uint16),
+            Err(IndexError::TooWide { position: 2, index: 65535, format: Uint16 }),
+        );
+        assert_eq!(validate_indices(&[0, 1, 65535], 65536, Uint32), Ok(()));
+    }
+
+    fn two_quads_sharing_an_edge() -> Renderer {
+        let mut renderer = Renderer::start();
+        renderer.add_quad(&Quad::new([0.0, 0.0], [0.5, 0.5]), [1.0, 1.0, 1.0]);
+        renderer.add_quad(&Quad::new([0.5, 0.0], [0.5, 0.5]), [1.0, 1.0, 1.0]);
+        renderer
+    }
+
+    fn triangle_positions(renderer: &Renderer) -> Vec<[f32; 2]> {
+        renderer.indices().iter().map(|&index| renderer.vertices()[index as usize].pos).collect()
+    }
+
+    #[test]
+    fn deduplication_merges_shared_vertices() {
+        let mut renderer = two_quads_sharing_an_edge();
+        let positions = triangle_positions(&renderer);
+
+        renderer.deduplicate_vertices();
+
+        assert_eq!(renderer.vertices().len(), 6);
+        assert_eq!(triangle_positions(&renderer), positions);
+    }
+
+    #[test]
+    fn deduplicated_batches_draw_the_same() {
+        let Some((_adapter, device, queue)) = testing::gpu() else {
+            return;
+        };
+        let shader = Shader::solid(&device, Texture::FORMAT, 1).unwrap();
+
+        let render = |deduplicate: bool| {
+            let mut renderer = two_quads_sharing_an_edge();
+            renderer.set_deduplicate_vertices(deduplicate);
+            let batch = renderer.finish(&device);
+
+            testing::render(&device, &queue, [16, 16], &[DrawCommand {
+                shader: &shader,
+                mesh: &batch.solid,
+                bind_groups: &[],
+                instances: None,
+                scissor: None,
+            }])
+        };
+
+        let duplicated = render(false);
+        assert!(duplicated.chunks(4).any(|texel| texel[3] != 0));
+        assert!(duplicated == render(true), "deduplicated output differs");
+    }
+
+    #[test]
+    fn deduplication_keeps_vertices_with_different_ids_or_depths() {
+        let mut renderer = two_quads_sharing_an_edge();
+        for vertex in &mut renderer.vertices[4..] {
+            vertex.id = 1;
+        }
+        renderer.deduplicate_vertices();
+        assert_eq!(renderer.vertices().len(), 8);
+
+        let mut renderer = two_quads_sharing_an_edge();
+        for vertex in &mut renderer.vertices[4..] {
+            vertex.depth = Renderer::layer_depth(1);
+        }
+        renderer.deduplicate_vertices();
+        assert_eq!(renderer.vertices().len(), 8);
+    }
+
+    fn quad(renderer: &mut Renderer) {
+        renderer.add_quad(&Quad::new([0.0, 0.0], [0.5, 0.5]), [1.0, 1.0, 1.0]);
+    }
+
+    fn ranges(renderer: &Renderer) -> Vec<(&str, std::ops::Range<u32>)> {
+        renderer.ranges.iter().map(|(name, range)| (name.as_str(), range.clone())).collect()
+    }
+
+    #[test]
+    fn back_to_back_and_empty_ranges() {
+        let mut renderer = Renderer::start();
+        quad(&mut renderer);
+        renderer.begin_range("first");
+        quad(&mut renderer);
+        renderer.begin_range("second");
+        quad(&mut renderer);
+        quad(&mut renderer);
+        renderer.begin_range("empty");
+        renderer.end_range();
+        renderer.end_range();
+
+        assert_eq!(ranges(&renderer), [("first", 6..12), ("second", 12..24), ("empty", 24..24)]);
+    }
+
+    #[test]
+    fn appended_ranges_are_shifted() {
+        let mut renderer = Renderer::start();
+        quad(&mut renderer);
+        renderer.begin_range("outer");
+        quad(&mut renderer);
+
+        let mut other = Renderer::start();
+        quad(&mut other);
+        other.begin_range("inner");
+        quad(&mut other);
+
+        renderer.append(other);
+        renderer.end_range();
+
+        assert_eq!(ranges(&renderer), [("inner", 18..24), ("outer", 6..24)]);
+    }
+
+    #[test]
+    fn finish_ends_the_open_range() {
+        let Some((_adapter, device, _queue)) = testing::gpu() else {
+            return;
+        };
+        let mut renderer = Renderer::start();
+        quad(&mut renderer);
+        renderer.begin_range("open");
+        quad(&mut renderer);
+
+        let batch = renderer.finish(&device);
+
+        assert_eq!(batch.range("open"), Some(6..12));
+        assert_eq!(batch.range("missing"), None);
+    }
+
+    #[test]
+    fn unindexed_meshes_draw_like_indexed_ones() {
+        let Some((_adapter, device, queue)) = testing::gpu() else {
+            return;
+        };
+        let shader = Shader::solid(&device, Texture::FORMAT, 1).unwrap();
+
+        let render = |indexed: bool| {
+            let mut renderer = Renderer::with_space(CoordinateSpace::Pixels {
+                width: 32.0,
+                height: 32.0,
+            });
+            renderer.set_indexed(indexed);
+            renderer.add_quad(&Quad::new([2.0, 2.0], [12.0, 8.0]), [1.0, 0.0, 0.0]);
+            renderer.add_rounded_quad(&RoundedQuad::new([18.0, 2.0], [12.0, 12.0], 4.0), [0.0; 3]);
+            renderer.add_convex_polygon(&[[2.0, 30.0], [14.0, 30.0], [8.0, 18.0]], [0.0, 1.0, 0.0])
+                .unwrap();
+            renderer.add_text([18.0, 18.0], "ab", 6.0, [0.0, 0.0, 1.0]);
+            let batch = renderer.finish(&device);
+            assert_eq!(batch.solid.index_buffer.is_some(), indexed);
+
+            testing::render(&device, &queue, [32, 32], &[DrawCommand {
+                shader: &shader,
+                mesh: &batch.solid,
+                bind_groups: &[],
+                instances: None,
+                scissor: None,
+            }])
+        };
+
+        let indexed = render(true);
+        assert!(indexed.chunks(4).any(|texel| texel[3] != 0));
+        assert!(indexed == render(false), "unindexed output differs from indexed output");
+    }
+
+    fn solid_spirv() -> Vec<u8> {
+        let module = naga::front::wgsl::parse_str(SOLID_WGSL).unwrap();
+        let info = naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        ).validate(&module).unwrap();
+        let options = naga::back::spv::Options {
+            flags: naga::back::spv::WriterFlags::empty(),
+            ..Default::default()
+        };
+        let words = naga::back::spv::write_vec(&module, &info, &options, None).unwrap();
+
+        bytemuck::cast_slice(&words).to_vec()
+    }
+
+    #[test]
+    fn pipelines_need_the_features_they_use() {
+        let Some((adapter, _device, _queue)) = testing::gpu() else {
+            return;
+        };
+        // NOTE: Unlike `testing::gpu`'s, this device has no features at all.
+        let desc = wgpu::DeviceDescriptor {
+            required_limits: adapter.limits(),
+            ..Default::default()
+        };
+        let (device, _queue) =
+            futures::executor::block_on(adapter.request_device(&desc, None)).unwrap();
+        let vertex_buffers = [Vertex::desc()];
+        let fragment_targets = [Some(wgpu::ColorTargetState::from(Texture::FORMAT))];
+        let desc = |primitive| ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(SOLID_WGSL)),
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &vertex_buffers,
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &fragment_targets,
+            primitive,
+            ..Default::default()
+        };
+
+        let lines = wgpu::PrimitiveState {
+            polygon_mode: wgpu::PolygonMode::Line,
+            ..ShaderDescriptor::UNCULLED_PRIMITIVE
+        };
+        let conservative_lines = wgpu::PrimitiveState { conservative: true, ..lines };
+        assert_eq!(
+            Shader::new(&device, desc(lines)).err(),
+            Some(ShaderError::MissingFeatures(wgpu::Features::POLYGON_MODE_LINE)),
+        );
+        assert_eq!(
+            Shader::new(&device, desc(conservative_lines)).err(),
+            Some(ShaderError::MissingFeatures(
+                wgpu::Features::POLYGON_MODE_LINE | wgpu::Features::CONSERVATIVE_RASTERIZATION,
+            )),
+        );
+        assert!(Shader::new(&device, desc(ShaderDescriptor::UNCULLED_PRIMITIVE)).is_ok());
+    }
+
+    #[test]
+    fn line_polygon_modes_only_draw_edges() {
+        let Some((_adapter, device, queue)) = testing::gpu() else {
+            return;
+        };
+        if !device.features().contains(wgpu::Features::POLYGON_MODE_LINE) {
+            return;
+        }
+        let vertex_buffers = [Vertex::desc()];
+        let fragment_targets = [Some(wgpu::ColorTargetState::from(Texture::FORMAT))];
+        let shader = Shader::new(&device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(SOLID_WGSL)),
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &vertex_buffers,
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &fragment_targets,
+            primitive: wgpu::PrimitiveState {
+                polygon_mode: wgpu::PolygonMode::Line,
+                ..ShaderDescriptor::UNCULLED_PRIMITIVE
+            },
+            ..Default::default()
+        }).unwrap();
+
+        let mut renderer = Renderer::with_space(CoordinateSpace::Pixels {
+            width: 16.0,
+            height: 16.0,
+        });
+        // NOTE: The edges run through the centers of pixels, so that they land on them.
+        renderer.add_quad(&Quad::new([2.5, 2.5], [11.0, 11.0]), [1.0, 1.0, 1.0]);
+        let batch = renderer.finish(&device);
+        let pixels = testing::render(&device, &queue, [16, 16], &[DrawCommand {
+            shader: &shader,
+            mesh: &batch.solid,
+            bind_groups: &[],
+            instances: None,
+            scissor: None,
+        }]);
+
+        let drawn = |x: usize, y: usize| pixels[(y * 16 + x) * 4 + 3] != 0;
+        assert!(drawn(2, 8) && drawn(8, 2) && drawn(13, 8) && drawn(8, 13));
+        assert!(!drawn(4, 4) && !drawn(11, 11) && !drawn(0, 0));
+    }
+
+    #[test]
+    fn entry_points_are_checked_against_their_stage() {
+        let desc = |vertex, fragment| ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(SOLID_WGSL)),
+            vertex_entry_point: Some(vertex),
+            fragment_entry_point: Some(fragment),
+            ..Default::default()
+        };
+        let missing = |name: &str| Err(ShaderError::MissingEntryPoint(name.to_string()));
+
+        assert_eq!(desc("vs_main", "fs_main").validate_entry_points(), Ok(()));
+        assert_eq!(desc("vs_mian", "fs_main").validate_entry_points(), missing("vs_mian"));
+        assert_eq!(desc("vs_main", "fs_mian").validate_entry_points(), missing("fs_mian"));
+        assert_eq!(desc("fs_main", "fs_main").validate_entry_points(), missing("fs_main"));
+        assert_eq!(desc("vs_main", "vs_main").validate_entry_points(), missing("vs_main"));
+    }
+
+    #[test]
+    fn spirv_bytes_are_checked() {
+        let bytes = solid_spirv();
+        let words = SpirV::words_from_bytes(&bytes).unwrap();
+        assert_eq!(words[0], 0x0723_0203);
+        assert_eq!(words.len() * 4, bytes.len());
+
+        let swapped: Vec<u8> = bytes
+            .chunks(4)
+            .flat_map(|word| [word[3], word[2], word[1], word[0]])
+            .collect();
+        assert_eq!(SpirV::words_from_bytes(&swapped).unwrap(), words);
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] ^= 0xFF;
+        for bytes in [&bad_magic[..], &bytes[..bytes.len() - 1], &bytes[..6], &[]] {
+            assert!(matches!(
+                SpirV::words_from_bytes(bytes),
+                Err(ShaderError::InvalidSpirV(_)),
+            ));
+            // SAFETY: Nothing is built from the module.
+            assert!(matches!(
+                unsafe { SpirV::from_bytes(bytes) },
+                Err(ShaderError::InvalidSpirV(_)),
+            ));
+        }
+    }
+
+    #[test]
+    fn malformed_spirv_modules_are_rejected() {
+        let header: [u32; 6] = [0x0723_0203, 0x0001_0000, 0, 8, 0, 0xDEAD_BEEF];
+        assert!(matches!(
+            ShaderDescriptor::from_spirv(bytemuck::cast_slice(&header)),
+            Err(ShaderError::InvalidSpirV(_)),
+        ));
+    }
+
+    #[test]
+    fn spirv_modules_draw_on_any_backend() {
+        let Some((_adapter, device, queue)) = testing::gpu() else {
+            return;
+        };
+        let bytes = solid_spirv();
+        let vertex_buffers = [Vertex::desc()];
+        let fragment_targets = [Some(wgpu::ColorTargetState {
+            format: Texture::FORMAT,
+            blend: None,
+            write_mask: wgpu::ColorWrites::ALL,
+        })];
+        let desc = |vertex_entry_point| ShaderDescriptor {
+            vertex_entry_point: Some(vertex_entry_point),
+            vertex_buffers: &vertex_buffers,
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &fragment_targets,
+            ..ShaderDescriptor::from_spirv(&bytes).unwrap()
+        };
+
+        assert_eq!(
+            Shader::new(&device, desc("vs_mian")).err(),
+            Some(ShaderError::MissingEntryPoint("vs_mian".to_string())),
+        );
+
+        let shader = Shader::new(&device, desc("vs_main")).unwrap();
+        let mut renderer = Renderer::start();
+        renderer.add_quad(&Quad::new([-0.5, -0.5], [1.0, 1.0]), [1.0, 1.0, 1.0]);
+        let batch = renderer.finish(&device);
+        let pixels = testing::render(&device, &queue, [8, 8], &[DrawCommand {
+            shader: &shader,
+            mesh: &batch.solid,
+            bind_groups: &[],
+            instances: None,
+            scissor: None,
+        }]);
+        assert_eq!(pixels[(4 * 8 + 4) * 4..][..4], [255; 4]);
+    }
+
+    #[test]
+    fn spirv_passthrough_needs_the_feature() {
+        let Some((_adapter, device, _queue)) = testing::gpu() else {
+            return;
+        };
+        if device.features().contains(wgpu::Features::SPIRV_SHADER_PASSTHROUGH) {
+            return;
+        }
+
+        // SAFETY: The module comes from naga, and the error is returned before it's used.
+        let desc = unsafe { ShaderDescriptor::from_spirv_passthrough(&solid_spirv()) }.unwrap();
+        assert_eq!(Shader::new(&device, desc).err(), Some(ShaderError::SpirVUnsupported));
     }
 }
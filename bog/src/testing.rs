@@ -0,0 +1,124 @@
+//! Test helpers
+
+
+
+use crate::{
+    AntiAliaser, AntiAliasing, BackgroundGradient, BlendMode, ChannelClear, Color, DrawCommand,
+    FadingTarget, Fxaa, ParticleSystem, Picker, RenderTarget, Shader, Texture,
+    TextureSet, pop_error_scope, read_texture, render_to,
+};
+
+
+
+/// A device with everything its adapter supports, or `None` if there's no adapter at all (in
+/// which case the test calling this should quietly pass).
+pub(crate) fn gpu() -> Option<(wgpu::Adapter, wgpu::Device, wgpu::Queue)> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::from_env_or_default());
+    let adapter = futures::executor::block_on(instance.request_adapter(&Default::default()))?;
+    let (device, queue) = futures::executor::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            label: Some("Test Device"),
+            required_features: adapter.features(),
+            required_limits: adapter.limits(),
+            ..Default::default()
+        },
+        None,
+    )).ok()?;
+
+    Some((adapter, device, queue))
+}
+
+/// A single-sampled [`Texture::FORMAT`] target of `size` that can be read back.
+pub(crate) fn target(device: &wgpu::Device, size: [u32; 2]) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Test Target"),
+        size: wgpu::Extent3d {
+            width: size[0],
+            height: size[1],
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: Texture::FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
+
+/// Clear a [`target`] of `size` to transparent, draw `commands` into it, and read it back.
+pub(crate) fn render(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    size: [u32; 2],
+    commands: &[DrawCommand],
+) -> Vec<u8> {
+    let texture = target(device, size);
+    let mut encoder = device.create_command_encoder(&Default::default());
+    render_to(
+        &mut encoder,
+        &[RenderTarget::from_texture(&texture)],
+        Some(wgpu::Color::TRANSPARENT),
+        commands,
+    ).unwrap();
+    queue.submit(std::iter::once(encoder.finish()));
+
+    read_texture(device, queue, &texture).unwrap()
+}
+
+/// Where the column-major `matrix` moves the point `[x, y]` (at a depth of `0.0`).
+pub(crate) fn transform(matrix: [[f32; 4]; 4], [x, y]: [f32; 2]) -> [f32; 2] {
+    [0, 1].map(|row| matrix[0][row] * x + matrix[1][row] * y + matrix[3][row])
+}
+
+
+
+mod tests {
+    use super::*;
+
+    /// Every pipeline that ships with the crate, built under a validation scope so that a bad
+    /// layout (like clashing shader locations) fails here instead of panicking in an app.
+    #[test]
+    fn builtin_pipelines_validate() {
+        let Some((adapter, device, _queue)) = gpu() else {
+            return;
+        };
+        let format = Texture::FORMAT;
+        let size = [16, 16];
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+
+        Shader::solid(&device, format, 1).unwrap();
+        Shader::solid_culled(&device, format, 1, Some(wgpu::Face::Back)).unwrap();
+        Shader::solid_layered(&device, format, 1).unwrap();
+        Shader::solid_blended(&device, format, 1, BlendMode::Additive).unwrap();
+        Shader::solid_model(&device, format, 1).unwrap();
+        Shader::solid_uniforms(&device, format, 1, None).unwrap();
+        Shader::solid_strip(&device, format, 1, wgpu::IndexFormat::Uint16).unwrap();
+        Shader::wireframe(&device, format, 1).unwrap();
+        Shader::instanced(&device, format, 1).unwrap();
+        Shader::textured(&device, format, 1).unwrap();
+        Shader::tinted_mask(&device, format, 1).unwrap();
+        Shader::circle(&device, format, 1).unwrap();
+        Shader::clipped(&device, format, 1).unwrap();
+        Shader::radial_gradient(&device, format, 1).unwrap();
+        Shader::linear_gradient(&device, format, 1).unwrap();
+        if device.features().contains(TextureSet::FEATURES) {
+            Shader::texture_array(&device, format, 1, 2).unwrap();
+        }
+        let (black, white) = (Color::rgb(0.0, 0.0, 0.0), Color::rgb(1.0, 1.0, 1.0));
+        BackgroundGradient::new(&device, format, 1, black, white).unwrap();
+        FadingTarget::new(&device, format, size, black, 0.1).unwrap();
+        ChannelClear::new(&device, format, 1, wgpu::ColorWrites::ALPHA, black).unwrap();
+        Fxaa::new(&device, format, size);
+        AntiAliaser::new(&adapter, &device, format, size, AntiAliasing::Msaa(4));
+        Picker::new(&device, size);
+        if device.limits().max_storage_buffers_per_shader_stage > 0 {
+            ParticleSystem::new(&device, format, 4, &[]).unwrap();
+        }
+
+        if let Some(error) = pop_error_scope(&device) {
+            panic!("{error}");
+        }
+    }
+}
@@ -0,0 +1,374 @@
+//! Text rendering & measurement
+//!
+//! Text is drawn with a small built-in bitmap font. Every glyph is a 5x8 grid of "dots" (the
+//! bottom row being reserved for descenders), and each lit run of dots is emitted as a quad, so
+//! text shares the solid-color pipeline with every other primitive.
+//!
+//! Since there's no glyph atlas, there's nothing to upload (or re-upload) per font: the glyphs
+//! of every `add_text` call end up in [`Batch::solid`](crate::Batch::solid), and all of the text
+//! in a frame is drawn by its single draw call.
+
+
+
+use crate::{Quad, Renderer};
+
+
+
+/// Metrics for the built-in font, in dots.
+pub struct FontMetrics;
+
+impl FontMetrics {
+    pub const GLYPH_WIDTH: u32 = 5;
+    pub const GLYPH_HEIGHT: u32 = 8;
+    /// The horizontal distance from the start of one glyph to the next.
+    pub const ADVANCE: u32 = 6;
+    /// The vertical distance from the top of one line to the next. A `size` passed to the text
+    /// functions is the height of one line, so each dot is `size / LINE_HEIGHT` units wide.
+    pub const LINE_HEIGHT: u32 = 10;
+    /// The number of empty dots above the glyphs on each line.
+    pub const LINE_PADDING: u32 = 1;
+
+    pub fn dot_size(size: f32) -> f32 {
+        size / Self::LINE_HEIGHT as f32
+    }
+}
+
+/// The `(width, height)` that `text` covers when drawn with [`Renderer::add_text`] at `size`.
+///
+/// Lines are separated by `\n`. The width is that of the longest line, excluding the spacing
+/// after its last glyph.
+pub fn measure_text(text: &str, size: f32) -> (f32, f32) {
+    let mut lines = 0;
+    let mut widest = 0.0f32;
+    for line in text.split('\n') {
+        lines += 1;
+        widest = widest.max(line_width(line, size));
+    }
+
+    (widest, lines as f32 * size)
+}
+
+fn line_width(line: &str, size: f32) -> f32 {
+    let glyphs = line.chars().count() as u32;
+    if glyphs == 0 {
+        return 0.0;
+    }
+
+    let dots = glyphs * FontMetrics::ADVANCE - (FontMetrics::ADVANCE - FontMetrics::GLYPH_WIDTH);
+    dots as f32 * FontMetrics::dot_size(size)
+}
+
+/// Break `text` into lines that each fit within `max_width` at `size`.
+///
+/// Explicit newlines always start a new line. Otherwise lines are broken on whitespace, and words
+/// too long to fit on a line by themselves are split wherever the line runs out.
+pub fn wrap_text(text: &str, size: f32, max_width: f32) -> Vec<String> {
+    let dot = FontMetrics::dot_size(size);
+    // The glyph after the first one on a line costs a full advance, but the first one only costs
+    // its width.
+    let max_glyphs = ((max_width / dot + (FontMetrics::ADVANCE - FontMetrics::GLYPH_WIDTH) as f32)
+        / FontMetrics::ADVANCE as f32)
+        .floor()
+        .max(1.0) as usize;
+
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut current = String::new();
+        let mut current_len = 0;
+        for word in paragraph.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            if current_len > 0 && current_len + 1 + word.len() <= max_glyphs {
+                current.push(' ');
+                current.extend(&word);
+                current_len += 1 + word.len();
+                continue;
+            }
+            if current_len > 0 {
+                lines.push(std::mem::take(&mut current));
+            }
+            while word.len() > max_glyphs {
+                lines.push(word.drain(..max_glyphs).collect());
+            }
+            current_len = word.len();
+            current.extend(word);
+        }
+        lines.push(current);
+    }
+
+    lines
+}
+
+/// The rows of dots for `ch`, top to bottom, with the leftmost dot in the highest bit.
+/// Characters outside printable ASCII are drawn as a hollow box.
+pub fn glyph(ch: char) -> [u8; 8] {
+    match ch {
+        ' '..='~' => GLYPHS[ch as usize - ' ' as usize],
+        _ => MISSING_GLYPH,
+    }
+}
+
+impl Renderer {
+    /// Draw `text` with its top-left corner at `pos`, in this renderer's coordinate space.
+    pub fn add_text(&mut self, pos: [f32; 2], text: &str, size: f32, color: [f32; 3]) {
+        let dot = FontMetrics::dot_size(size);
+        let y_down = self.space().is_y_down();
+
+        for (line_index, line) in text.split('\n').enumerate() {
+            let line_top = if y_down {
+                pos[1] + line_index as f32 * size
+            } else {
+                pos[1] - line_index as f32 * size
+            };
+            for (glyph_index, ch) in line.chars().enumerate() {
+                let x = pos[0] + (glyph_index as u32 * FontMetrics::ADVANCE) as f32 * dot;
+                self.add_glyph([x, line_top], glyph(ch), dot, y_down, color);
+            }
+        }
+    }
+
+    /// Draw `text` like [`Renderer::add_text`], with an `outline_width` halo of `outline_color`
+    /// around each glyph so it stays legible over busy backgrounds.
+    ///
+    /// NOTE: The built-in font isn't a distance field, so the outline is made by drawing the
+    ///       text again behind the fill, offset in each of the 8 directions.
+    pub fn add_text_outlined(
+        &mut self,
+        pos: [f32; 2],
+        text: &str,
+        size: f32,
+        fill_color: [f32; 3],
+        outline_color: [f32; 3],
+        outline_width: f32,
+    ) {
+        if outline_width > 0.0 {
+            for dx in [-1.0, 0.0, 1.0] {
+                for dy in [-1.0, 0.0, 1.0] {
+                    if dx == 0.0 && dy == 0.0 {
+                        continue;
+                    }
+                    let offset = [pos[0] + dx * outline_width, pos[1] + dy * outline_width];
+                    self.add_text(offset, text, size, outline_color);
+                }
+            }
+        }
+        self.add_text(pos, text, size, fill_color);
+    }
+
+    /// Draw `text` wrapped (see [`wrap_text`]) to the width of `rect`, starting at its top-left
+    /// corner. Returns the total height of the drawn lines.
+    ///
+    /// NOTE: Text isn't clipped to the bottom of `rect`.
+    pub fn add_text_wrapped(
+        &mut self,
+        rect: &Quad,
+        text: &str,
+        size: f32,
+        color: [f32; 3],
+    ) -> f32 {
+        let lines = wrap_text(text, size, rect.size[0]);
+        let top_left = if self.space().is_y_down() {
+            rect.pos
+        } else {
+            [rect.pos[0], rect.pos[1] + rect.size[1]]
+        };
+        self.add_text(top_left, &lines.join("\n"), size, color);
+
+        lines.len() as f32 * size
+    }
+
+    fn add_glyph(
+        &mut self,
+        pos: [f32; 2],
+        rows: [u8; 8],
+        dot: f32,
+        y_down: bool,
+        color: [f32; 3],
+    ) {
+        for (row_index, row) in rows.into_iter().enumerate() {
+            let row_offset = (FontMetrics::LINE_PADDING as usize + row_index) as f32 * dot;
+            let row_min = if y_down {
+                pos[1] + row_offset
+            } else {
+                pos[1] - row_offset - dot
+            };
+
+            // Merge each horizontal run of lit dots into a single quad.
+            let mut column = 0;
+            while column < FontMetrics::GLYPH_WIDTH {
+                if !dot_is_lit(row, column) {
+                    column += 1;
+                    continue;
+                }
+                let start = column;
+                while column < FontMetrics::GLYPH_WIDTH && dot_is_lit(row, column) {
+                    column += 1;
+                }
+                self.add_quad(
+                    &Quad::new(
+                        [pos[0] + start as f32 * dot, row_min],
+                        [(column - start) as f32 * dot, dot],
+                    ),
+                    color,
+                );
+            }
+        }
+    }
+}
+
+fn dot_is_lit(row: u8, column: u32) -> bool {
+    row & (1 << (FontMetrics::GLYPH_WIDTH - 1 - column)) != 0
+}
+
+
+
+const MISSING_GLYPH: [u8; 8] = [
+    0b11111, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11111, 0b00000,
+];
+
+/// Printable ASCII, starting at `' '`.
+const GLYPHS: [[u8; 8]; 95] = [
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000], // ' '
+    [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100, 0b00000], // '!'
+    [0b01010, 0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000], // '"'
+    [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010, 0b00000], // '#'
+    [0b00100, 0b01111, 0b10100, 0b01110, 0b00101, 0b11110, 0b00100, 0b00000], // '$'
+    [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011, 0b00000], // '%'
+    [0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101, 0b00000], // '&'
+    [0b00100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000], // '\''
+    [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010, 0b00000], // '('
+    [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000, 0b00000], // ')'
+    [0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000, 0b00000], // '*'
+    [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000, 0b00000], // '+'
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000], // ','
+    [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000, 0b00000], // '-'
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100, 0b00000], // '.'
+    [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000, 0b00000], // '/'
+    [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110, 0b00000], // '0'
+    [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000], // '1'
+    [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111, 0b00000], // '2'
+    [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110, 0b00000], // '3'
+    [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010, 0b00000], // '4'
+    [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110, 0b00000], // '5'
+    [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110, 0b00000], // '6'
+    [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00000], // '7'
+    [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110, 0b00000], // '8'
+    [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100, 0b00000], // '9'
+    [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000, 0b00000], // ':'
+    [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b00100, 0b01000, 0b00000], // ';'
+    [0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00000], // '<'
+    [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000], // '='
+    [0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000, 0b00000], // '>'
+    [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100, 0b00000], // '?'
+    [0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110, 0b00000], // '@'
+    [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001, 0b00000], // 'A'
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110, 0b00000], // 'B'
+    [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110, 0b00000], // 'C'
+    [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100, 0b00000], // 'D'
+    [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111, 0b00000], // 'E'
+    [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000, 0b00000], // 'F'
+    [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111, 0b00000], // 'G'
+    [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001, 0b00000], // 'H'
+    [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000], // 'I'
+    [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100, 0b00000], // 'J'
+    [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001, 0b00000], // 'K'
+    [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111, 0b00000], // 'L'
+    [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001, 0b00000], // 'M'
+    [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001, 0b00000], // 'N'
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110, 0b00000], // 'O'
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000, 0b00000], // 'P'
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101, 0b00000], // 'Q'
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001, 0b00000], // 'R'
+    [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110, 0b00000], // 'S'
+    [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000], // 'T'
+    [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110, 0b00000], // 'U'
+    [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00000], // 'V'
+    [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010, 0b00000], // 'W'
+    [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001, 0b00000], // 'X'
+    [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00000], // 'Y'
+    [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111, 0b00000], // 'Z'
+    [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110, 0b00000], // '['
+    [0b00000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000, 0b00000], // '\\'
+    [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110, 0b00000], // ']'
+    [0b00100, 0b01010, 0b10001, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000], // '^'
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111, 0b00000], // '_'
+    [0b01000, 0b00100, 0b00010, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000], // '`'
+    [0b00000, 0b00000, 0b01110, 0b00001, 0b01111, 0b10001, 0b01111, 0b00000], // 'a'
+    [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b11110, 0b00000], // 'b'
+    [0b00000, 0b00000, 0b01110, 0b10000, 0b10000, 0b10001, 0b01110, 0b00000], // 'c'
+    [0b00001, 0b00001, 0b01101, 0b10011, 0b10001, 0b10001, 0b01111, 0b00000], // 'd'
+    [0b00000, 0b00000, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110, 0b00000], // 'e'
+    [0b00110, 0b01001, 0b01000, 0b11100, 0b01000, 0b01000, 0b01000, 0b00000], // 'f'
+    [0b00000, 0b00000, 0b01111, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110], // 'g'
+    [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001, 0b00000], // 'h'
+    [0b00100, 0b00000, 0b01100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000], // 'i'
+    [0b00010, 0b00000, 0b00110, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100], // 'j'
+    [0b10000, 0b10000, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b00000], // 'k'
+    [0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110, 0b00000], // 'l'
+    [0b00000, 0b00000, 0b11010, 0b10101, 0b10101, 0b10001, 0b10001, 0b00000], // 'm'
+    [0b00000, 0b00000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001, 0b00000], // 'n'
+    [0b00000, 0b00000, 0b01110, 0b10001, 0b10001, 0b10001, 0b01110, 0b00000], // 'o'
+    [0b00000, 0b00000, 0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000], // 'p'
+    [0b00000, 0b00000, 0b01111, 0b10001, 0b10001, 0b01111, 0b00001, 0b00001], // 'q'
+    [0b00000, 0b00000, 0b10110, 0b11001, 0b10000, 0b10000, 0b10000, 0b00000], // 'r'
+    [0b00000, 0b00000, 0b01111, 0b10000, 0b01110, 0b00001, 0b11110, 0b00000], // 's'
+    [0b01000, 0b01000, 0b11100, 0b01000, 0b01000, 0b01001, 0b00110, 0b00000], // 't'
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b10011, 0b01101, 0b00000], // 'u'
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00000], // 'v'
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b10101, 0b10101, 0b01010, 0b00000], // 'w'
+    [0b00000, 0b00000, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b00000], // 'x'
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110], // 'y'
+    [0b00000, 0b00000, 0b11111, 0b00010, 0b00100, 0b01000, 0b11111, 0b00000], // 'z'
+    [0b00010, 0b00100, 0b00100, 0b01000, 0b00100, 0b00100, 0b00010, 0b00000], // '{'
+    [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000], // '|'
+    [0b01000, 0b00100, 0b00100, 0b00010, 0b00100, 0b00100, 0b01000, 0b00000], // '}'
+    [0b00000, 0b00000, 0b01000, 0b10101, 0b00010, 0b00000, 0b00000, 0b00000], // '~'
+];
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measured_widths_leave_out_the_last_advance() {
+        // NOTE: At a size of 10, each dot is 1 unit wide, so a glyph is 5 wide and advances 6.
+        assert_eq!(measure_text("a", 10.0), (5.0, 10.0));
+        assert_eq!(measure_text("abc", 10.0), (17.0, 10.0));
+        assert_eq!(measure_text("abc", 20.0), (34.0, 20.0));
+        assert_eq!(measure_text("a c", 10.0), (17.0, 10.0));
+        assert_eq!(measure_text("\u{e9}\u{e8}", 10.0), (11.0, 10.0));
+    }
+
+    #[test]
+    fn measured_lines_are_as_wide_as_the_longest() {
+        assert_eq!(measure_text("", 10.0), (0.0, 10.0));
+        assert_eq!(measure_text("ab\nabcd\na", 10.0), (23.0, 30.0));
+        assert_eq!(measure_text("abcd\n", 10.0), (23.0, 20.0));
+    }
+
+    #[test]
+    fn lines_are_broken_between_words() {
+        // NOTE: At a size of 10, 17 units fit exactly 3 glyphs, and 29 fit 5.
+        assert_eq!(wrap_text("ab cd ef", 10.0, 17.0), ["ab", "cd", "ef"]);
+        assert_eq!(wrap_text("ab cd ef", 10.0, 29.0), ["ab cd", "ef"]);
+        assert_eq!(wrap_text("ab cd ef", 10.0, 28.9), ["ab", "cd", "ef"]);
+        assert_eq!(wrap_text("ab  cd", 10.0, 100.0), ["ab cd"]);
+        assert_eq!(wrap_text("ab cd", 20.0, 58.0), ["ab cd"]);
+    }
+
+    #[test]
+    fn long_words_are_split_where_lines_run_out() {
+        assert_eq!(wrap_text("abcdefgh", 10.0, 17.0), ["abc", "def", "gh"]);
+        assert_eq!(wrap_text("a bcdef", 10.0, 17.0), ["a", "bcd", "ef"]);
+        // NOTE: Even lines too narrow for a single glyph get one.
+        assert_eq!(wrap_text("ab", 10.0, 1.0), ["a", "b"]);
+    }
+
+    #[test]
+    fn newlines_always_break() {
+        assert_eq!(wrap_text("a\n\nb", 10.0, 100.0), ["a", "", "b"]);
+        assert_eq!(wrap_text("ab cd\nef", 10.0, 17.0), ["ab", "cd", "ef"]);
+        assert_eq!(wrap_text("", 10.0, 100.0), [""]);
+    }
+}
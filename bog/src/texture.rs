@@ -0,0 +1,347 @@
+//! Textures & samplers
+
+
+
+pub struct Texture {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+    pub size: [u32; 2],
+}
+
+impl Texture {
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+    /// The single-channel format of alpha masks, like the ones loaded by [`Texture::from_r8`].
+    pub const MASK_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R8Unorm;
+
+    /// Create an uninitialized RGBA8 (sRGB) texture with the given usage.
+    pub fn empty(
+        device: &wgpu::Device,
+        size: [u32; 2],
+        usage: wgpu::TextureUsages,
+        sampler: &SamplerConfig,
+        label: Option<&str>,
+    ) -> Self {
+        Self::empty_with_format(device, size, Self::FORMAT, usage, sampler, label)
+    }
+
+    /// Like [`Texture::empty`], but with any (single-aspect, non-compressed) `format`.
+    pub fn empty_with_format(
+        device: &wgpu::Device,
+        size: [u32; 2],
+        format: wgpu::TextureFormat,
+        usage: wgpu::TextureUsages,
+        sampler: &SamplerConfig,
+        label: Option<&str>,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label,
+            size: wgpu::Extent3d {
+                width: size[0],
+                height: size[1],
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&sampler.descriptor(label));
+
+        Self {
+            texture,
+            view,
+            sampler,
+            size,
+        }
+    }
+
+    /// Create a texture from tightly packed RGBA8 (sRGB) pixels.
+    pub fn from_rgba8(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        size: [u32; 2],
+        data: &[u8],
+        sampler: &SamplerConfig,
+        label: Option<&str>,
+    ) -> Result<Self, String> {
+        Self::from_texels(device, queue, size, Self::FORMAT, data, sampler, label)
+    }
+
+    /// Create an alpha mask (of [`Texture::MASK_FORMAT`]) from one byte per pixel.
+    pub fn from_r8(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        size: [u32; 2],
+        data: &[u8],
+        sampler: &SamplerConfig,
+        label: Option<&str>,
+    ) -> Result<Self, String> {
+        Self::from_texels(device, queue, size, Self::MASK_FORMAT, data, sampler, label)
+    }
+
+    fn from_texels(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        size: [u32; 2],
+        format: wgpu::TextureFormat,
+        data: &[u8],
+        sampler: &SamplerConfig,
+        label: Option<&str>,
+    ) -> Result<Self, String> {
+        let texel_size = texel_size(format);
+        let expected_len = size[0] as usize * size[1] as usize * texel_size as usize;
+        if data.len() != expected_len {
+            return Err(format!(
+                "expected {expected_len} bytes of {format:?} data for a {}x{} texture, got {}",
+                size[0], size[1], data.len(),
+            ));
+        }
+
+        let texture = Self::empty_with_format(
+            device,
+            size,
+            format,
+            wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            sampler,
+            label,
+        );
+        queue.write_texture(
+            texture.texture.as_image_copy(),
+            data,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(size[0] * texel_size),
+                rows_per_image: Some(size[1]),
+            },
+            wgpu::Extent3d {
+                width: size[0],
+                height: size[1],
+                depth_or_array_layers: 1,
+            },
+        );
+
+        Ok(texture)
+    }
+
+    /// Record a copy of tightly packed texels (in the texture's format) covering all of it into
+    /// `encoder`, through a staging buffer. The texture must have been created with
+    /// [`wgpu::TextureUsages::COPY_DST`].
+    ///
+    /// Unlike [`wgpu::Queue::write_texture`], the copy runs as part of the caller's own
+    /// submission, so large uploads can be spread across frames alongside their rendering.
+    pub fn upload_staged(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        data: &[u8],
+    ) -> Result<(), String> {
+        let [width, height] = self.size;
+        let format = self.texture.format();
+        let row_len = width as usize * texel_size(format) as usize;
+        if data.len() != row_len * height as usize {
+            return Err(format!(
+                "expected {} bytes of {format:?} data for a {width}x{height} texture, got {}",
+                row_len * height as usize, data.len(),
+            ));
+        }
+        if !self.texture.usage().contains(wgpu::TextureUsages::COPY_DST) {
+            return Err("texture wasn't created with `TextureUsages::COPY_DST`".to_string());
+        }
+        if row_len == 0 || height == 0 {
+            return Ok(());
+        }
+
+        // NOTE: Rows in buffer-to-texture copies must start at multiples of this alignment, so
+        //       each row is padded out to it.
+        let padded_row_len = (row_len as u32).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Texture Staging Buffer"),
+            size: padded_row_len as wgpu::BufferAddress * height as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: true,
+        });
+        {
+            let mut mapped = buffer.slice(..).get_mapped_range_mut();
+            for (dst, src) in mapped
+                .chunks_exact_mut(padded_row_len as usize)
+                .zip(data.chunks_exact(row_len))
+            {
+                dst[..row_len].copy_from_slice(src);
+            }
+        }
+        buffer.unmap();
+
+        encoder.copy_buffer_to_texture(
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_len),
+                    rows_per_image: Some(height),
+                },
+            },
+            self.texture.as_image_copy(),
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        Ok(())
+    }
+}
+
+
+/// The size of one texel of `format`, in bytes.
+///
+/// # Panics
+///
+/// If `format` has multiple aspects (like depth-stencil formats).
+pub(crate) fn texel_size(format: wgpu::TextureFormat) -> u32 {
+    format.block_copy_size(None).expect("textures must have a single aspect")
+}
+
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SamplerConfig {
+    pub mag_filter: wgpu::FilterMode,
+    pub min_filter: wgpu::FilterMode,
+    pub mipmap_filter: wgpu::FilterMode,
+    pub address_mode_u: wgpu::AddressMode,
+    pub address_mode_v: wgpu::AddressMode,
+    /// The maximum anisotropic filtering level, from `1` (off) to `16`. Anything above `1`
+    /// needs every filter to be [`wgpu::FilterMode::Linear`], and adapter support (see
+    /// [`SamplerConfig::validate`]).
+    pub anisotropy: u16,
+}
+
+impl Default for SamplerConfig {
+    fn default() -> Self {
+        Self::linear()
+    }
+}
+
+impl SamplerConfig {
+    /// Smooth filtering, clamped to the texture's edges.
+    pub const fn linear() -> Self {
+        Self {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            anisotropy: 1,
+        }
+    }
+
+    /// Unfiltered texel lookups (for pixel art), clamped to the texture's edges.
+    pub const fn nearest() -> Self {
+        Self {
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            anisotropy: 1,
+        }
+    }
+
+    pub const fn with_address_mode(mut self, mode: wgpu::AddressMode) -> Self {
+        self.address_mode_u = mode;
+        self.address_mode_v = mode;
+        self
+    }
+
+    pub const fn repeat(self) -> Self {
+        self.with_address_mode(wgpu::AddressMode::Repeat)
+    }
+
+    /// Filter anisotropically, up to `level` (clamped to `1..=16`) samples.
+    pub const fn with_anisotropy(mut self, level: u16) -> Self {
+        self.anisotropy = if level < 1 {
+            1
+        } else if level > 16 {
+            16
+        } else {
+            level
+        };
+        self
+    }
+
+    /// Check that samplers made from this config can be created on devices from `adapter`.
+    pub fn validate(&self, adapter: &wgpu::Adapter) -> Result<(), String> {
+        if !(1..=16).contains(&self.anisotropy) {
+            return Err(format!("anisotropy of {} is outside of 1..=16", self.anisotropy));
+        }
+        if self.anisotropy == 1 {
+            return Ok(());
+        }
+
+        let linear = wgpu::FilterMode::Linear;
+        if self.mag_filter != linear || self.min_filter != linear || self.mipmap_filter != linear {
+            return Err("anisotropic filtering needs every filter to be linear".to_string());
+        }
+        let flags = adapter.get_downlevel_capabilities().flags;
+        if !flags.contains(wgpu::DownlevelFlags::ANISOTROPIC_FILTERING) {
+            return Err(format!(
+                "{} doesn't support anisotropic filtering",
+                adapter.get_info().name,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// The descriptor of a sampler made from this config.
+    ///
+    /// NOTE: wgpu rejects anisotropy with any filter that isn't linear, so it's turned off for
+    ///       those (rather than failing when the sampler is created), and levels outside
+    ///       `1..=16` are clamped. [`SamplerConfig::validate`] reports both.
+    pub fn descriptor<'a>(&self, label: Option<&'a str>) -> wgpu::SamplerDescriptor<'a> {
+        let linear = [self.mag_filter, self.min_filter, self.mipmap_filter]
+            .iter()
+            .all(|filter| *filter == wgpu::FilterMode::Linear);
+        let anisotropy_clamp = if linear { self.anisotropy.clamp(1, 16) } else { 1 };
+
+        wgpu::SamplerDescriptor {
+            label,
+            address_mode_u: self.address_mode_u,
+            address_mode_v: self.address_mode_v,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: self.mag_filter,
+            min_filter: self.min_filter,
+            mipmap_filter: self.mipmap_filter,
+            anisotropy_clamp,
+            ..Default::default()
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anisotropy_needs_linear_filters() {
+        let linear = SamplerConfig::linear().with_anisotropy(8);
+        assert_eq!(linear.descriptor(None).anisotropy_clamp, 8);
+
+        let nearest = SamplerConfig { anisotropy: 8, ..SamplerConfig::nearest() };
+        assert_eq!(nearest.descriptor(None).anisotropy_clamp, 1);
+        let mipmap_nearest = SamplerConfig { mipmap_filter: wgpu::FilterMode::Nearest, ..linear };
+        assert_eq!(mipmap_nearest.descriptor(None).anisotropy_clamp, 1);
+
+        for (anisotropy, clamp) in [(0, 1), (16, 16), (64, 16)] {
+            let config = SamplerConfig { anisotropy, ..SamplerConfig::linear() };
+            assert_eq!(config.descriptor(None).anisotropy_clamp, clamp);
+        }
+    }
+}
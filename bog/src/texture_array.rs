@@ -0,0 +1,278 @@
+//! Texture arrays
+
+
+
+use crate::{
+    Geometry, IndexWidth, Quad, Renderer, Shader, ShaderDescriptor, ShaderError,
+    TEXTURE_ARRAY_WGSL, Texture, TexturedMesh, TexturedVertex,
+};
+
+
+
+/// A [`TexturedVertex`], and the index of the texture it samples in a [`TextureSet`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TextureArrayVertex {
+    vertex: TexturedVertex,
+    texture: u32,
+}
+
+impl crate::Positioned for TextureArrayVertex {
+    fn pos(&self) -> [f32; 2] {
+        crate::Positioned::pos(&self.vertex)
+    }
+
+    fn pos_mut(&mut self) -> &mut [f32; 2] {
+        crate::Positioned::pos_mut(&mut self.vertex)
+    }
+}
+
+impl TextureArrayVertex {
+    pub const fn new(vertex: TexturedVertex, texture: u32) -> Self {
+        Self { vertex, texture }
+    }
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<TextureArrayVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Uint32,
+                },
+            ]
+        }
+    }
+}
+
+/// The geometry drawn with one [`TextureSet`]'s binding array.
+#[derive(Clone)]
+pub(crate) struct TextureArrayGeometry {
+    pub(crate) bind_group: wgpu::BindGroup,
+    pub(crate) geometry: Geometry<TextureArrayVertex>,
+}
+
+impl TexturedMesh {
+    /// Upload each non-empty geometry in `list` with its binding array.
+    pub(crate) fn upload_arrays(
+        list: &[TextureArrayGeometry],
+        device: &wgpu::Device,
+        width: Option<IndexWidth>,
+    ) -> Vec<Self> {
+        list.iter()
+            .filter_map(|array| Some(Self {
+                mesh: array.geometry.upload(device, width, "Texture Array")?,
+                bind_group: array.bind_group.clone(),
+            }))
+            .collect()
+    }
+}
+
+/// The geometry in `list` that samples the binding array of `bind_group`, which is added if it
+/// hasn't been used yet.
+pub(crate) fn array_geometry_for<'a>(
+    list: &'a mut Vec<TextureArrayGeometry>,
+    bind_group: &wgpu::BindGroup,
+) -> &'a mut Geometry<TextureArrayVertex> {
+    let index = match list.iter().position(|array| array.bind_group == *bind_group) {
+        Some(index) => index,
+        None => {
+            list.push(TextureArrayGeometry {
+                bind_group: bind_group.clone(),
+                geometry: Geometry::new(),
+            });
+            list.len() - 1
+        }
+    };
+
+    &mut list[index].geometry
+}
+
+
+
+/// Textures that quads can pick between per vertex, so that sprites using any of them are drawn
+/// together in one call.
+///
+/// This needs [`TextureSet::FEATURES`]. Without them (or with more textures than the device can
+/// sample in one stage), the set isn't batched: quads added with it fall back to one mesh (and
+/// draw call) per texture in [`Batch::textured`](crate::Batch::textured).
+///
+/// NOTE: When batched, every texture is sampled with the first one's sampler, so they should all
+///       be made with the same [`SamplerConfig`](crate::SamplerConfig).
+pub struct TextureSet {
+    textures: Vec<Texture>,
+    /// The binding array of every texture, if the set is batched.
+    bind_group: Option<wgpu::BindGroup>,
+}
+
+impl TextureSet {
+    /// What the device needs to index a binding array with a per-vertex texture index.
+    pub const FEATURES: wgpu::Features = wgpu::Features::TEXTURE_BINDING_ARRAY
+        .union(wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING);
+
+    pub fn new(device: &wgpu::Device, textures: Vec<Texture>) -> Self {
+        let batched = !textures.is_empty()
+            && device.features().contains(Self::FEATURES)
+            && textures.len() <= device.limits().max_sampled_textures_per_shader_stage as usize;
+        let bind_group = batched.then(|| {
+            let views: Vec<_> = textures.iter().map(|texture| &texture.view).collect();
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Texture Set Bind Group"),
+                layout: &Self::bind_group_layout(device, textures.len() as u32),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureViewArray(&views),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&textures[0].sampler),
+                    },
+                ],
+            })
+        });
+
+        Self {
+            textures,
+            bind_group,
+        }
+    }
+
+    /// The layout of a batched set's bind group of `count` textures: the binding array at
+    /// `@binding(0)` and the sampler at `@binding(1)`.
+    pub fn bind_group_layout(device: &wgpu::Device, count: u32) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Texture Set Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: std::num::NonZeroU32::new(count.max(1)),
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        })
+    }
+
+    pub fn textures(&self) -> &[Texture] {
+        &self.textures
+    }
+
+    pub fn len(&self) -> usize {
+        self.textures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty()
+    }
+
+    /// Whether quads using this set are drawn in a single call.
+    pub fn is_batched(&self) -> bool {
+        self.bind_group.is_some()
+    }
+}
+
+impl Renderer {
+    /// Draw `uv_rect` of the texture at `index` in `textures` stretched over `quad`, like
+    /// [`Renderer::add_textured_quad`].
+    ///
+    /// These are collected into [`Batch::texture_arrays`](crate::Batch::texture_arrays) when
+    /// the set is batched, and into [`Batch::textured`](crate::Batch::textured) otherwise.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds for `textures`.
+    pub fn add_texture_set_quad(
+        &mut self,
+        quad: &Quad,
+        textures: &TextureSet,
+        index: usize,
+        uv_rect: &Quad,
+    ) {
+        let texture = &textures.textures[index];
+        let Some(bind_group) = &textures.bind_group else {
+            self.add_textured_quad(quad, texture, uv_rect);
+            return;
+        };
+        if quad.is_degenerate() {
+            return;
+        }
+
+        let uv_max = [uv_rect.pos[0] + uv_rect.size[0], uv_rect.pos[1] + uv_rect.size[1]];
+        let vertices = self
+            .textured_quad_vertices(quad, uv_rect.pos, uv_max, crate::Color::WHITE)
+            .map(|vertex| TextureArrayVertex::new(vertex, index as u32));
+        array_geometry_for(&mut self.texture_arrays, bind_group)
+            .add(vertices, Quad::indices_u32());
+    }
+}
+
+impl Shader {
+    /// The built-in pipeline for drawing [`Batch::texture_arrays`] made with a [`TextureSet`] of
+    /// `count` textures into a target of `format` with `sample_count` samples per pixel.
+    ///
+    /// Fails if the device doesn't have [`TextureSet::FEATURES`].
+    ///
+    /// [`Batch::texture_arrays`]: crate::Batch::texture_arrays
+    pub fn texture_array(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        count: u32,
+    ) -> Result<Self, ShaderError> {
+        let missing = TextureSet::FEATURES - device.features();
+        if !missing.is_empty() {
+            return Err(ShaderError::MissingFeatures(missing));
+        }
+
+        Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(TEXTURE_ARRAY_WGSL)),
+            label: Some("texture_array.wgsl"),
+            pipeline_label: Some("Texture Array Pipeline"),
+            pipeline_layout_label: Some("Texture Array Pipeline Layout"),
+            bind_group_layouts: &[&TextureSet::bind_group_layout(device, count)],
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &[TextureArrayVertex::desc()],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            primitive: ShaderDescriptor::UNCULLED_PRIMITIVE,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..ShaderDescriptor::default().multisample
+            },
+            ..Default::default()
+        })
+    }
+}
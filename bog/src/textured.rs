@@ -0,0 +1,337 @@
+//! Textured primitives
+
+
+
+use crate::{
+    Atlas, AtlasRegion, Color, Geometry, IndexWidth, MASK_WGSL, Mesh, Quad, Renderer, Shader,
+    ShaderDescriptor, ShaderError, TEXTURED_WGSL, Texture,
+};
+
+
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TexturedVertex {
+    pos: [f32; 2],
+    uv: [f32; 2],
+    /// Multiplied with the sampled texel (or, for masks, used as the color).
+    color: [f32; 4],
+}
+
+impl crate::Positioned for TexturedVertex {
+    fn pos(&self) -> [f32; 2] {
+        self.pos
+    }
+
+    fn pos_mut(&mut self) -> &mut [f32; 2] {
+        &mut self.pos
+    }
+}
+
+impl TexturedVertex {
+    pub const fn new(pos: [f32; 2], uv: [f32; 2]) -> Self {
+        Self::tinted(pos, uv, Color::WHITE)
+    }
+
+    pub const fn tinted(pos: [f32; 2], uv: [f32; 2], color: Color) -> Self {
+        Self {
+            pos,
+            uv,
+            color: [color.r, color.g, color.b, color.a],
+        }
+    }
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<TexturedVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ]
+        }
+    }
+}
+
+/// The geometry drawn with one texture.
+#[derive(Clone)]
+pub(crate) struct TexturedGeometry {
+    pub(crate) view: wgpu::TextureView,
+    pub(crate) sampler: wgpu::Sampler,
+    pub(crate) geometry: Geometry<TexturedVertex>,
+}
+
+impl TexturedGeometry {
+    pub(crate) fn bind_group(&self, device: &wgpu::Device) -> wgpu::BindGroup {
+        texture_bind_group(device, &self.view, &self.sampler)
+    }
+}
+
+/// A mesh made of [`TexturedVertex`]es, and the texture it samples (bound at `@group(0)`).
+pub struct TexturedMesh {
+    pub mesh: Mesh,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl TexturedMesh {
+    /// Upload each non-empty geometry in `list` with its texture's bind group.
+    pub(crate) fn upload_all(
+        list: &[TexturedGeometry],
+        device: &wgpu::Device,
+        width: Option<IndexWidth>,
+        label: &str,
+    ) -> Vec<Self> {
+        list.iter()
+            .filter_map(|textured| Some(Self {
+                mesh: textured.geometry.upload(device, width, label)?,
+                bind_group: textured.bind_group(device),
+            }))
+            .collect()
+    }
+}
+
+/// The geometry in `list` that samples `view`, which is added if it hasn't been used yet.
+pub(crate) fn geometry_for<'a>(
+    list: &'a mut Vec<TexturedGeometry>,
+    view: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+) -> &'a mut Geometry<TexturedVertex> {
+    let index = match list.iter().position(|textured| textured.view == *view) {
+        Some(index) => index,
+        None => {
+            list.push(TexturedGeometry {
+                view: view.clone(),
+                sampler: sampler.clone(),
+                geometry: Geometry::new(),
+            });
+            list.len() - 1
+        }
+    };
+
+    &mut list[index].geometry
+}
+
+impl Renderer {
+    /// The geometry that samples `view`, which is added if it hasn't been used yet.
+    pub(crate) fn textured_geometry(
+        &mut self,
+        view: &wgpu::TextureView,
+        sampler: &wgpu::Sampler,
+    ) -> &mut Geometry<TexturedVertex> {
+        geometry_for(&mut self.textured, view, sampler)
+    }
+
+    /// Draw `uv_rect` of `texture` (in texture coordinates, from its top-left corner) stretched
+    /// over `quad`.
+    ///
+    /// These are collected into [`Batch::textured`](crate::Batch::textured).
+    pub fn add_textured_quad(&mut self, quad: &Quad, texture: &Texture, uv_rect: &Quad) {
+        self.add_textured_quad_tinted(quad, texture, uv_rect, Color::WHITE);
+    }
+
+    /// Like [`Renderer::add_textured_quad`], but with every texel multiplied by `tint`, e.g. to
+    /// fade out or darken a sprite.
+    pub fn add_textured_quad_tinted(
+        &mut self,
+        quad: &Quad,
+        texture: &Texture,
+        uv_rect: &Quad,
+        tint: Color,
+    ) {
+        if quad.is_degenerate() {
+            return;
+        }
+
+        let uv_max = [uv_rect.pos[0] + uv_rect.size[0], uv_rect.pos[1] + uv_rect.size[1]];
+        let vertices = self.textured_quad_vertices(quad, uv_rect.pos, uv_max, tint);
+        self.textured_geometry(&texture.view, &texture.sampler)
+            .add(vertices, Quad::indices_u32());
+    }
+
+    /// The corners of `quad`, with texture coordinates from `uv_min` (at the top-left) to
+    /// `uv_max`.
+    pub(crate) fn textured_quad_vertices(
+        &self,
+        quad: &Quad,
+        uv_min: [f32; 2],
+        uv_max: [f32; 2],
+        color: Color,
+    ) -> [TexturedVertex; 4] {
+        // NOTE: `quad.pos` is the top-left corner in Y-down spaces, but the bottom-left one
+        //       otherwise, while texture coordinates always start at the top.
+        let (top, bottom) = if self.space().is_y_down() {
+            (uv_min[1], uv_max[1])
+        } else {
+            (uv_max[1], uv_min[1])
+        };
+        let [x, y] = quad.pos;
+        let [w, h] = quad.size;
+
+        [
+            TexturedVertex::tinted([x, y], [uv_min[0], top], color),
+            TexturedVertex::tinted([x + w, y], [uv_max[0], top], color),
+            TexturedVertex::tinted([x, y + h], [uv_min[0], bottom], color),
+            TexturedVertex::tinted([x + w, y + h], [uv_max[0], bottom], color),
+        ]
+    }
+
+    /// Draw `region` of the alpha masks in `masks` (see [`Atlas::masks`]) stretched over `quad`,
+    /// in `color`.
+    ///
+    /// These are collected into [`Batch::tinted_icons`](crate::Batch::tinted_icons). If the atlas
+    /// grows afterwards, the icon still samples the old texture.
+    pub fn add_tinted_icon(
+        &mut self,
+        quad: &Quad,
+        masks: &Atlas,
+        region: AtlasRegion,
+        color: [f32; 3],
+    ) {
+        if quad.is_degenerate() {
+            return;
+        }
+
+        let (uv_min, uv_max) = region.uv_rect(masks.size());
+        let vertices = self.textured_quad_vertices(quad, uv_min, uv_max, color.into());
+        let texture = masks.texture();
+        geometry_for(&mut self.tinted_icons, &texture.view, &texture.sampler)
+            .add(vertices, Quad::indices_u32());
+    }
+}
+
+impl Texture {
+    /// The layout of the bind group made by [`Texture::bind_group`]: the texture at
+    /// `@binding(0)` and its sampler at `@binding(1)`.
+    pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Texture Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        })
+    }
+
+    pub fn bind_group(&self, device: &wgpu::Device) -> wgpu::BindGroup {
+        texture_bind_group(device, &self.view, &self.sampler)
+    }
+}
+
+fn texture_bind_group(
+    device: &wgpu::Device,
+    view: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Texture Bind Group"),
+        // NOTE: Layouts with identical entries are interchangeable, so this works with any
+        //       pipeline built from `Texture::bind_group_layout`.
+        layout: &Texture::bind_group_layout(device),
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    })
+}
+
+impl Shader {
+    /// The built-in pipeline for drawing [`Batch::textured`] meshes into a target of `format`
+    /// with `sample_count` samples per pixel.
+    ///
+    /// [`Batch::textured`]: crate::Batch::textured
+    pub fn textured(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Result<Self, ShaderError> {
+        Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(TEXTURED_WGSL)),
+            label: Some("textured.wgsl"),
+            pipeline_label: Some("Textured Pipeline"),
+            pipeline_layout_label: Some("Textured Pipeline Layout"),
+            bind_group_layouts: &[&Texture::bind_group_layout(device)],
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &[TexturedVertex::desc()],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            primitive: ShaderDescriptor::UNCULLED_PRIMITIVE,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..ShaderDescriptor::default().multisample
+            },
+            ..Default::default()
+        })
+    }
+}
+
+impl Shader {
+    /// The built-in pipeline for drawing [`Batch::tinted_icons`] into a target of `format` with
+    /// `sample_count` samples per pixel.
+    ///
+    /// [`Batch::tinted_icons`]: crate::Batch::tinted_icons
+    pub fn tinted_mask(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Result<Self, ShaderError> {
+        Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(MASK_WGSL)),
+            label: Some("mask.wgsl"),
+            pipeline_label: Some("Tinted Mask Pipeline"),
+            pipeline_layout_label: Some("Tinted Mask Pipeline Layout"),
+            bind_group_layouts: &[&Texture::bind_group_layout(device)],
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &[TexturedVertex::desc()],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            primitive: ShaderDescriptor::UNCULLED_PRIMITIVE,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..ShaderDescriptor::default().multisample
+            },
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,145 @@
+//! Solid uniforms
+
+
+
+use wgpu::util::DeviceExt as _;
+
+use crate::{Color, SOLID_UNIFORMS_WGSL, Shader, ShaderDescriptor, ShaderError, Vertex};
+
+
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[derive(bytemuck::Pod, bytemuck::Zeroable)]
+struct SolidUniformsParams {
+    tint: [f32; 4],
+    time: f32,
+    _padding: [f32; 3],
+}
+
+/// The uniform block that [`Shader::solid_uniforms`] reads at `@group(0)`, for animating (or
+/// otherwise parameterizing) the standard quad path without rebuilding batches.
+///
+/// Pass [`SolidUniforms::bind_group`] in [`DrawCommand::bind_groups`](crate::DrawCommand).
+pub struct SolidUniforms {
+    params: SolidUniformsParams,
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl SolidUniforms {
+    /// Upload a white tint (which leaves colors as they are) at a time of `0.0`.
+    pub fn new(device: &wgpu::Device) -> Self {
+        let params = SolidUniformsParams {
+            tint: [1.0; 4],
+            time: 0.0,
+            _padding: [0.0; 3],
+        };
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Solid Uniforms Buffer"),
+            contents: bytemuck::bytes_of(&params),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Solid Uniforms Bind Group"),
+            // NOTE: Layouts with identical entries are interchangeable.
+            layout: &Self::bind_group_layout(device),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            params,
+            buffer,
+            bind_group,
+        }
+    }
+
+    /// The layout [`Shader::solid_uniforms`] uses when it isn't given one. The block is visible
+    /// to both stages.
+    pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Solid Uniforms Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        })
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    pub fn tint(&self) -> Color {
+        let [r, g, b, a] = self.params.tint;
+        Color::rgba(r, g, b, a)
+    }
+
+    pub fn time(&self) -> f32 {
+        self.params.time
+    }
+
+    pub fn set_tint(&mut self, queue: &wgpu::Queue, tint: Color) {
+        self.params.tint = [tint.r, tint.g, tint.b, tint.a];
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&self.params));
+    }
+
+    pub fn set_time(&mut self, queue: &wgpu::Queue, time: f32) {
+        self.params.time = time;
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&self.params));
+    }
+}
+
+impl Shader {
+    /// Like [`Shader::solid`], but with a uniform block at `@group(0)` (see
+    /// [`SOLID_UNIFORMS_WGSL`]), which draw commands must bind.
+    ///
+    /// `layout` defaults to [`SolidUniforms::bind_group_layout`]. Custom layouts (e.g. with
+    /// more bindings, or a bigger block for a copy of the shader) must still have the block at
+    /// `@binding(0)`, and the matching bind groups are then up to the caller.
+    pub fn solid_uniforms(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        layout: Option<&wgpu::BindGroupLayout>,
+    ) -> Result<Self, ShaderError> {
+        let default_layout;
+        let layout = match layout {
+            Some(layout) => layout,
+            None => {
+                default_layout = SolidUniforms::bind_group_layout(device);
+                &default_layout
+            }
+        };
+        Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(SOLID_UNIFORMS_WGSL)),
+            label: Some("solid_uniforms.wgsl"),
+            pipeline_label: Some("Solid Uniforms Pipeline"),
+            pipeline_layout_label: Some("Solid Uniforms Pipeline Layout"),
+            bind_group_layouts: &[layout],
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &[Vertex::desc()],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            primitive: ShaderDescriptor::UNCULLED_PRIMITIVE,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..ShaderDescriptor::default().multisample
+            },
+            ..Default::default()
+        })
+    }
+}
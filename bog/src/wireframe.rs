@@ -0,0 +1,88 @@
+//! Debug wireframes
+
+
+
+use crate::{
+    IndexWidth, Mesh, Renderer, Shader, ShaderDescriptor, ShaderError, Vertex, WIREFRAME_WGSL,
+};
+
+
+
+impl Renderer {
+    /// Outline every solid primitive added while this is on with the edges of its triangles,
+    /// uploaded as a separate line list in [`Batch::wireframe`](crate::Batch::wireframe). Off
+    /// by default.
+    pub fn set_debug_wireframe(&mut self, enabled: bool) {
+        let len = self.indices.len() as u32;
+        match self.wireframe_start {
+            None if enabled => self.wireframe_start = Some(len),
+            Some(start) if !enabled => {
+                self.wireframed.push(start..len);
+                self.wireframe_start = None;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn debug_wireframe(&self) -> bool {
+        self.wireframe_start.is_some()
+    }
+
+    /// The wireframe of the solid mesh, or `None` if nothing was added with it on.
+    pub(crate) fn upload_wireframe(
+        &self,
+        device: &wgpu::Device,
+        width: Option<IndexWidth>,
+    ) -> Option<Mesh> {
+        let lines: Vec<u32> = self.wireframed
+            .iter()
+            .flat_map(|range| {
+                self.indices[range.start as usize..range.end as usize].chunks_exact(3)
+            })
+            .flat_map(|triangle| {
+                let [a, b, c] = [triangle[0], triangle[1], triangle[2]];
+                [a, b, b, c, c, a]
+            })
+            .collect();
+
+        let format = width.map(|width| width.format_for(&lines));
+        (!lines.is_empty()).then(|| {
+            Mesh::upload(device, &self.vertices, &lines, format, Some("Wireframe"))
+        })
+    }
+}
+
+impl Shader {
+    /// The built-in pipeline for drawing [`Batch::wireframe`](crate::Batch::wireframe) into a
+    /// target of `format` with `sample_count` samples per pixel.
+    pub fn wireframe(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Result<Self, ShaderError> {
+        Shader::new(device, ShaderDescriptor {
+            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(WIREFRAME_WGSL)),
+            label: Some("wireframe.wgsl"),
+            pipeline_label: Some("Wireframe Pipeline"),
+            pipeline_layout_label: Some("Wireframe Pipeline Layout"),
+            vertex_entry_point: Some("vs_main"),
+            vertex_buffers: &[Vertex::desc()],
+            fragment_entry_point: Some("fs_main"),
+            fragment_targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::REPLACE),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                cull_mode: None,
+                ..ShaderDescriptor::default().primitive
+            },
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..ShaderDescriptor::default().multisample
+            },
+            ..Default::default()
+        })
+    }
+}
@@ -6,112 +6,399 @@ use winit::{event::WindowEvent, window::Window};
 
 
 
+/// What the scene is cleared to, in the same (linear) space as the primitives' colors.
+const BACKGROUND: Color = Color::rgb(0.2, 0.1, 0.3);
+
 fn main() {
+    // NOTE: Set `BOG_ALPHA` to `premultiplied`, `postmultiplied`, or `inherit` to composite the
+    //       window with what's behind it, where the platform supports it.
+    let alpha_mode = match std::env::var("BOG_ALPHA").as_deref() {
+        Ok("premultiplied") => wgpu::CompositeAlphaMode::PreMultiplied,
+        Ok("postmultiplied") => wgpu::CompositeAlphaMode::PostMultiplied,
+        Ok("inherit") => wgpu::CompositeAlphaMode::Inherit,
+        _ => wgpu::CompositeAlphaMode::Opaque,
+    };
+
     let event_loop = winit::event_loop::EventLoop::new().unwrap();
     let window = winit::window::WindowBuilder::new()
         .with_title("Bog WGPU")
+        .with_transparent(alpha_mode != wgpu::CompositeAlphaMode::Opaque)
         .with_inner_size(winit::dpi::LogicalSize::new(1200, 800))
         .build(&event_loop)
         .unwrap();
 
-    let mut state = futures::executor::block_on(async { State::new(&window).await });
+    // NOTE: Set `BOG_ADAPTER` to an index into `bog::enumerate_adapters` to force a specific GPU.
+    let adapter_index = std::env::var("BOG_ADAPTER").ok().and_then(|index| index.parse().ok());
+    // NOTE: Set `BOG_AA` to `msaa`, `msaa<samples>`, or `fxaa` to turn on anti-aliasing.
+    let antialiasing = std::env::var("BOG_AA").ok().map_or(AntiAliasing::None, |mode| {
+        match mode.as_str() {
+            "fxaa" => AntiAliasing::Fxaa,
+            "msaa" => AntiAliasing::Msaa(4),
+            _ => mode
+                .strip_prefix("msaa")
+                .and_then(|samples| samples.parse().ok())
+                .map_or(AntiAliasing::None, AntiAliasing::Msaa),
+        }
+    });
+    // NOTE: Set `BOG_FRAMES_IN_FLIGHT` to bound how far the GPU can fall behind (2 by default).
+    let frames_in_flight = std::env::var("BOG_FRAMES_IN_FLIGHT")
+        .ok()
+        .and_then(|frames| frames.parse().ok())
+        .unwrap_or(2);
+    // NOTE: Set `BOG_UNINDEXED` to draw without index buffers, which is always done on the web
+    //       since some WebGL implementations mishandle them.
+    let indexed = std::env::var_os("BOG_UNINDEXED").is_none() && !cfg!(target_arch = "wasm32");
+    // NOTE: Set `BOG_LINEAR_VIEW` to allow linear views of the (sRGB) surface as well.
+    let linear_view = std::env::var_os("BOG_LINEAR_VIEW").is_some();
+    let mut state = futures::executor::block_on(async {
+        State::new(
+            &window,
+            adapter_index,
+            antialiasing,
+            frames_in_flight,
+            alpha_mode,
+            indexed,
+            linear_view,
+        ).await
+    });
+    // NOTE: Set `BOG_MAX_FPS` to cap the frame rate when the surface isn't vsynced.
+    if let Some(max_fps) = std::env::var("BOG_MAX_FPS").ok().and_then(|fps| fps.parse().ok()) {
+        state.set_max_fps(Some(max_fps));
+    }
     let mut surface_configured = false;
+    let mut cursor_position = winit::dpi::PhysicalPosition::new(0.0, 0.0);
+
+    let mut suspended = false;
 
     event_loop.run(move |event, control_flow| {
         match event {
-            winit::event::Event::WindowEvent { window_id: _, event } => {
-                match event {
-                    WindowEvent::CloseRequested => {
-                        control_flow.exit();
-                    }
-                    WindowEvent::Resized(physical_size) => {
-                        surface_configured = true;
-                        state.resize(physical_size);
+            winit::event::Event::Suspended => {
+                suspended = true;
+                surface_configured = false;
+            }
+            // NOTE: This is also sent once at startup, when the surface is still valid.
+            winit::event::Event::Resumed if suspended => {
+                suspended = false;
+                let window = state.window();
+                state.recreate_surface(window);
+                let size = window.inner_size();
+                surface_configured = size.width > 0 && size.height > 0;
+                window.request_redraw();
+            }
+            _ => {}
+        }
+        if let winit::event::Event::WindowEvent { window_id: _, event } = event {
+            match event {
+                WindowEvent::CloseRequested => {
+                    control_flow.exit();
+                }
+                WindowEvent::Resized(physical_size) => {
+                    surface_configured = true;
+                    state.resize(physical_size);
+                }
+                WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                    state.scale_factor_changed(scale_factor);
+                }
+                WindowEvent::CursorMoved { position, .. } => {
+                    cursor_position = position;
+                }
+                WindowEvent::MouseInput {
+                    state: winit::event::ElementState::Pressed,
+                    button: winit::event::MouseButton::Left,
+                    ..
+                } => {
+                    let (x, y) = (cursor_position.x as u32, cursor_position.y as u32);
+                    if let Some(id) = state.pick(x, y) {
+                        println!("Picked primitive {id}");
                     }
-                    WindowEvent::RedrawRequested => {
-                        // This tells winit that we want another frame after this one
+                }
+                WindowEvent::RedrawRequested => {
+                    // This tells winit that we want another frame after this one
+                    if state.continuous_redraw() {
                         state.window().request_redraw();
+                    }
 
-                        if !surface_configured {
-                            return;
-                        }
+                    if !surface_configured {
+                        return;
+                    }
 
-                        state.update();
-                        match state.render() {
-                            Ok(_) => {}
-                            Err(
-                                wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated,
-                            ) => state.resize(state.size),
-                            Err(wgpu::SurfaceError::OutOfMemory | wgpu::SurfaceError::Other) => {
-                                println!("FATAL: Out of memory!");
-                                control_flow.exit();
-                            }
-                            Err(wgpu::SurfaceError::Timeout) => {
-                                println!(
-                                    "WARNING: Surface timed out, frame took too long to present",
-                                );
-                            }
+                    state.update();
+                    match state.render() {
+                        Ok(_) => {}
+                        Err(
+                            wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated,
+                        ) => state.resize(state.size),
+                        Err(wgpu::SurfaceError::OutOfMemory | wgpu::SurfaceError::Other) => {
+                            println!("FATAL: Out of memory!");
+                            control_flow.exit();
+                        }
+                        Err(wgpu::SurfaceError::Timeout) => {
+                            println!(
+                                "WARNING: Surface timed out, frame took too long to present",
+                            );
                         }
                     }
-                    _ => {}
                 }
+                _ => {}
             }
-            _ => {}
         }
     }).unwrap();
 }
 
 
 
-struct State<'a> {
+/// A window and the surface presenting to it.
+///
+/// The surface borrows the window for `'a`, so the borrow checker (rather than field order)
+/// guarantees that it's dropped first. Keeping both behind one type means nothing else can hold
+/// onto the surface after the window goes away.
+struct WindowSurface<'a> {
     surface: wgpu::Surface<'a>,
+    config: wgpu::SurfaceConfiguration,
+    window: &'a Window,
+}
+
+impl<'a> WindowSurface<'a> {
+    /// Configure `surface` (which must have been created from `window`) for `device`, preferring
+    /// an sRGB format.
+    ///
+    /// `alpha_mode` is used if the surface supports it, falling back to `Opaque` (or whatever
+    /// the surface prefers, if not even that is supported). See [`WindowSurface::alpha_mode`].
+    fn new(
+        surface: wgpu::Surface<'a>,
+        window: &'a Window,
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        alpha_mode: wgpu::CompositeAlphaMode,
+    ) -> Self {
+        let size = window.inner_size();
+        let surface_caps = surface.get_capabilities(adapter);
+        let surface_format = surface_caps
+            .formats
+            .iter()
+            .copied()
+            .find(|f| f.is_srgb())
+            .unwrap_or(surface_caps.formats[0]);
+        let alpha_mode = [alpha_mode, wgpu::CompositeAlphaMode::Opaque]
+            .into_iter()
+            .find(|mode| surface_caps.alpha_modes.contains(mode))
+            .unwrap_or(surface_caps.alpha_modes[0]);
+        // NOTE: Copying into the surface lets frames be kept between redraws (see
+        //       `State::mark_dirty`), but not every surface allows it.
+        let usage = wgpu::TextureUsages::RENDER_ATTACHMENT
+            | (surface_caps.usages & wgpu::TextureUsages::COPY_DST);
+        let config = wgpu::SurfaceConfiguration {
+            usage,
+            format: surface_format,
+            width: size.width,
+            height: size.height,
+            present_mode: surface_caps.present_modes[0],
+            alpha_mode,
+            desired_maximum_frame_latency: 2,
+            view_formats: vec![],
+        };
+        if size.width > 0 && size.height > 0 {
+            surface.configure(device, &config);
+        }
+
+        Self {
+            surface,
+            config,
+            window,
+        }
+    }
+
+    fn window(&self) -> &'a Window {
+        self.window
+    }
+
+    /// Allow views of the surface's textures in `view_formats` (besides its own format), like
+    /// a linear view of an sRGB surface.
+    ///
+    /// Fails (keeping the old view formats) if `adapter` can't view the surface that way. See
+    /// [`validate_surface_view_formats`].
+    fn set_view_formats(
+        &mut self,
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        view_formats: Vec<wgpu::TextureFormat>,
+    ) -> Result<(), String> {
+        validate_surface_view_formats(adapter, self.config.format, &view_formats)?;
+        self.config.view_formats = view_formats;
+        if self.config.width > 0 && self.config.height > 0 {
+            self.surface.configure(device, &self.config);
+        }
+
+        Ok(())
+    }
+
+    fn format(&self) -> wgpu::TextureFormat {
+        self.config.format
+    }
+
+    /// Whether frames can be copied into the surface's textures.
+    fn can_copy_into(&self) -> bool {
+        self.config.usage.contains(wgpu::TextureUsages::COPY_DST)
+    }
+
+    /// Whether presenting waits for vertical blanks, which already caps the frame rate.
+    fn is_vsynced(&self) -> bool {
+        matches!(
+            self.config.present_mode,
+            wgpu::PresentMode::Fifo | wgpu::PresentMode::FifoRelaxed | wgpu::PresentMode::AutoVsync,
+        )
+    }
+
+    /// How the surface is composited with what's behind the window.
+    fn alpha_mode(&self) -> wgpu::CompositeAlphaMode {
+        self.config.alpha_mode
+    }
+
+    fn resize(&mut self, device: &wgpu::Device, size: winit::dpi::PhysicalSize<u32>) {
+        self.config.width = size.width;
+        self.config.height = size.height;
+        self.surface.configure(device, &self.config);
+    }
+
+    /// Swap in a `surface` newly created from `window`, e.g. after the old one was destroyed
+    /// while the app was suspended.
+    ///
+    /// NOTE: The old configuration (and so its format) is kept, because pipelines were already
+    ///       built for it. Only the size is updated.
+    fn recreate(
+        &mut self,
+        surface: wgpu::Surface<'a>,
+        window: &'a Window,
+        device: &wgpu::Device,
+    ) {
+        let size = window.inner_size();
+        self.surface = surface;
+        self.window = window;
+        self.config.width = size.width;
+        self.config.height = size.height;
+        if size.width > 0 && size.height > 0 {
+            self.surface.configure(device, &self.config);
+        }
+    }
+
+    fn get_current_texture(&self) -> Result<wgpu::SurfaceTexture, wgpu::SurfaceError> {
+        self.surface.get_current_texture()
+    }
+}
+
+
+
+/// Extra drawing injected into [`State::render`], given the encoder and the target to draw into.
+type RenderHook<'a> = Box<dyn FnMut(&mut wgpu::CommandEncoder, &RenderTarget) + 'a>;
+
+struct State<'a> {
+    /// Kept around to recreate the surface with.
+    instance: wgpu::Instance,
+    surface: WindowSurface<'a>,
+    adapter: wgpu::Adapter,
     device: wgpu::Device,
     queue: wgpu::Queue,
-    config: wgpu::SurfaceConfiguration,
     size: winit::dpi::PhysicalSize<u32>,
-
-    // NOTE: Window must be dropped after the other surface fields.
-    window: &'a Window,
+    scale_factor: f64,
 
     shader: Shader,
-    vertex_buffer: wgpu::Buffer,
-    index_buffer: wgpu::Buffer,
-    num_indices: u32,
+    gradient_shader: Shader,
+    circle_shader: Shader,
+    /// Keeps the circles' edges 1.5 physical pixels soft at any size.
+    feather: EdgeFeather,
+    batch: Batch,
+    /// What `batch` contains, shown in the HUD.
+    stats: RenderStats,
+    /// Laid out in physical pixels from the top-left, so resizing only changes its viewport,
+    /// and it's only laid out again when the scale factor changes.
+    hud_layout: Renderer,
+    /// `hud_layout`, uploaded.
+    hud: Batch,
+    picker: Picker,
+    antialiaser: AntiAliaser,
+    /// `None` on devices without compute support.
+    particles: Option<ParticleSystem>,
+    frame_limiter: FrameLimiter,
+    /// See [`State::set_max_fps`].
+    frame_rate_cap: FrameRateCap,
+    /// Whether meshes are drawn with index buffers.
+    indexed: bool,
+    last_update: std::time::Instant,
+    dt: f32,
+    /// See [`State::set_pre_render`].
+    pre_render: Option<RenderHook<'a>>,
+    /// See [`State::set_post_render`].
+    post_render: Option<RenderHook<'a>>,
+    /// Whether every frame requests the next one. When this is off, frames are only drawn when
+    /// something calls `window().request_redraw()` (or the platform asks for one).
+    continuous_redraw: bool,
+    /// The last frame, kept between redraws when `continuous_redraw` is off so that only what
+    /// was marked dirty has to be drawn again. `None` if the surface can't be copied into.
+    canvas: Option<wgpu::Texture>,
+    /// What has changed in `canvas` since it was last drawn (see [`State::mark_dirty`]).
+    dirty: Option<ScissorRect>,
+    /// A quad covering the whole target, for clearing dirty rects to the background.
+    background: Batch,
+    /// Drawn in place of clearing to `BACKGROUND`, if set.
+    background_gradient: Option<BackgroundGradient>,
 }
 
 impl<'a> State<'a> {
-    async fn new(window: &'a Window) -> State<'a> {
+    async fn new(
+        window: &'a Window,
+        adapter_index: Option<usize>,
+        antialiasing: AntiAliasing,
+        frames_in_flight: usize,
+        alpha_mode: wgpu::CompositeAlphaMode,
+        indexed: bool,
+        linear_view: bool,
+    ) -> State<'a> {
         let size = window.inner_size();
 
+        #[cfg(not(target_arch = "wasm32"))]
+        let backends = wgpu::Backends::PRIMARY;
+        #[cfg(target_arch = "wasm32")]
+        let backends = wgpu::Backends::GL;
+
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
-            #[cfg(not(target_arch = "wasm32"))]
-            backends: wgpu::Backends::PRIMARY,
-            #[cfg(target_arch = "wasm32")]
-            backends: wgpu::Backends::GL,
+            backends,
             ..Default::default()
         });
 
         let surface = instance.create_surface(window).unwrap();
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
+        let adapter = match adapter_index {
+            #[cfg(not(target_arch = "wasm32"))]
+            Some(index) => select_adapter(&instance, backends, index, Some(&surface))
+                .unwrap_or_else(|| panic!("adapter {index} is unavailable for this surface")),
+            _ => instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::default(),
+                    compatible_surface: Some(&surface),
+                    force_fallback_adapter: false,
+                })
+                .await
+                .unwrap(),
+        };
+
+        let required_features = wgpu::Features::empty();
+        let mut required_limits = if cfg!(target_arch = "wasm32") {
+            wgpu::Limits::downlevel_webgl2_defaults()
+        } else {
+            wgpu::Limits::default()
+        };
+        if !unsupported_limits(&adapter, &required_limits).is_empty() {
+            // Degrade to what any downlevel device can do rather than failing `request_device`.
+            required_limits = wgpu::Limits::downlevel_webgl2_defaults()
+                .using_resolution(adapter.limits());
+        }
 
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: None,
-                    required_features: wgpu::Features::empty(),
-                    required_limits: if cfg!(target_arch = "wasm32") {
-                        wgpu::Limits::downlevel_webgl2_defaults()
-                    } else {
-                        wgpu::Limits::default()
-                    },
+                    required_features,
+                    required_limits,
                     memory_hints: Default::default(),
                 },
                 // Some(&std::path::Path::new("trace")), // Trace path
@@ -120,81 +407,314 @@ impl<'a> State<'a> {
             .await
             .unwrap();
 
-        let surface_caps = surface.get_capabilities(&adapter);
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_caps.formats[0]);
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
-            width: size.width,
-            height: size.height,
-            present_mode: surface_caps.present_modes[0],
-            alpha_mode: surface_caps.alpha_modes[0],
-            desired_maximum_frame_latency: 2,
-            view_formats: vec![],
-        };
+        let mut surface = WindowSurface::new(surface, window, &adapter, &device, alpha_mode);
+        if surface.alpha_mode() != alpha_mode {
+            println!(
+                "WARNING: {alpha_mode:?} alpha is unsupported, using {:?}",
+                surface.alpha_mode(),
+            );
+        }
+        if linear_view {
+            let linear = surface.format().remove_srgb_suffix();
+            if let Err(error) = surface.set_view_formats(&adapter, &device, vec![linear]) {
+                println!("WARNING: {error}, so the surface can't be viewed as {linear:?}");
+            }
+        }
+        let antialiaser = AntiAliaser::new(
+            &adapter,
+            &device,
+            surface.format(),
+            [size.width, size.height],
+            antialiasing,
+        );
+        if antialiaser.mode() != antialiasing {
+            println!("WARNING: {antialiasing:?} is unsupported, using {:?}", antialiaser.mode());
+        }
 
-        let shader = Shader::new(&device, ShaderDescriptor {
-            source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(
-                include_str!("shader.wgsl"),
-            )),
-            label: Some("shader.wgsl"),
-            pipeline_label: Some("Render Pipeline"),
-            pipeline_layout_label: Some("Render Pipeline Layout"),
-            vertex_entry_point: Some("vs_main"),
-            vertex_buffers: &[Vertex::desc()],
-            fragment_entry_point: Some("fs_main"),
-            fragment_targets: &[Some(wgpu::ColorTargetState {
-                format: config.format,
-                blend: Some(wgpu::BlendState::REPLACE),
-                write_mask: wgpu::ColorWrites::ALL,
-            })],
-            ..Default::default()
-        }).unwrap();
+        let shader = Shader::solid(
+            &device,
+            surface.format(),
+            antialiaser.sample_count(),
+        ).unwrap();
+
+        let gradient_shader = Shader::radial_gradient(
+            &device,
+            surface.format(),
+            antialiaser.sample_count(),
+        ).unwrap();
+
+        let circle_shader = Shader::circle(
+            &device,
+            surface.format(),
+            antialiaser.sample_count(),
+        ).unwrap();
+        let feather = EdgeFeather::new(&device, [size.width, size.height], 1.5);
 
         let mut renderer = Renderer::start();
+        renderer.set_indexed(indexed);
+        renderer.add_radial_gradient([0.0, 0.0], 1.5, [0.3, 0.15, 0.45], [0.2, 0.1, 0.3]);
+        renderer.set_pick_id(1);
         renderer.add_quad(&Quad::new([0.1, 0.2], [0.5, 0.3]), [0.5, 0.3, 0.7]);
-        let (vertex_buffer, index_buffer, num_indices) = renderer.finish(&device);
+        renderer.set_pick_id(2);
+        renderer.add_rounded_quad(
+            &RoundedQuad::with_radii([-0.6, -0.5], [0.5, 0.3], [0.1, 0.1, 0.0, 0.0]),
+            [0.3, 0.6, 0.5],
+        );
+        renderer.set_pick_id(0);
+        renderer.add_circle_outline([0.6, -0.5], 0.2, 0.03, [0.9, 0.7, 0.3]);
+        renderer.set_pick_id(3);
+        renderer.add_text([-0.9, -0.8], "Hello, bog!", 0.1, [0.9, 0.9, 0.9]);
+        let stats = renderer.stats();
+        let batch = renderer.finish(&device);
+        let picker = Picker::new(&device, [size.width, size.height]);
+        let scale_factor = window.scale_factor();
+        let hud_layout = layout_hud(size, scale_factor, &stats, indexed);
+        let hud = hud_layout.clone().finish(&device);
+        let particles =
+            ParticleSystem::new(&device, surface.format(), 1, &initial_particles(256)).ok();
+        let canvas = create_canvas(&device, &surface, size);
+        let mut background = Renderer::start();
+        background.add_quad(
+            &Quad::new([-1.0, -1.0], [2.0, 2.0]),
+            [BACKGROUND.r, BACKGROUND.g, BACKGROUND.b],
+        );
+        let background = background.finish(&device);
 
         Self {
+            instance,
             surface,
+            adapter,
             device,
             queue,
-            config,
             size,
-            window,
+            scale_factor,
             shader,
-            vertex_buffer,
-            index_buffer,
-            num_indices,
+            gradient_shader,
+            circle_shader,
+            feather,
+            batch,
+            stats,
+            hud_layout,
+            hud,
+            picker,
+            antialiaser,
+            particles,
+            frame_limiter: FrameLimiter::new(frames_in_flight),
+            frame_rate_cap: FrameRateCap::new(None),
+            indexed,
+            last_update: std::time::Instant::now(),
+            dt: 0.0,
+            continuous_redraw: true,
+            pre_render: None,
+            post_render: None,
+            canvas,
+            dirty: Some(ScissorRect::new(0, 0, size.width, size.height)),
+            background,
+            background_gradient: None,
         }
     }
 
-    fn window(&self) -> &Window {
-        &self.window
+    fn window(&self) -> &'a Window {
+        self.surface.window()
+    }
+
+    /// Drop the surface and create a new one for `window`.
+    ///
+    /// Some platforms (Android in particular) destroy the surface when the app is suspended, so
+    /// this has to be called once it's resumed.
+    pub fn recreate_surface(&mut self, window: &'a Window) {
+        let surface = self.instance.create_surface(window).unwrap();
+        self.surface.recreate(surface, window, &self.device);
+        self.resize(window.inner_size());
+    }
+
+    #[allow(unused)]
+    fn set_title(&self, title: &str) {
+        self.window().set_title(title);
+    }
+
+    #[allow(unused)]
+    fn set_window_icon(&self, icon: Option<winit::window::Icon>) {
+        self.window().set_window_icon(icon);
+    }
+
+    fn continuous_redraw(&self) -> bool {
+        self.continuous_redraw
+    }
+
+    /// Turn off continuous rendering to schedule redraws yourself, e.g. only in response to
+    /// input. On by default.
+    #[allow(unused)]
+    fn set_continuous_redraw(&mut self, continuous: bool) {
+        self.continuous_redraw = continuous;
+        if continuous {
+            self.window().request_redraw();
+        } else {
+            // NOTE: Nothing was kept while drawing continuously.
+            self.mark_all_dirty();
+        }
+    }
+
+    /// Redraw `rect` (in physical pixels) next frame. Without continuous redraws, the rest of
+    /// the previous frame is kept, and frames without anything dirty aren't redrawn at all, just
+    /// presented again.
+    ///
+    /// NOTE: Hooks, particles, and the HUD aren't tracked, so whatever changes them has to mark
+    ///       them dirty.
+    #[allow(unused)]
+    fn mark_dirty(&mut self, rect: ScissorRect) {
+        self.dirty = Some(match self.dirty {
+            Some(dirty) => dirty.union(&rect),
+            None => rect,
+        });
+        self.window().request_redraw();
+    }
+
+    fn mark_all_dirty(&mut self) {
+        self.mark_dirty(ScissorRect::new(0, 0, self.size.width, self.size.height));
+    }
+
+    /// Draw at most `max_fps` frames per second (or as many as possible with `None`, the
+    /// default). This does nothing while the surface is vsynced, since that caps it already.
+    fn set_max_fps(&mut self, max_fps: Option<f32>) {
+        self.frame_rate_cap.set_max_fps(max_fps);
+    }
+
+    /// Fill the background with a vertical gradient from `top` to `bottom` (in its own pass,
+    /// before the pre-render hook) instead of clearing it to a solid color.
+    #[allow(unused)]
+    fn set_background_gradient(&mut self, top: Color, bottom: Color) {
+        match &mut self.background_gradient {
+            Some(gradient) => gradient.set_colors(&self.queue, top, bottom),
+            None => {
+                self.background_gradient = Some(BackgroundGradient::new(
+                    &self.device,
+                    self.surface.format(),
+                    self.antialiaser.sample_count(),
+                    top,
+                    bottom,
+                ).unwrap());
+            }
+        }
+        self.mark_all_dirty();
+    }
+
+    /// Draw with `hook` every frame, after the scene is cleared but before the batch is drawn
+    /// over it, in a pass of its own.
+    ///
+    /// The target is the scene's, so pipelines must be made for the surface's format and the
+    /// anti-aliaser's sample count. Its contents must be kept (`LoadOp::Load`).
+    #[allow(unused)]
+    fn set_pre_render(
+        &mut self,
+        hook: impl FnMut(&mut wgpu::CommandEncoder, &RenderTarget) + 'a,
+    ) {
+        self.pre_render = Some(Box::new(hook));
+    }
+
+    /// Draw with `hook` every frame, over the finished (anti-aliased) frame and particles, but
+    /// before it's submitted. Good for overlays like profilers.
+    ///
+    /// The target is the surface itself, so pipelines must be single-sampled. Its contents must
+    /// be kept (`LoadOp::Load`).
+    #[allow(unused)]
+    fn set_post_render(
+        &mut self,
+        hook: impl FnMut(&mut wgpu::CommandEncoder, &RenderTarget) + 'a,
+    ) {
+        self.post_render = Some(Box::new(hook));
+    }
+
+    #[allow(unused)]
+    fn adapter(&self) -> &wgpu::Adapter {
+        &self.adapter
     }
 
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
             self.size = new_size;
-            self.config.width = new_size.width;
-            self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
+            self.surface.resize(&self.device, new_size);
+            self.picker.resize(&self.device, [new_size.width, new_size.height]);
+            self.antialiaser.resize(&self.device, [new_size.width, new_size.height]);
+            self.feather.set_viewport(&self.queue, [new_size.width, new_size.height]);
+            self.hud_layout.set_viewport(new_size.width, new_size.height);
+            self.hud = self.hud_layout.clone().finish(&self.device);
+            self.canvas = create_canvas(&self.device, &self.surface, new_size);
+            self.mark_all_dirty();
         }
     }
 
+    /// A summary of the GPU memory allocated so far, or `None` if the backend can't report it
+    /// (only Vulkan and DX12 can).
+    #[allow(unused)]
+    fn memory_report(&self) -> Option<MemoryReport> {
+        MemoryReport::generate(&self.device)
+    }
+
+    #[allow(unused)]
+    fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// The size of the surface, in the physical pixels that targets and scissor rects use.
+    #[allow(unused)]
+    fn physical_size(&self) -> winit::dpi::PhysicalSize<u32> {
+        self.size
+    }
+
+    /// The size of the surface, in the logical pixels that layout (like the HUD) uses.
+    #[allow(unused)]
+    fn logical_size(&self) -> winit::dpi::LogicalSize<f32> {
+        self.size.to_logical(self.scale_factor)
+    }
+
+    /// Convert a physical position (like the cursor's) into logical pixels, with the window's
+    /// current scale factor.
+    #[allow(unused)]
+    fn to_logical(
+        &self,
+        position: winit::dpi::PhysicalPosition<f64>,
+    ) -> winit::dpi::LogicalPosition<f32> {
+        position.to_logical(self.scale_factor)
+    }
+
+    /// Convert a logical position into physical pixels, with the window's current scale factor.
+    #[allow(unused)]
+    fn to_physical(
+        &self,
+        position: winit::dpi::LogicalPosition<f32>,
+    ) -> winit::dpi::PhysicalPosition<f32> {
+        position.to_physical(self.scale_factor)
+    }
+
+    /// Called when the window moves to a display with a different scale factor. The window's
+    /// physical size changes along with it, so every size-dependent resource is rebuilt.
+    pub fn scale_factor_changed(&mut self, scale_factor: f64) {
+        self.scale_factor = scale_factor;
+        self.hud_layout = layout_hud(self.size, scale_factor, &self.stats, self.indexed);
+        self.resize(self.window().inner_size());
+    }
+
+    /// The pick id of the primitive under the given physical pixel, if any.
+    fn pick(&self, x: u32, y: u32) -> Option<u32> {
+        self.picker.pick(&self.device, &self.queue, &[&self.batch.solid], x, y)
+    }
+
     #[allow(unused)]
     fn input(&mut self, event: &WindowEvent) -> bool {
         false
     }
 
-    fn update(&mut self) {}
+    fn update(&mut self) {
+        let now = std::time::Instant::now();
+        self.dt = (now - self.last_update).as_secs_f32();
+        self.last_update = now;
+    }
 
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        if !self.surface.is_vsynced() {
+            self.frame_rate_cap.wait();
+        }
         let output = self.surface.get_current_texture().unwrap();
         let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
@@ -204,36 +724,230 @@ impl<'a> State<'a> {
             },
         );
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.2,
-                            g: 0.1,
-                            b: 0.3,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
+        let full = ScissorRect::new(0, 0, self.size.width, self.size.height);
+        // NOTE: Without continuous redraws, frames are kept in the canvas, so only the dirty
+        //       rect has to be drawn again (and nothing at all when nothing changed).
+        let canvas = self.canvas.as_ref().filter(|_| !self.continuous_redraw);
+        let damage = match canvas {
+            Some(_) => self.dirty.take(),
+            None => {
+                self.dirty = None;
+                Some(full)
+            }
+        };
+        let scissor = damage.filter(|damage| *damage != full);
+
+        let feather_bind_groups = [self.feather.bind_group()];
+        let mut clear_commands = Vec::new();
+        let mut commands = Vec::new();
+        let gradient = self.background_gradient.as_ref();
+        if scissor.is_some() && gradient.is_none() {
+            clear_commands.push(DrawCommand {
+                shader: &self.shader,
+                mesh: &self.background.solid,
+                bind_groups: &[],
+                instances: None,
+                scissor,
+            });
+        }
+        if let Some(gradients) = &self.batch.radial_gradients {
+            commands.push(DrawCommand {
+                shader: &self.gradient_shader,
+                mesh: gradients,
+                bind_groups: &[],
+                instances: None,
+                scissor,
+            });
+        }
+        commands.push(DrawCommand {
+            shader: &self.shader,
+            mesh: &self.batch.solid,
+            bind_groups: &[],
+            instances: None,
+            scissor,
+        });
+        if let Some(circles) = &self.batch.circles {
+            commands.push(DrawCommand {
+                shader: &self.circle_shader,
+                mesh: circles,
+                bind_groups: &feather_bind_groups,
+                instances: None,
+                scissor,
             });
+        }
+        commands.push(DrawCommand {
+            shader: &self.shader,
+            mesh: &self.hud.solid,
+            bind_groups: &[],
+            instances: None,
+            scissor,
+        });
 
-            render_pass.set_pipeline(&self.shader.pipeline);
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-            render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+        let size = [self.size.width, self.size.height];
+        let target = RenderTarget::new(view).with_size(size);
+        let canvas_target = canvas.map(RenderTarget::from_texture);
+        let frame = canvas_target.as_ref().unwrap_or(&target);
+        let scene = self.antialiaser.target(&frame.view);
+        let mut graph = FrameGraph::new();
+        graph.add_target("surface", &target);
+        graph.add_target("frame", frame);
+        graph.add_target("scene", &scene);
+        // NOTE: Dirty rects are cleared by drawing the background over them, since load ops
+        //       always clear the whole target. A background gradient replaces the clear.
+        let scene_clear = (scissor.is_none() && gradient.is_none()).then_some(BACKGROUND.into());
+        if damage.is_some() {
+            graph.add_pass("background", &[], move |encoder, targets| {
+                if let Some(gradient) = gradient {
+                    gradient.render(encoder, targets.get("scene").unwrap(), scissor).unwrap();
+                }
+            });
+            // NOTE: The pre-render hook draws between the clear and the batch, so the clear is
+            //       only split off into its own pass when there is one.
+            let scene_clear = match &mut self.pre_render {
+                Some(pre_render) => {
+                    let clear_commands = &clear_commands;
+                    graph.add_pass("pre_render", &["background"], move |encoder, targets| {
+                        let scene = targets.get("scene").unwrap();
+                        render_to(encoder, std::slice::from_ref(scene), scene_clear, clear_commands)
+                            .unwrap();
+                        pre_render(encoder, scene);
+                    });
+                    None
+                }
+                None => {
+                    commands.splice(0..0, clear_commands.drain(..));
+                    graph.add_pass("pre_render", &["background"], |_, _| {});
+                    scene_clear
+                }
+            };
+            graph.add_pass("scene", &["pre_render"], move |encoder, targets| {
+                render_to(
+                    encoder,
+                    std::slice::from_ref(targets.get("scene").unwrap()),
+                    scene_clear,
+                    &commands,
+                ).unwrap();
+            });
+            let antialiaser = &self.antialiaser;
+            graph.add_pass("antialias", &["scene"], |encoder, targets| {
+                antialiaser.resolve(encoder, &targets.get("frame").unwrap().view);
+            });
+        }
+        let composite_after: &[&str] = if damage.is_some() { &["antialias"] } else { &[] };
+        let surface_texture = &output.texture;
+        graph.add_pass("composite", composite_after, move |encoder, _| {
+            if let Some(canvas) = canvas {
+                encoder.copy_texture_to_texture(
+                    canvas.as_image_copy(),
+                    surface_texture.as_image_copy(),
+                    canvas.size(),
+                );
+            }
+        });
+        if let Some(particles) = &self.particles {
+            let (queue, dt) = (&self.queue, self.dt);
+            graph.add_pass("update_particles", &[], move |encoder, _| {
+                particles.update(queue, encoder, dt);
+            });
+            // NOTE: Particles are drawn over the finished frame, so they have to come after it.
+            //       They're too small to need anti-aliasing.
+            graph.add_pass("particles", &["update_particles", "composite"], |encoder, targets| {
+                particles.render(encoder, targets.get("surface").unwrap());
+            });
         }
+        if let Some(post_render) = &mut self.post_render {
+            let after: &[&str] = if self.particles.is_some() {
+                &["composite", "particles"]
+            } else {
+                &["composite"]
+            };
+            graph.add_pass("post_render", after, |encoder, targets| {
+                post_render(encoder, targets.get("surface").unwrap());
+            });
+        }
+        graph.execute(&mut encoder).unwrap();
 
-        self.queue.submit(std::iter::once(encoder.finish()));
+        self.frame_limiter.submit(&self.device, &self.queue, std::iter::once(encoder.finish()));
         output.present();
 
         Ok(())
     }
 }
+
+
+
+/// A texture to keep frames in between redraws, if the surface can be copied into.
+fn create_canvas(
+    device: &wgpu::Device,
+    surface: &WindowSurface,
+    size: winit::dpi::PhysicalSize<u32>,
+) -> Option<wgpu::Texture> {
+    surface.can_copy_into().then(|| device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Canvas"),
+        size: wgpu::Extent3d {
+            width: size.width.max(1),
+            height: size.height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: surface.format(),
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    }))
+}
+
+/// Lay the HUD out in the physical pixels of a surface of `size`, from logical positions and
+/// sizes scaled by `scale_factor`.
+fn layout_hud(
+    size: winit::dpi::PhysicalSize<u32>,
+    scale_factor: f64,
+    stats: &RenderStats,
+    indexed: bool,
+) -> Renderer {
+    let scale = scale_factor as f32;
+    let mut renderer = Renderer::with_space(CoordinateSpace::Pixels {
+        width: size.width as f32,
+        height: size.height as f32,
+    });
+    renderer.set_pixel_snap(Some(1.0));
+    renderer.set_indexed(indexed);
+    renderer.add_text(
+        [16.0 * scale, 16.0 * scale],
+        &format!("Scale: {scale_factor}x"),
+        20.0 * scale,
+        [0.9, 0.9, 0.9],
+    );
+    renderer.add_text(
+        [16.0 * scale, 44.0 * scale],
+        &format!(
+            "{} quads, {} draw calls, {} bytes",
+            stats.quads,
+            stats.draw_calls,
+            stats.buffer_bytes,
+        ),
+        20.0 * scale,
+        [0.9, 0.9, 0.9],
+    );
+
+    renderer
+}
+
+fn initial_particles(count: u32) -> Vec<Particle> {
+    // NOTE: A tiny LCG is plenty for scattering particles around.
+    let mut seed = 0x2545_f491u32;
+    let mut next = move || {
+        seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (seed >> 8) as f32 / (1 << 24) as f32
+    };
+
+    (0..count)
+        .map(|_| Particle {
+            pos: [next() * 2.0 - 1.0, next() * 2.0 - 1.0],
+            vel: [next() - 0.5, next() - 0.5],
+            color: [next(), next(), next()],
+            size: 0.01,
+        })
+        .collect()
+}